@@ -20,8 +20,9 @@ fn main() {
         let glyph_mapping = font.glyph_mapping_for_codepoint_ranges(&codepoint_ranges).unwrap();
         for (glyph_index, (_, glyph_id)) in glyph_mapping.iter().enumerate() {
             let codepoint = '!' as u32 + glyph_index as u32;
-            println!("Glyph {}: codepoint {} '{}':",
+            println!("Glyph {} ({}): codepoint {} '{}':",
                      glyph_id,
+                     font.glyph_name(glyph_id).unwrap_or("unnamed"),
                      codepoint,
                      char::from_u32(codepoint).unwrap_or('?'));
 
@@ -37,7 +37,7 @@ layout(std140) uniform ubImageDescriptors {
 };
 
 // The position of each vertex in glyph space.
-in ivec2 aPosition;
+in vec2 aPosition;
 
 // Which glyph the vertex belongs to.
 in uint aGlyphIndex;
@@ -51,7 +51,8 @@ void main() {
     vec4 image = uImages[aGlyphIndex];
     GlyphDescriptor glyph = uGlyphs[aGlyphIndex];
 
-    vec2 glyphPos = vec2(aPosition.x - glyph.extents.x, glyph.extents.w - aPosition.y);
+    vec2 glyphPos = vec2(aPosition.x - float(glyph.extents.x),
+                         float(glyph.extents.w) - aPosition.y);
     float pointSize = IMAGE_DESCRIPTOR_POINT_SIZE(image);
     vec2 glyphPxPos = glyphPos * pointSize / GLYPH_DESCRIPTOR_UNITS_PER_EM(glyph);
     vec2 atlasPos = glyphPxPos + IMAGE_DESCRIPTOR_ATLAS_POS(image);
@@ -0,0 +1,188 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bare Compact Font Format (`.cff`) files, such as the fonts embedded in PDFs.
+//!
+//! These are the contents of a `CFF ` table on its own, without any of the other tables of an
+//! OpenType font.
+//!
+//! See Adobe's spec: http://wwwimages.adobe.com/content/dam/Adobe/en/devnet/font/pdfs/5176.CFF.pdf
+
+use byteorder::{BigEndian, WriteBytesExt};
+use containers::otf::{KNOWN_TABLES, KNOWN_TABLE_COUNT};
+use error::FontError;
+use font::{Font, FontTable};
+use outline::GlyphBounds;
+use std::cmp;
+use std::i16;
+use tables::cff::{self, CffTable};
+use tables::head::{self, HeadTable};
+use tables::{cmap, hhea, hmtx, maxp};
+
+/// The first three bytes of a bare CFF file: version 1.0 and a four-byte header. The fourth byte,
+/// which is the offset size, varies.
+pub const MAGIC_NUMBER_PREFIX: u32 = 0x010004;
+
+// The FontMatrix of almost every CFF font maps 1000 units to the em.
+const UNITS_PER_EM: u16 = 1000;
+
+const HEAD_MAGIC_NUMBER: u32 = 0x5f0f3cf5;
+
+impl<'a> Font<'a> {
+    /// Creates a new font from a single font within the FontSet of a bare CFF file.
+    ///
+    /// Bare CFF files lack the tables that OpenType fonts must have, so they're synthesized into
+    /// the given buffer, with metrics taken from the CharStrings. There's no character map, so
+    /// look up glyphs with `glyph_id_for_cff_code()` or `glyph_id_for_name()` instead.
+    pub fn from_cff_index<'b>(bytes: &'b [u8], index: u16, buffer: &'b mut Vec<u8>)
+                              -> Result<Font<'b>, FontError> {
+        let cff_table = FontTable {
+            bytes: bytes,
+        };
+        let cff = try!(CffTable::from_font_set_index(cff_table, index));
+        let glyph_count = cff.glyph_count();
+
+        // The FontMatrix scales the outlines to these units.
+        let head_table = HeadTable {
+            units_per_em: UNITS_PER_EM,
+            index_to_loc_format: 0,
+            max_glyph_bounds: GlyphBounds::default(),
+        };
+
+        // Measure the glyphs.
+        //
+        // Writing to a `Vec` can't fail, so the `unwrap()`s below are safe.
+        let mut hmtx = vec![];
+        let mut font_bounds: Option<GlyphBounds> = None;
+        let mut advance_width_max = 0;
+        let (mut min_lsb, mut min_rsb, mut x_max_extent) = (i16::MAX, i16::MAX, i16::MIN);
+        for glyph_id in 0..glyph_count {
+            let advance_width = try!(cff.advance_width(&head_table, glyph_id));
+            let advance_width = cmp::max(advance_width.round() as i32, 0) as u16;
+            advance_width_max = cmp::max(advance_width_max, advance_width);
+
+            let mut glyph_bounds: Option<GlyphBounds> = None;
+            try!(cff.for_each_point(&head_table, glyph_id, |point| {
+                glyph_bounds = Some(cff::add_point_to_bounds(glyph_bounds, &point.position))
+            }));
+
+            let lsb = match glyph_bounds {
+                None => 0,
+                Some(bounds) => {
+                    let lsb = bounds.left as i16;
+                    min_lsb = cmp::min(min_lsb, lsb);
+                    min_rsb = cmp::min(min_rsb, (advance_width as i32 - bounds.right) as i16);
+                    x_max_extent = cmp::max(x_max_extent, bounds.right as i16);
+
+                    font_bounds = Some(match font_bounds {
+                        None => bounds,
+                        Some(font_bounds) => {
+                            GlyphBounds {
+                                left: cmp::min(font_bounds.left, bounds.left),
+                                bottom: cmp::min(font_bounds.bottom, bounds.bottom),
+                                right: cmp::max(font_bounds.right, bounds.right),
+                                top: cmp::max(font_bounds.top, bounds.top),
+                            }
+                        }
+                    });
+                    lsb
+                }
+            };
+
+            hmtx.write_u16::<BigEndian>(advance_width).unwrap();
+            hmtx.write_i16::<BigEndian>(lsb).unwrap();
+        }
+        if font_bounds.is_none() {
+            min_lsb = 0;
+            min_rsb = 0;
+            x_max_extent = 0;
+        }
+        let font_bounds = font_bounds.unwrap_or_default();
+
+        let mut head = vec![];
+        head.write_u16::<BigEndian>(1).unwrap();                    // majorVersion
+        head.write_u16::<BigEndian>(0).unwrap();                    // minorVersion
+        head.write_u32::<BigEndian>(0x10000).unwrap();              // fontRevision
+        head.write_u32::<BigEndian>(0).unwrap();                    // checkSumAdjustment
+        head.write_u32::<BigEndian>(HEAD_MAGIC_NUMBER).unwrap();
+        head.write_u16::<BigEndian>(0b11).unwrap();                 // flags: baseline and lsb at 0
+        head.write_u16::<BigEndian>(UNITS_PER_EM).unwrap();
+        head.write_u64::<BigEndian>(0).unwrap();                    // created
+        head.write_u64::<BigEndian>(0).unwrap();                    // modified
+        head.write_i16::<BigEndian>(font_bounds.left as i16).unwrap();
+        head.write_i16::<BigEndian>(font_bounds.bottom as i16).unwrap();
+        head.write_i16::<BigEndian>(font_bounds.right as i16).unwrap();
+        head.write_i16::<BigEndian>(font_bounds.top as i16).unwrap();
+        head.write_u16::<BigEndian>(0).unwrap();                    // macStyle
+        head.write_u16::<BigEndian>(0).unwrap();                    // lowestRecPPEM
+        head.write_i16::<BigEndian>(2).unwrap();                    // fontDirectionHint
+        head.write_i16::<BigEndian>(0).unwrap();                    // indexToLocFormat
+        head.write_i16::<BigEndian>(0).unwrap();                    // glyphDataFormat
+
+        // There are no line metrics to go by, so use the extent of the glyphs.
+        let mut hhea = vec![];
+        hhea.write_u16::<BigEndian>(1).unwrap();                    // majorVersion
+        hhea.write_u16::<BigEndian>(0).unwrap();                    // minorVersion
+        hhea.write_i16::<BigEndian>(font_bounds.top as i16).unwrap();
+        hhea.write_i16::<BigEndian>(font_bounds.bottom as i16).unwrap();
+        hhea.write_i16::<BigEndian>(0).unwrap();                    // lineGap
+        hhea.write_u16::<BigEndian>(advance_width_max).unwrap();
+        hhea.write_i16::<BigEndian>(min_lsb).unwrap();
+        hhea.write_i16::<BigEndian>(min_rsb).unwrap();
+        hhea.write_i16::<BigEndian>(x_max_extent).unwrap();
+        hhea.write_i16::<BigEndian>(1).unwrap();                    // caretSlopeRise
+        hhea.write_i16::<BigEndian>(0).unwrap();                    // caretSlopeRun
+        hhea.write_i16::<BigEndian>(0).unwrap();                    // caretOffset
+        for _ in 0..5 {
+            hhea.write_i16::<BigEndian>(0).unwrap();                // reserved, metricDataFormat
+        }
+        hhea.write_u16::<BigEndian>(glyph_count).unwrap();          // numberOfHMetrics
+
+        let mut maxp = vec![];
+        maxp.write_u32::<BigEndian>(0x5000).unwrap();               // version 0.5
+        maxp.write_u16::<BigEndian>(glyph_count).unwrap();
+
+        // A character map with no subtables.
+        let mut cmap = vec![];
+        cmap.write_u16::<BigEndian>(0).unwrap();                    // version
+        cmap.write_u16::<BigEndian>(0).unwrap();                    // numTables
+
+        // Copy the synthesized tables into the buffer and point at them.
+        let synthesized_tables = [
+            (cmap::TAG, cmap),
+            (head::TAG, head),
+            (hhea::TAG, hhea),
+            (hmtx::TAG, hmtx),
+            (maxp::TAG, maxp),
+        ];
+        let buffer_start = buffer.len();
+        for &(_, ref data) in &synthesized_tables {
+            buffer.extend_from_slice(data)
+        }
+        let mut buffer = &buffer[buffer_start..];
+
+        let mut tables = [None; KNOWN_TABLE_COUNT];
+        debug_assert!(KNOWN_TABLES.windows(2).all(|w| w[0] < w[1]));
+        if let Ok(table_index) = KNOWN_TABLES.binary_search(&cff::TAG) {
+            tables[table_index] = Some(cff_table)
+        }
+        for &(tag, ref data) in &synthesized_tables {
+            let (table, rest) = buffer.split_at(data.len());
+            buffer = rest;
+            if let Ok(table_index) = KNOWN_TABLES.binary_search(&tag) {
+                tables[table_index] = Some(FontTable {
+                    bytes: table,
+                })
+            }
+        }
+
+        Font::from_table_list_with_cff_index(bytes, &tables, index)
+    }
+}
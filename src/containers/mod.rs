@@ -10,6 +10,7 @@
 
 //! Various kinds of files that can contain OpenType fonts.
 
+pub mod cff;
 pub mod dfont;
 pub mod otf;
 pub mod ttc;
@@ -12,19 +12,31 @@
 //!
 //! See Microsoft's spec: https://www.microsoft.com/typography/otspec/otff.htm
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use error::FontError;
 use font::{Font, FontTable};
 use std::mem;
+use tables::avar::{self, AvarTable};
 use tables::cff::{self, CffTable};
+use tables::cff2::{self, Cff2Table};
 use tables::cmap::{self, CmapTable};
+use tables::fvar::{self, FvarTable};
 use tables::glyf::{self, GlyfTable};
+use tables::gdef::{self, GdefTable};
+use tables::gpos::{self, GposTable};
+use tables::gsub::{self, GsubTable};
+use tables::gvar::{self, GvarTable};
 use tables::head::{self, HeadTable};
 use tables::hhea::{self, HheaTable};
 use tables::hmtx::{self, HmtxTable};
+use tables::hvar::{self, HvarTable};
 use tables::kern::{self, KernTable};
 use tables::loca::{self, LocaTable};
+use tables::maxp::{self, MaxpTable};
+use tables::mvar::{self, MvarTable};
+use tables::name::{self, NameTable};
 use tables::os_2::{self, Os2Table};
+use tables::post::{self, PostTable};
 use util::Jump;
 
 const OTTO: u32 = ((b'O' as u32) << 24) |
@@ -32,30 +44,60 @@ const OTTO: u32 = ((b'O' as u32) << 24) |
                   ((b'T' as u32) << 8)  |
                    (b'O' as u32);
 
-pub const KNOWN_TABLE_COUNT: usize = 9;
+pub const KNOWN_TABLE_COUNT: usize = 21;
 
 pub static KNOWN_TABLES: [u32; KNOWN_TABLE_COUNT] = [
     cff::TAG,
+    cff2::TAG,
+    gdef::TAG,
+    gpos::TAG,
+    gsub::TAG,
+    hvar::TAG,
+    mvar::TAG,
     os_2::TAG,
+    avar::TAG,
     cmap::TAG,
+    fvar::TAG,
     glyf::TAG,
+    gvar::TAG,
     head::TAG,
     hhea::TAG,
     hmtx::TAG,
     kern::TAG,
     loca::TAG,
+    maxp::TAG,
+    name::TAG,
+    post::TAG,
 ];
 
 // This must agree with the above.
 const TABLE_INDEX_CFF:  usize = 0;
-const TABLE_INDEX_OS_2: usize = 1;
-const TABLE_INDEX_CMAP: usize = 2;
-const TABLE_INDEX_GLYF: usize = 3;
-const TABLE_INDEX_HEAD: usize = 4;
-const TABLE_INDEX_HHEA: usize = 5;
-const TABLE_INDEX_HMTX: usize = 6;
-const TABLE_INDEX_KERN: usize = 7;
-const TABLE_INDEX_LOCA: usize = 8;
+const TABLE_INDEX_CFF2: usize = 1;
+const TABLE_INDEX_GDEF: usize = 2;
+const TABLE_INDEX_GPOS: usize = 3;
+const TABLE_INDEX_GSUB: usize = 4;
+const TABLE_INDEX_HVAR: usize = 5;
+const TABLE_INDEX_MVAR: usize = 6;
+const TABLE_INDEX_OS_2: usize = 7;
+const TABLE_INDEX_AVAR: usize = 8;
+const TABLE_INDEX_CMAP: usize = 9;
+const TABLE_INDEX_FVAR: usize = 10;
+const TABLE_INDEX_GLYF: usize = 11;
+const TABLE_INDEX_GVAR: usize = 12;
+const TABLE_INDEX_HEAD: usize = 13;
+const TABLE_INDEX_HHEA: usize = 14;
+const TABLE_INDEX_HMTX: usize = 15;
+const TABLE_INDEX_KERN: usize = 16;
+const TABLE_INDEX_LOCA: usize = 17;
+const TABLE_INDEX_MAXP: usize = 18;
+const TABLE_INDEX_NAME: usize = 19;
+const TABLE_INDEX_POST: usize = 20;
+
+// The value that the checksum of a whole font file must come out to.
+const CHECKSUM_MAGIC: u32 = 0xb1b0afba;
+
+// The offset of `checkSumAdjustment` in the `head` table.
+const HEAD_CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
 
 pub static SFNT_VERSIONS: [u32; 3] = [
     0x10000,
@@ -69,12 +111,27 @@ pub struct FontTables<'a> {
     pub head: HeadTable,
     pub hhea: HheaTable,
     pub hmtx: HmtxTable<'a>,
-    pub os_2: Os2Table,
 
+    pub avar: Option<AvarTable<'a>>,
     pub cff: Option<CffTable<'a>>,
+    pub cff2: Option<Cff2Table<'a>>,
+    pub fvar: Option<FvarTable<'a>>,
+    pub gdef: Option<GdefTable<'a>>,
     pub glyf: Option<GlyfTable<'a>>,
+    pub gpos: Option<GposTable<'a>>,
+    pub gsub: Option<GsubTable<'a>>,
+    pub gvar: Option<GvarTable<'a>>,
+    pub hvar: Option<HvarTable<'a>>,
     pub loca: Option<LocaTable<'a>>,
     pub kern: Option<KernTable<'a>>,
+    pub maxp: Option<MaxpTable>,
+    pub mvar: Option<MvarTable<'a>>,
+    pub name: Option<NameTable<'a>>,
+    pub os_2: Option<Os2Table>,
+    pub post: Option<PostTable<'a>>,
+
+    /// The raw data of each of `KNOWN_TABLES` that the font has.
+    pub table_list: [Option<FontTable<'a>>; KNOWN_TABLE_COUNT],
 }
 
 impl<'a> Font<'a> {
@@ -121,9 +178,24 @@ impl<'a> Font<'a> {
     pub fn from_table_list<'b>(bytes: &'b [u8],
                                tables: &[Option<FontTable<'b>>; KNOWN_TABLE_COUNT])
                                -> Result<Font<'b>, FontError> {
+        Font::from_table_list_with_cff_index(bytes, tables, 0)
+    }
+
+    /// Like `from_table_list()`, but reads the font with the given index from the FontSet in the
+    /// `CFF ` table instead of the first one.
+    #[doc(hidden)]
+    pub fn from_table_list_with_cff_index<'b>(bytes: &'b [u8],
+                                              tables: &[Option<FontTable<'b>>; KNOWN_TABLE_COUNT],
+                                              cff_index: u16)
+                                              -> Result<Font<'b>, FontError> {
         let cff_table = match tables[TABLE_INDEX_CFF] {
             None => None,
-            Some(cff_table) => Some(try!(CffTable::new(cff_table))),
+            Some(cff_table) => Some(try!(CffTable::from_font_set_index(cff_table, cff_index))),
+        };
+
+        let cff2_table = match tables[TABLE_INDEX_CFF2] {
+            None => None,
+            Some(cff2_table) => Some(try!(Cff2Table::new(cff2_table))),
         };
 
         let loca_table = match tables[TABLE_INDEX_LOCA] {
@@ -139,15 +211,110 @@ impl<'a> Font<'a> {
             head: try!(HeadTable::new(try!(tables[TABLE_INDEX_HEAD].ok_or(missing)))),
             hhea: try!(HheaTable::new(try!(tables[TABLE_INDEX_HHEA].ok_or(missing)))),
             hmtx: HmtxTable::new(try!(tables[TABLE_INDEX_HMTX].ok_or(missing))),
-            os_2: try!(Os2Table::new(try!(tables[TABLE_INDEX_OS_2].ok_or(missing)))),
 
+            avar: tables[TABLE_INDEX_AVAR].and_then(|table| AvarTable::new(table).ok()),
             cff: cff_table,
+            cff2: cff2_table,
+            fvar: tables[TABLE_INDEX_FVAR].and_then(|table| FvarTable::new(table).ok()),
+            gdef: tables[TABLE_INDEX_GDEF].and_then(|table| GdefTable::new(table).ok()),
             glyf: tables[TABLE_INDEX_GLYF].map(GlyfTable::new),
+            gpos: tables[TABLE_INDEX_GPOS].and_then(|table| GposTable::new(table).ok()),
+            gsub: tables[TABLE_INDEX_GSUB].and_then(|table| GsubTable::new(table).ok()),
+            gvar: tables[TABLE_INDEX_GVAR].and_then(|table| GvarTable::new(table).ok()),
+            hvar: tables[TABLE_INDEX_HVAR].and_then(|table| HvarTable::new(table).ok()),
             loca: loca_table,
             kern: tables[TABLE_INDEX_KERN].and_then(|table| KernTable::new(table).ok()),
+            maxp: tables[TABLE_INDEX_MAXP].and_then(|table| MaxpTable::new(table).ok()),
+            mvar: tables[TABLE_INDEX_MVAR].and_then(|table| MvarTable::new(table).ok()),
+            name: tables[TABLE_INDEX_NAME].and_then(|table| NameTable::new(table).ok()),
+            os_2: tables[TABLE_INDEX_OS_2].and_then(|table| Os2Table::new(table).ok()),
+            post: tables[TABLE_INDEX_POST].and_then(|table| PostTable::new(table).ok()),
+
+            table_list: *tables,
         };
 
         Ok(Font::from_tables(bytes, tables))
     }
 }
 
+/// Writes an OpenType file with the given version and tables, which are given as pairs of tags and
+/// table data.
+///
+/// The `checkSumAdjustment` field of the `head` table, if there is one, is filled in.
+#[doc(hidden)]
+pub fn write_otf(sfnt_version: u32, tables: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    // The table directory must be sorted by tag.
+    let mut tables: Vec<_> = tables.iter().collect();
+    tables.sort_by_key(|&&(tag, _)| tag);
+
+    let num_tables = tables.len() as u16;
+    let mut entry_selector = 0;
+    while (1 << (entry_selector + 1)) <= num_tables {
+        entry_selector += 1
+    }
+    let search_range = (1 << entry_selector) * 16;
+
+    // Writing to a `Vec` can't fail, so the `unwrap()`s below are safe.
+    let mut bytes = vec![];
+    bytes.write_u32::<BigEndian>(sfnt_version).unwrap();
+    bytes.write_u16::<BigEndian>(num_tables).unwrap();
+    bytes.write_u16::<BigEndian>(search_range).unwrap();
+    bytes.write_u16::<BigEndian>(entry_selector).unwrap();
+    bytes.write_u16::<BigEndian>(num_tables * 16 - search_range).unwrap();
+
+    let mut offset = bytes.len() + tables.len() * 16;
+    for &&(tag, ref data) in &tables {
+        // The checksum of the `head` table is computed as though `checkSumAdjustment` were zero.
+        let mut table_checksum = checksum(data);
+        if tag == head::TAG {
+            if let Some(mut adjustment) = data.get(HEAD_CHECKSUM_ADJUSTMENT_OFFSET..) {
+                let adjustment = adjustment.read_u32::<BigEndian>().unwrap_or(0);
+                table_checksum = table_checksum.wrapping_sub(adjustment)
+            }
+        }
+
+        bytes.write_u32::<BigEndian>(tag).unwrap();
+        bytes.write_u32::<BigEndian>(table_checksum).unwrap();
+        bytes.write_u32::<BigEndian>(offset as u32).unwrap();
+        bytes.write_u32::<BigEndian>(data.len() as u32).unwrap();
+        offset += (data.len() + 3) & !3
+    }
+
+    let mut head_offset = None;
+    for &&(tag, ref data) in &tables {
+        if tag == head::TAG {
+            head_offset = Some(bytes.len())
+        }
+        bytes.extend_from_slice(data);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0)
+        }
+    }
+
+    if let Some(head_offset) = head_offset {
+        let offset = head_offset + HEAD_CHECKSUM_ADJUSTMENT_OFFSET;
+        if bytes.len() >= offset + mem::size_of::<u32>() {
+            for byte in &mut bytes[offset..(offset + mem::size_of::<u32>())] {
+                *byte = 0
+            }
+            let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&bytes));
+            (&mut bytes[offset..]).write_u32::<BigEndian>(adjustment).unwrap();
+        }
+    }
+
+    bytes
+}
+
+// Computes the OpenType checksum of the given data, which is the sum of its big-endian `u32`s,
+// padded with zeroes.
+fn checksum(mut data: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    while !data.is_empty() {
+        let mut word = [0; 4];
+        let length = if data.len() < 4 { data.len() } else { 4 };
+        word[..length].copy_from_slice(&data[..length]);
+        sum = sum.wrapping_add((&word[..]).read_u32::<BigEndian>().unwrap());
+        data = &data[length..]
+    }
+    sum
+}
@@ -43,18 +43,54 @@ pub enum FontError {
     UnsupportedHheaVersion,
     /// We don't support the declared version of the font's OS/2 and Windows table.
     UnsupportedOs2Version,
+    /// We don't support the declared version of the font's glyph substitution table.
+    UnsupportedGsubVersion,
+    /// We don't support the declared version of the font's glyph positioning table.
+    UnsupportedGposVersion,
+    /// We don't support the declared version of the font's glyph definition table.
+    UnsupportedGdefVersion,
+    /// We don't support the declared version of the font's variations table.
+    UnsupportedFvarVersion,
+    /// We don't support the declared version of the font's glyph variations table.
+    UnsupportedGvarVersion,
+    /// We don't support the declared version of the font's axis variations table.
+    UnsupportedAvarVersion,
+    /// We don't support the declared version of the font's horizontal metrics variations table.
+    UnsupportedHvarVersion,
+    /// We don't support the declared version of the font's metrics variations table.
+    UnsupportedMvarVersion,
+    /// We don't support the declared version of the font's maximum profile.
+    UnsupportedMaxpVersion,
+    /// We don't support the declared format of the font's naming table.
+    UnsupportedNameVersion,
     /// A required table is missing.
     RequiredTableMissing,
     /// An integer in a CFF DICT was not found.
     CffIntegerNotFound,
+    /// A real number in a CFF DICT was malformed.
+    CffBadRealNumber,
     /// The CFF Top DICT was not found.
     CffTopDictNotFound,
+    /// The CFF FontSet has no font with the requested name.
+    CffFontNotFound,
     /// A CFF `Offset` value was formatted incorrectly.
     CffBadOffset,
     /// The CFF evaluation stack overflowed.
     CffStackOverflow,
+    /// A CFF CharString operator needed more operands than were on the evaluation stack.
+    CffStackUnderflow,
+    /// A CFF CharString called a subroutine that doesn't exist.
+    CffSubrNotFound,
+    /// CFF subroutine calls were nested too deeply.
+    CffSubrNestingTooDeep,
+    /// The CFF Font DICT for a glyph in a CID-keyed font was not found.
+    CffFontDictNotFound,
+    /// A glyph that a CFF accented character is built from was not found.
+    CffGlyphNotFound,
     /// An unimplemented CFF CharString operator was encountered.
     CffUnimplementedOperator,
+    /// Variation data that a variable font refers to was not found.
+    VariationDataNotFound,
 }
 
 impl FontError {
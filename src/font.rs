@@ -10,16 +10,76 @@
 
 //! OpenType fonts.
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use charmap::{CodepointRange, GlyphMapping};
+use containers::cff as bare_cff;
 use containers::dfont;
-use containers::otf::{FontTables, SFNT_VERSIONS};
+use containers::otf::{self, FontTables, KNOWN_TABLES, SFNT_VERSIONS};
 use containers::ttc;
 use containers::woff;
 use error::FontError;
 use euclid::Point2D;
 use outline::GlyphBounds;
-use tables::hmtx::HorizontalMetrics;
+use std::cmp;
+use std::i16;
+use std::u16;
+use tables::fvar::{self, NamedInstance, VariationAxis};
+use tables::gpos::MarkAttachment;
+use tables::hmtx::{self, HorizontalMetrics};
+use tables::os_2::FsSelection;
+use tables::{avar, cff, cff2, glyf, gvar, head, hhea, hvar, loca, mvar, name, os_2, post};
+
+pub use tables::name::{COPYRIGHT_NOTICE, FAMILY_NAME, FULL_NAME, POSTSCRIPT_NAME, SUBFAMILY_NAME,
+                       TYPOGRAPHIC_FAMILY_NAME, TYPOGRAPHIC_SUBFAMILY_NAME, UNIQUE_ID,
+                       VERSION_STRING};
+pub use tables::os_2::{BITMAP_EMBEDDING_ONLY, EDITABLE_EMBEDDING, EmbeddingPermissions};
+pub use tables::os_2::{NO_SUBSETTING, PREVIEW_AND_PRINT_EMBEDDING, RESTRICTED_LICENSE_EMBEDDING};
+
+// The version number of TrueType font files.
+const TRUETYPE_SFNT_VERSION: u32 = 0x10000;
+
+// The `hhea` fields that `MVAR` tables can vary, in the same form as `OS_2_VARIABLE_VALUES`.
+static HHEA_VARIABLE_VALUES: [(u32, usize, bool); 6] = [
+    (mvar::HORIZONTAL_ASCENDER, 4, true),
+    (mvar::HORIZONTAL_DESCENDER, 6, true),
+    (mvar::HORIZONTAL_LINE_GAP, 8, true),
+    (mvar::HORIZONTAL_CARET_RISE, 18, true),
+    (mvar::HORIZONTAL_CARET_RUN, 20, true),
+    (mvar::HORIZONTAL_CARET_OFFSET, 22, true),
+];
+
+// The `post` fields that `MVAR` tables can vary, in the same form as `OS_2_VARIABLE_VALUES`.
+static POST_VARIABLE_VALUES: [(u32, usize, bool); 2] = [
+    (mvar::UNDERLINE_OFFSET, 8, true),
+    (mvar::UNDERLINE_SIZE, 10, true),
+];
+
+// The `OS/2` fields that `MVAR` tables can vary: their value tags, offsets, and whether they are
+// signed.
+static OS_2_VARIABLE_VALUES: [(u32, usize, bool); 17] = [
+    (mvar::SUBSCRIPT_X_SIZE, 10, true),
+    (mvar::SUBSCRIPT_Y_SIZE, 12, true),
+    (mvar::SUBSCRIPT_X_OFFSET, 14, true),
+    (mvar::SUBSCRIPT_Y_OFFSET, 16, true),
+    (mvar::SUPERSCRIPT_X_SIZE, 18, true),
+    (mvar::SUPERSCRIPT_Y_SIZE, 20, true),
+    (mvar::SUPERSCRIPT_X_OFFSET, 22, true),
+    (mvar::SUPERSCRIPT_Y_OFFSET, 24, true),
+    (mvar::STRIKEOUT_SIZE, 26, true),
+    (mvar::STRIKEOUT_OFFSET, 28, true),
+    (mvar::HORIZONTAL_ASCENDER, 68, true),
+    (mvar::HORIZONTAL_DESCENDER, 70, true),
+    (mvar::HORIZONTAL_LINE_GAP, 72, true),
+    (mvar::HORIZONTAL_CLIPPING_ASCENT, 74, false),
+    (mvar::HORIZONTAL_CLIPPING_DESCENT, 76, false),
+    (mvar::X_HEIGHT, 86, true),
+    (mvar::CAP_HEIGHT, 88, true),
+];
+
+// The widths, as percentages of the normal width, that each `OS/2.usWidthClass` stands for.
+static WIDTH_CLASS_PERCENTAGES: [f32; 9] = [
+    50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0,
+];
 
 /// A handle to a font backed by a byte buffer containing the contents of the file (`.ttf`,
 /// `.otf`), etc.
@@ -28,6 +88,7 @@ use tables::hmtx::HorizontalMetrics;
 pub struct Font<'a> {
     pub bytes: &'a [u8],
     tables: FontTables<'a>,
+    line_metrics_policy: LineMetricsPolicy,
 }
 
 #[doc(hidden)]
@@ -42,11 +103,12 @@ impl<'a> Font<'a> {
         Font {
             bytes: bytes,
             tables: tables,
+            line_metrics_policy: LineMetricsPolicy::Default,
         }
     }
 
     /// Creates a new font from a byte buffer containing the contents of a file or font collection
-    /// (`.ttf`, `.ttc`, `.otf`, etc.)
+    /// (`.ttf`, `.ttc`, `.otf`, `.cff`, etc.)
     ///
     /// If this is a `.ttc` or `.dfont` collection, this returns the first font within it. If you
     /// want to read another one, use the `Font::from_collection_index` API.
@@ -62,7 +124,8 @@ impl<'a> Font<'a> {
     /// Creates a new font from a single font within a byte buffer containing the contents of a
     /// file or a font collection (`.ttf`, `.ttc`, `.otf`, etc.)
     ///
-    /// If this is a `.ttc` or `.dfont` collection, this returns the appropriate font within it.
+    /// If this is a `.ttc` or `.dfont` collection, this returns the appropriate font within it. If
+    /// this is a bare `.cff` file, the index selects a font within its FontSet.
     ///
     /// The supplied `buffer` is an arbitrary vector that may or may not be used as a temporary
     /// storage space. Typically you will want to just pass an empty vector here.
@@ -78,10 +141,62 @@ impl<'a> Font<'a> {
             woff::MAGIC_NUMBER => Font::from_woff(bytes, buffer),
             dfont::MAGIC_NUMBER => Font::from_dfont_index(bytes, index),
             magic_number if SFNT_VERSIONS.contains(&magic_number) => Font::from_otf(bytes, 0),
+            magic_number if magic_number >> 8 == bare_cff::MAGIC_NUMBER_PREFIX => {
+                if index > u16::MAX as u32 {
+                    return Err(FontError::FontIndexOutOfBounds)
+                }
+                Font::from_cff_index(bytes, index as u16, buffer)
+            }
             _ => Err(FontError::UnknownFormat),
         }
     }
 
+    /// Creates a new font from the font with the given index within the CFF FontSet of a byte
+    /// buffer containing either an OpenType font with CFF outlines or a bare `.cff` file.
+    ///
+    /// The `CFF ` table of an OpenType font normally holds just one font, but bare CFF files, such
+    /// as those embedded in PDFs, may hold several. Use `cff_font_count()` to find out how many.
+    ///
+    /// The supplied `buffer` is used as for `Font::new()`.
+    pub fn from_cff_font_set_index<'b>(bytes: &'b [u8], index: u16, buffer: &'b mut Vec<u8>)
+                                       -> Result<Font<'b>, FontError> {
+        Font::from_cff_font_set(bytes, buffer, |_| Ok(index))
+    }
+
+    /// Creates a new font from the font with the given name, which is usually its PostScript
+    /// name, within the CFF FontSet of a byte buffer containing either an OpenType font with CFF
+    /// outlines or a bare `.cff` file.
+    ///
+    /// The supplied `buffer` is used as for `Font::new()`.
+    pub fn from_cff_font_set_name<'b>(bytes: &'b [u8], name: &str, buffer: &'b mut Vec<u8>)
+                                      -> Result<Font<'b>, FontError> {
+        Font::from_cff_font_set(bytes, buffer, |font_set| {
+            cff::CffTable::font_index_for_name(font_set, name)
+        })
+    }
+
+    // Creates a new font from the font in the CFF FontSet of the given file that `select` picks,
+    // given the raw FontSet.
+    fn from_cff_font_set<'b, F>(bytes: &'b [u8], buffer: &'b mut Vec<u8>, select: F)
+                                -> Result<Font<'b>, FontError>
+                                where F: FnOnce(FontTable<'b>) -> Result<u16, FontError> {
+        let mut reader = bytes;
+        let magic_number = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        if magic_number >> 8 == bare_cff::MAGIC_NUMBER_PREFIX {
+            let index = try!(select(FontTable {
+                bytes: bytes,
+            }));
+            return Font::from_cff_index(bytes, index, buffer)
+        }
+
+        let font = try!(Font::new(bytes, buffer));
+        let index = match font.cff_font_set() {
+            Some(font_set) => try!(select(font_set)),
+            None => return Err(FontError::RequiredTableMissing),
+        };
+        Font::from_table_list_with_cff_index(bytes, &font.tables.table_list, index)
+    }
+
     /// Returns the glyph IDs that map to the given ranges of Unicode codepoints.
     ///
     /// The returned glyph ranges are in the same order as the codepoints.
@@ -91,43 +206,99 @@ impl<'a> Font<'a> {
         self.tables.cmap.glyph_mapping_for_codepoint_ranges(codepoint_ranges)
     }
 
+    /// Returns the glyph for the given base character followed by the given variation selector,
+    /// such as an emoji presentation selector or an ideographic variation selector.
+    ///
+    /// Returns `None` if the font doesn't define the sequence, in which case the base
+    /// character's usual glyph should be used.
+    #[inline]
+    pub fn glyph_for_variation_sequence(&self, base: u32, selector: u32)
+                                        -> Result<Option<u16>, FontError> {
+        self.tables.cmap.glyph_for_variation_sequence(base, selector)
+    }
+
     /// Calls the given callback for each point in the supplied glyph's contour.
     ///
     /// This function is the primary method for accessing a glyph's outline.
     #[inline]
     pub fn for_each_point<F>(&self, glyph_id: u16, callback: F) -> Result<(), FontError>
                              where F: FnMut(&Point) {
-        match (self.tables.glyf, self.tables.cff) {
-            (Some(glyf), None) => {
+        self.for_each_point_with_variations(glyph_id, &[], callback)
+    }
+
+    /// Calls the given callback for each point in the supplied glyph's contour at the instance of
+    /// this variable font with the given normalized coordinates.
+    ///
+    /// There is one coordinate per axis, in the order that `variation_axes()` returns them; use
+    /// `VariationAxis::normalize()` to compute them from axis values. Missing coordinates are
+    /// treated as zero, which is the default instance. The font's `avar` mapping, if any, is
+    /// applied to the coordinates first.
+    pub fn for_each_point_with_variations<F>(&self, glyph_id: u16, coords: &[f32], callback: F)
+                                             -> Result<(), FontError> where F: FnMut(&Point) {
+        let coords = try!(self.map_variation_coords(coords));
+        match (self.tables.glyf, self.tables.cff, self.tables.cff2) {
+            (Some(glyf), None, None) => {
                 let loca = match self.tables.loca {
                     Some(ref loca) => loca,
                     None => return Err(FontError::RequiredTableMissing),
                 };
 
-                glyf.for_each_point(&self.tables.head, loca, glyph_id, callback)
+                glyf.for_each_point(&self.tables.head,
+                                    loca,
+                                    self.tables.gvar.as_ref(),
+                                    glyph_id,
+                                    &coords,
+                                    callback)
+            }
+            (None, Some(cff), None) => cff.for_each_point(&self.tables.head, glyph_id, callback),
+            (None, None, Some(cff2)) => {
+                cff2.for_each_point(&self.tables.head, glyph_id, &coords, callback)
             }
-            (None, Some(cff)) => cff.for_each_point(glyph_id, callback),
-            (Some(_), Some(_)) => Err(FontError::Failed),
-            (None, None) => Ok(()),
+            (None, None, None) => Ok(()),
+            _ => Err(FontError::Failed),
         }
     }
 
     /// Returns the boundaries of the given glyph in font units.
     #[inline]
     pub fn glyph_bounds(&self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
-        match (self.tables.glyf, self.tables.cff) {
-            (Some(glyf), None) => {
+        self.glyph_bounds_with_variations(glyph_id, &[])
+    }
+
+    /// Returns the boundaries of the given glyph in font units at the instance of this variable
+    /// font with the given normalized coordinates.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn glyph_bounds_with_variations(&self, glyph_id: u16, coords: &[f32])
+                                        -> Result<GlyphBounds, FontError> {
+        let coords = try!(self.map_variation_coords(coords));
+        match (self.tables.glyf, self.tables.cff, self.tables.cff2) {
+            (Some(glyf), None, None) => {
                 let loca = match self.tables.loca {
                     Some(ref loca) => loca,
                     None => return Err(FontError::RequiredTableMissing),
                 };
 
-                glyf.glyph_bounds(&self.tables.head, loca, glyph_id)
+                glyf.glyph_bounds(&self.tables.head,
+                                  loca,
+                                  self.tables.gvar.as_ref(),
+                                  glyph_id,
+                                  &coords)
             }
-            (None, Some(cff)) => cff.glyph_bounds(glyph_id),
-            (Some(_), Some(_)) => Err(FontError::Failed),
-            (None, None) => Err(FontError::RequiredTableMissing),
+            (None, Some(cff), None) => cff.glyph_bounds(&self.tables.head, glyph_id),
+            (None, None, Some(cff2)) => cff2.glyph_bounds(&self.tables.head, glyph_id, &coords),
+            (None, None, None) => Err(FontError::RequiredTableMissing),
+            _ => Err(FontError::Failed),
+        }
+    }
+
+    // Applies the font's `avar` mapping, if any, to the given normalized coordinates.
+    fn map_variation_coords(&self, coords: &[f32]) -> Result<Vec<f32>, FontError> {
+        let mut coords = coords.to_vec();
+        if let Some(avar) = self.tables.avar {
+            try!(avar.map_coords(&mut coords))
         }
+        Ok(coords)
     }
 
     /// Returns the minimum shelf height that an atlas containing glyphs from this font will need.
@@ -161,26 +332,452 @@ impl<'a> Font<'a> {
         self.tables.hmtx.metrics_for_glyph(&self.tables.hhea, glyph_id)
     }
 
+    /// Returns the horizontal metrics for the glyph with the given ID at the instance of this
+    /// variable font with the given normalized coordinates.
+    ///
+    /// Deltas come from the `HVAR` table if the font has one and from the phantom points in the
+    /// `gvar` table otherwise. Without an `HVAR` table, the left side bearing doesn't vary. See
+    /// `for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn metrics_for_glyph_with_variations(&self, glyph_id: u16, coords: &[f32])
+                                             -> Result<HorizontalMetrics, FontError> {
+        let mut metrics = try!(self.metrics_for_glyph(glyph_id));
+        let coords = try!(self.map_variation_coords(coords));
+        if coords.iter().all(|&coord| coord == 0.0) {
+            return Ok(metrics)
+        }
+
+        let (advance_width_delta, lsb_delta) = match (self.tables.hvar, self.tables.gvar) {
+            (Some(hvar), _) => {
+                (try!(hvar.advance_width_delta(glyph_id, &coords)),
+                 try!(hvar.lsb_delta(glyph_id, &coords)).unwrap_or(0.0))
+            }
+            (None, Some(ref gvar)) => {
+                match (self.tables.glyf, self.tables.loca.as_ref()) {
+                    (Some(glyf), Some(loca)) => {
+                        let advance_width_delta = try!(glyf.advance_width_delta(&self.tables.head,
+                                                                                loca,
+                                                                                gvar,
+                                                                                glyph_id,
+                                                                                &coords));
+                        (advance_width_delta, 0.0)
+                    }
+                    _ => (0.0, 0.0),
+                }
+            }
+            (None, None) => (0.0, 0.0),
+        };
+
+        let advance_width = metrics.advance_width as f32 + advance_width_delta;
+        metrics.advance_width = advance_width.round().max(0.0) as u16;
+        metrics.lsb = (metrics.lsb as f32 + lsb_delta).round() as i16;
+        Ok(metrics)
+    }
+
+    /// Returns the advance width that the CFF outline of the glyph with the given ID specifies, in
+    /// font units.
+    ///
+    /// This should agree with the advance width that `metrics_for_glyph()` returns, which comes
+    /// from the `hmtx` table. Returns `None` if the font doesn't have CFF outlines.
+    #[inline]
+    pub fn cff_advance_width(&self, glyph_id: u16) -> Result<Option<f32>, FontError> {
+        match self.tables.cff {
+            None => Ok(None),
+            Some(cff) => cff.advance_width(&self.tables.head, glyph_id).map(Some),
+        }
+    }
+
+    /// Returns the number of fonts in the FontSet of the font's CFF outlines, or zero if it
+    /// doesn't have any.
+    ///
+    /// This is 1 for OpenType fonts, but bare `.cff` files may have more. Use
+    /// `Font::from_cff_font_set_index()` to load the others.
+    pub fn cff_font_count(&self) -> u16 {
+        match self.cff_font_set() {
+            None => 0,
+            Some(font_set) => cff::CffTable::font_count(font_set).unwrap_or(0),
+        }
+    }
+
+    // Returns the raw `CFF ` table, which holds the FontSet, if the font has one.
+    fn cff_font_set(&self) -> Option<FontTable<'a>> {
+        KNOWN_TABLES.iter()
+                    .zip(self.tables.table_list.iter())
+                    .filter(|&(&tag, _)| tag == cff::TAG)
+                    .filter_map(|(_, table)| *table)
+                    .next()
+    }
+
     /// Returns the kerning between the given two glyph IDs in font units.
     ///
     /// Positive values move glyphs farther apart; negative values move glyphs closer together.
     ///
-    /// Zero is returned if no kerning is available in the font.
+    /// Kerning is read from the `kern` feature of the `GPOS` table if the font has one and from
+    /// the legacy `kern` table otherwise. Zero is returned if no kerning is available in the font.
     #[inline]
     pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16) -> i16 {
+        if let Some(ref gpos) = self.tables.gpos {
+            if gpos.has_kerning() {
+                return gpos.kerning_for_glyph_pair(left_glyph_id, right_glyph_id).unwrap_or(0)
+            }
+        }
+
         match self.tables.kern {
             None => 0,
             Some(kern) => kern.kerning_for_glyph_pair(left_glyph_id, right_glyph_id).unwrap_or(0),
         }
     }
 
+    /// Applies the font's glyph substitutions (ligatures, contextual forms, etc.) to the given run
+    /// of glyphs and returns the result.
+    ///
+    /// Only the features that are enabled by default for the default script and language are
+    /// applied. If the font has no `GSUB` table, the glyphs are returned unchanged.
+    pub fn substitute_glyphs(&self, glyph_ids: &[u16]) -> Result<Vec<u16>, FontError> {
+        let mut glyph_ids = glyph_ids.to_vec();
+        if let Some(gsub) = self.tables.gsub {
+            try!(gsub.substitute(&mut glyph_ids, self.tables.gdef));
+        }
+        Ok(glyph_ids)
+    }
+
+    /// Determines where the mark glyphs (accents, vowel points, etc.) in the given run of glyphs
+    /// attach to the glyphs before them.
+    ///
+    /// The result has one entry per glyph, which is `None` if the glyph does not attach to
+    /// anything. If the font has no `GPOS` table, nothing attaches.
+    pub fn mark_attachments(&self, glyph_ids: &[u16])
+                            -> Result<Vec<Option<MarkAttachment>>, FontError> {
+        match self.tables.gpos {
+            Some(ref gpos) => gpos.mark_attachments(glyph_ids, self.tables.gdef),
+            None => Ok(vec![None; glyph_ids.len()]),
+        }
+    }
+
+    /// Returns the class of the given glyph as assigned by the font's `GDEF` table.
+    ///
+    /// Returns `None` if the font has no `GDEF` table or doesn't assign the glyph a class.
+    #[inline]
+    pub fn glyph_class(&self, glyph_id: u16) -> Option<GlyphClass> {
+        match self.tables.gdef {
+            None => None,
+            Some(gdef) => gdef.glyph_class(glyph_id).unwrap_or(None),
+        }
+    }
+
+    /// Returns the PostScript name of the glyph with the given ID, if the font names its glyphs.
+    ///
+    /// Names come from the charset of fonts with CFF outlines, falling back to the `post` table.
+    pub fn glyph_name(&self, glyph_id: u16) -> Option<&'a str> {
+        if let Some(cff) = self.tables.cff {
+            if let Some(name) = cff.glyph_name(glyph_id).unwrap_or(None) {
+                return Some(name)
+            }
+        }
+        match self.tables.post {
+            None => None,
+            Some(post) => post.glyph_name(glyph_id).unwrap_or(None),
+        }
+    }
+
+    /// Returns the ID of the glyph with the given PostScript name, if there is one.
+    ///
+    /// This is the inverse of `glyph_name()`.
+    pub fn glyph_id_for_name(&self, name: &str) -> Option<u16> {
+        if let Some(cff) = self.tables.cff {
+            if let Some(glyph_id) = cff.glyph_id_for_name(name).unwrap_or(None) {
+                return Some(glyph_id)
+            }
+        }
+        match self.tables.post {
+            None => None,
+            Some(post) => post.glyph_id_for_name(name).unwrap_or(None),
+        }
+    }
+
+    /// Returns the ID of the glyph that the built-in encoding of the font's CFF outlines maps the
+    /// given character code to, if any.
+    ///
+    /// Fonts embedded in PDFs are usually addressed by these codes rather than by Unicode
+    /// codepoints. Returns `None` if the font doesn't have CFF outlines or is CID-keyed.
+    #[inline]
+    pub fn glyph_id_for_cff_code(&self, code: u8) -> Option<u16> {
+        match self.tables.cff {
+            None => None,
+            Some(cff) => cff.glyph_id_for_code(code).unwrap_or(None),
+        }
+    }
+
+    /// Returns the family name of the font, such as "Nimbus Sans L".
+    ///
+    /// The typographic family name is preferred, so all the styles of a large family share a name.
+    #[inline]
+    pub fn family_name(&self) -> Option<String> {
+        self.name(name::TYPOGRAPHIC_FAMILY_NAME, None)
+            .or_else(|| self.name(name::FAMILY_NAME, None))
+    }
+
+    /// Returns the name of the style of the font within its family, such as "Regular" or
+    /// "Condensed Bold Italic".
+    ///
+    /// Like `family_name()`, this prefers the typographic subfamily name.
+    #[inline]
+    pub fn style_name(&self) -> Option<String> {
+        self.name(name::TYPOGRAPHIC_SUBFAMILY_NAME, None)
+            .or_else(|| self.name(name::SUBFAMILY_NAME, None))
+    }
+
+    /// Returns the full name of the font, such as "Nimbus Sans L Regular".
+    #[inline]
+    pub fn full_name(&self) -> Option<String> {
+        self.name(name::FULL_NAME, None)
+    }
+
+    /// Returns the PostScript name of the font, such as "NimbusSanL-Regu".
+    ///
+    /// Fonts with CFF outlines and no naming table, such as bare `.cff` files, fall back to the
+    /// name of the font within its FontSet.
+    pub fn postscript_name(&self) -> Option<String> {
+        match self.name(name::POSTSCRIPT_NAME, None) {
+            Some(name) => Some(name),
+            None => self.tables.cff.and_then(|cff| cff.font_name()).map(|name| name.to_owned()),
+        }
+    }
+
+    /// Returns the string with the given ID from the font's naming table, if there is one.
+    ///
+    /// The IDs of the common strings, such as `FAMILY_NAME`, are constants in this module;
+    /// variation axes and named instances refer to others. If a language is given, it's a Windows
+    /// language ID such as 0x0409 for US English, and only strings in that language are returned.
+    /// Otherwise, the string is in English if possible.
+    pub fn name(&self, name_id: u16, language_id: Option<u16>) -> Option<String> {
+        match self.tables.name {
+            None => None,
+            Some(name) => name.name(name_id, language_id).unwrap_or(None),
+        }
+    }
+
+    /// Returns the axes that this font varies along, such as weight or width.
+    ///
+    /// The axes are in the order that variation coordinates are specified in. If this is not a
+    /// variable font, this returns an empty list.
+    pub fn variation_axes(&self) -> Result<Vec<VariationAxis>, FontError> {
+        match self.tables.fvar {
+            Some(fvar) => fvar.axes(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the instances that this variable font predefines, such as "Bold" or
+    /// "SemiBold Condensed".
+    ///
+    /// If this is not a variable font, this returns an empty list.
+    pub fn named_instances(&self) -> Result<Vec<NamedInstance>, FontError> {
+        match self.tables.fvar {
+            Some(fvar) => fvar.named_instances(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Writes out a static TrueType font file holding the instance of this variable font with the
+    /// given normalized coordinates.
+    ///
+    /// The new font's outlines, horizontal metrics, bounding boxes, and `OS/2` metrics are those
+    /// of the instance, and it has no variation tables. Only the tables that Pathfinder reads are
+    /// copied, so hinting instructions are dropped. To bake a named instance, normalize its
+    /// coordinates with `VariationAxis::normalize()`. See `for_each_point_with_variations()` for
+    /// the meaning of the coordinates.
+    ///
+    /// Only fonts with TrueType outlines can be instanced.
+    pub fn instantiate(&self, coords: &[f32]) -> Result<Vec<u8>, FontError> {
+        let (glyf, loca) = match (self.tables.glyf, self.tables.loca.as_ref()) {
+            (Some(glyf), Some(loca)) => (glyf, loca),
+            _ => return Err(FontError::UnsupportedGlyphFormat),
+        };
+        let glyph_count = match self.tables.maxp {
+            Some(ref maxp) => maxp.num_glyphs,
+            None => return Err(FontError::RequiredTableMissing),
+        };
+
+        let mapped_coords = try!(self.map_variation_coords(coords));
+        let instance = try!(glyf.instantiate(&self.tables.head,
+                                             loca,
+                                             self.tables.gvar.as_ref(),
+                                             glyph_count,
+                                             &mapped_coords));
+
+        // Build the horizontal metrics. Each glyph's origin stays where its phantom points put it.
+        //
+        // Writing to a `Vec` can't fail, so the `unwrap()`s below are safe.
+        let mut hmtx = vec![];
+        let mut font_bounds: Option<GlyphBounds> = None;
+        let mut advance_width_max = 0;
+        let (mut min_lsb, mut min_rsb, mut x_max_extent) = (i16::MAX, i16::MAX, i16::MIN);
+        for glyph_id in 0..glyph_count {
+            let metrics = try!(self.metrics_for_glyph_with_variations(glyph_id, coords));
+            advance_width_max = cmp::max(advance_width_max, metrics.advance_width);
+
+            let lsb = match instance.bounds[glyph_id as usize] {
+                None => 0,
+                Some(bounds) => {
+                    let original_lsb = try!(self.metrics_for_glyph(glyph_id)).lsb;
+                    let original_left = try!(glyf.glyph_bounds(&self.tables.head,
+                                                               loca,
+                                                               None,
+                                                               glyph_id,
+                                                               &[])).left;
+                    let origin = (original_left - original_lsb as i32) as f32 +
+                        instance.origin_deltas[glyph_id as usize];
+                    let lsb = (bounds.left as f32 - origin).round() as i16;
+
+                    let extent = lsb as i32 + bounds.right - bounds.left;
+                    min_lsb = cmp::min(min_lsb, lsb);
+                    min_rsb = cmp::min(min_rsb, (metrics.advance_width as i32 - extent) as i16);
+                    x_max_extent = cmp::max(x_max_extent, extent as i16);
+
+                    font_bounds = Some(match font_bounds {
+                        None => bounds,
+                        Some(font_bounds) => {
+                            GlyphBounds {
+                                left: cmp::min(font_bounds.left, bounds.left),
+                                bottom: cmp::min(font_bounds.bottom, bounds.bottom),
+                                right: cmp::max(font_bounds.right, bounds.right),
+                                top: cmp::max(font_bounds.top, bounds.top),
+                            }
+                        }
+                    });
+                    lsb
+                }
+            };
+
+            hmtx.write_u16::<BigEndian>(metrics.advance_width).unwrap();
+            hmtx.write_i16::<BigEndian>(lsb).unwrap();
+        }
+        if font_bounds.is_none() {
+            min_lsb = 0;
+            min_rsb = 0;
+            x_max_extent = 0;
+        }
+
+        let mut loca_data = vec![];
+        for &offset in &instance.loca {
+            loca_data.write_u32::<BigEndian>(offset).unwrap();
+        }
+
+        // Copy the other tables, patching the fields that the instance changes.
+        let axes = try!(self.variation_axes());
+        let mut tables = vec![];
+        for (&tag, table) in KNOWN_TABLES.iter().zip(self.tables.table_list.iter()) {
+            let mut data = match *table {
+                Some(table) => table.bytes.to_vec(),
+                None => continue,
+            };
+
+            match tag {
+                avar::TAG | cff::TAG | cff2::TAG | fvar::TAG | gvar::TAG | hvar::TAG | mvar::TAG |
+                glyf::TAG | loca::TAG | hmtx::TAG => continue,
+                head::TAG => {
+                    let font_bounds = font_bounds.unwrap_or_default();
+                    write_u16_at(&mut data, 36, font_bounds.left as i16 as u16);
+                    write_u16_at(&mut data, 38, font_bounds.bottom as i16 as u16);
+                    write_u16_at(&mut data, 40, font_bounds.right as i16 as u16);
+                    write_u16_at(&mut data, 42, font_bounds.top as i16 as u16);
+
+                    // We always write the long version of `loca`.
+                    write_u16_at(&mut data, 50, 1);
+                }
+                hhea::TAG => {
+                    write_u16_at(&mut data, 10, advance_width_max);
+                    write_u16_at(&mut data, 12, min_lsb as u16);
+                    write_u16_at(&mut data, 14, min_rsb as u16);
+                    write_u16_at(&mut data, 16, x_max_extent as u16);
+                    write_u16_at(&mut data, 34, glyph_count);
+                    try!(self.vary_table_values(&mut data, &HHEA_VARIABLE_VALUES, &mapped_coords))
+                }
+                post::TAG => {
+                    try!(self.vary_table_values(&mut data, &POST_VARIABLE_VALUES, &mapped_coords))
+                }
+                os_2::TAG => {
+                    try!(self.vary_table_values(&mut data, &OS_2_VARIABLE_VALUES, &mapped_coords));
+
+                    for (axis_index, axis) in axes.iter().enumerate() {
+                        let value = axis.denormalize(*coords.get(axis_index).unwrap_or(&0.0));
+                        match axis.tag {
+                            fvar::WEIGHT => {
+                                let weight_class = value.round().max(1.0).min(1000.0) as u16;
+                                write_u16_at(&mut data, 4, weight_class)
+                            }
+                            fvar::WIDTH => {
+                                let mut width_class = 1;
+                                for (index, &percentage) in WIDTH_CLASS_PERCENTAGES.iter()
+                                                                                  .enumerate() {
+                                    let closest = WIDTH_CLASS_PERCENTAGES[width_class - 1];
+                                    if (value - percentage).abs() < (value - closest).abs() {
+                                        width_class = index + 1
+                                    }
+                                }
+                                write_u16_at(&mut data, 6, width_class as u16)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            tables.push((tag, data))
+        }
+
+        tables.push((glyf::TAG, instance.glyf));
+        tables.push((loca::TAG, loca_data));
+        tables.push((hmtx::TAG, hmtx));
+        Ok(otf::write_otf(TRUETYPE_SFNT_VERSION, &tables))
+    }
+
+    // Applies the `MVAR` deltas at the given mapped coordinates to the fields of a table, given as
+    // value tags, offsets, and whether they are signed.
+    fn vary_table_values(&self, data: &mut [u8], values: &[(u32, usize, bool)], coords: &[f32])
+                         -> Result<(), FontError> {
+        let mvar = match self.tables.mvar {
+            None => return Ok(()),
+            Some(mvar) => mvar,
+        };
+
+        for &(value_tag, offset, signed) in values {
+            let value = match read_u16_at(data, offset) {
+                Some(value) if signed => value as i16 as f32,
+                Some(value) => value as f32,
+                None => continue,
+            };
+            let value = (value + try!(mvar.delta(value_tag, coords))).round();
+            let value = if signed { value as i16 as u16 } else { value as u16 };
+            write_u16_at(data, offset, value)
+        }
+        Ok(())
+    }
+
+    /// Returns which of the font's sets of line metrics `ascender()`, `descender()`, and
+    /// `line_gap()` report.
+    #[inline]
+    pub fn line_metrics_policy(&self) -> LineMetricsPolicy {
+        self.line_metrics_policy
+    }
+
+    /// Chooses which of the font's sets of line metrics `ascender()`, `descender()`, and
+    /// `line_gap()` report.
+    ///
+    /// The default is `LineMetricsPolicy::Default`.
+    #[inline]
+    pub fn set_line_metrics_policy(&mut self, policy: LineMetricsPolicy) {
+        self.line_metrics_policy = policy
+    }
+
     /// Returns the distance from the baseline to the top of the text box in font units.
     ///
     /// The following expression computes the baseline-to-baseline height:
     /// `font.ascender() - font.descender() + font.line_gap()`.
     #[inline]
     pub fn ascender(&self) -> i16 {
-        self.tables.os_2.typo_ascender
+        self.line_metrics(&[]).0
     }
 
     /// Returns the distance from the baseline to the bottom of the text box in font units.
@@ -189,7 +786,7 @@ impl<'a> Font<'a> {
     /// `font.ascender() - font.descender() + font.line_gap()`.
     #[inline]
     pub fn descender(&self) -> i16 {
-        self.tables.os_2.typo_descender
+        self.line_metrics(&[]).1
     }
 
     /// Returns the recommended extra gap between lines in font units.
@@ -198,14 +795,336 @@ impl<'a> Font<'a> {
     /// `font.ascender() - font.descender() + font.line_gap()`.
     #[inline]
     pub fn line_gap(&self) -> i16 {
-        self.tables.os_2.typo_line_gap
+        self.line_metrics(&[]).2
+    }
+
+    /// Returns the ascender at the instance of this variable font with the given normalized
+    /// coordinates, varied according to the font's `MVAR` table.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    #[inline]
+    pub fn ascender_with_variations(&self, coords: &[f32]) -> i16 {
+        self.line_metrics(coords).0
+    }
+
+    /// Returns the descender at the instance of this variable font with the given normalized
+    /// coordinates, varied according to the font's `MVAR` table.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    #[inline]
+    pub fn descender_with_variations(&self, coords: &[f32]) -> i16 {
+        self.line_metrics(coords).1
+    }
+
+    /// Returns the line gap at the instance of this variable font with the given normalized
+    /// coordinates, varied according to the font's `MVAR` table.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    #[inline]
+    pub fn line_gap_with_variations(&self, coords: &[f32]) -> i16 {
+        self.line_metrics(coords).2
+    }
+
+    // Returns the ascender, descender, and line gap that the line metrics policy calls for, varied
+    // at the given normalized coordinates.
+    fn line_metrics(&self, coords: &[f32]) -> (i16, i16, i16) {
+        let hhea = &self.tables.hhea;
+        let policy = match self.line_metrics_policy {
+            // Some fonts leave the `hhea` metrics zeroed, so use the typographic ones instead.
+            LineMetricsPolicy::Default => {
+                match self.tables.os_2 {
+                    Some(ref table) if table.fs_selection.contains(os_2::USE_TYPO_METRICS) => {
+                        LineMetricsPolicy::Typographic
+                    }
+                    Some(_) if hhea.ascender == 0 && hhea.descender == 0 => {
+                        LineMetricsPolicy::Typographic
+                    }
+                    _ => LineMetricsPolicy::Horizontal,
+                }
+            }
+            policy => policy,
+        };
+
+        // Fonts without an `OS/2` table get the `hhea` metrics no matter what.
+        match (policy, self.tables.os_2.as_ref()) {
+            (LineMetricsPolicy::Typographic, Some(table)) => {
+                (self.vary_metric(table.typo_ascender, mvar::HORIZONTAL_ASCENDER, coords),
+                 self.vary_metric(table.typo_descender, mvar::HORIZONTAL_DESCENDER, coords),
+                 self.vary_metric(table.typo_line_gap, mvar::HORIZONTAL_LINE_GAP, coords))
+            }
+            (LineMetricsPolicy::Windows, Some(table)) => {
+                let win_ascent = self.vary_metric(table.win_ascent as i16,
+                                                  mvar::HORIZONTAL_CLIPPING_ASCENT,
+                                                  coords);
+                let win_descent = self.vary_metric(table.win_descent as i16,
+                                                   mvar::HORIZONTAL_CLIPPING_DESCENT,
+                                                   coords);
+
+                // Like Windows, take whatever of the `hhea` line height the clipping metrics leave
+                // over as the line gap.
+                let (ascender, descender, line_gap) = self.hhea_line_metrics(coords);
+                let line_height = ascender as i32 - descender as i32 + line_gap as i32;
+                let win_line_gap = line_height - (win_ascent as i32 + win_descent as i32);
+                (win_ascent, -win_descent, cmp::max(win_line_gap, 0) as i16)
+            }
+            _ => self.hhea_line_metrics(coords),
+        }
+    }
+
+    // Returns the ascender, descender, and line gap from the `hhea` table. `MVAR` varies these
+    // along with the typographic metrics.
+    fn hhea_line_metrics(&self, coords: &[f32]) -> (i16, i16, i16) {
+        let hhea = &self.tables.hhea;
+        (self.vary_metric(hhea.ascender, mvar::HORIZONTAL_ASCENDER, coords),
+         self.vary_metric(hhea.descender, mvar::HORIZONTAL_DESCENDER, coords),
+         self.vary_metric(hhea.line_gap, mvar::HORIZONTAL_LINE_GAP, coords))
+    }
+
+    /// Returns the positions and thicknesses of underlines and strikeouts in font units.
+    ///
+    /// If the `post` table doesn't specify the underline, it's synthesized from the size of the
+    /// em. If the `OS/2` table doesn't specify the strikeout, it's as thick as the underline and
+    /// centered halfway up the x-height.
+    #[inline]
+    pub fn decoration_metrics(&self) -> DecorationMetrics {
+        self.decoration_metrics_with_variations(&[])
+    }
+
+    /// Returns the underline and strikeout metrics at the instance of this variable font with the
+    /// given normalized coordinates, varied according to the font's `MVAR` table.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn decoration_metrics_with_variations(&self, coords: &[f32]) -> DecorationMetrics {
+        let units_per_em = self.tables.head.units_per_em as i32;
+
+        let (underline_position, underline_thickness) = match self.tables.post {
+            Some(ref table) if table.underline_thickness > 0 => {
+                (self.vary_metric(table.underline_position, mvar::UNDERLINE_OFFSET, coords),
+                 self.vary_metric(table.underline_thickness, mvar::UNDERLINE_SIZE, coords))
+            }
+            _ => (-(units_per_em / 10) as i16, cmp::max(units_per_em / 20, 1) as i16),
+        };
+
+        let (strikeout_position, strikeout_size) = match self.tables.os_2 {
+            Some(ref table) if table.strikeout_size > 0 => {
+                (self.vary_metric(table.strikeout_position, mvar::STRIKEOUT_OFFSET, coords),
+                 self.vary_metric(table.strikeout_size, mvar::STRIKEOUT_SIZE, coords))
+            }
+            _ => {
+                let x_height = match self.x_height() {
+                    Some(x_height) if x_height > 0 => {
+                        self.vary_metric(x_height, mvar::X_HEIGHT, coords) as i32
+                    }
+                    _ => units_per_em / 2,
+                };
+                ((x_height / 2 + underline_thickness as i32 / 2) as i16, underline_thickness)
+            }
+        };
+
+        DecorationMetrics {
+            underline_position: underline_position,
+            underline_thickness: underline_thickness,
+            strikeout_position: strikeout_position,
+            strikeout_size: strikeout_size,
+        }
+    }
+
+    /// Returns the angle of the font's vertical strokes in degrees counterclockwise from the
+    /// vertical, which is negative for fonts that lean forward.
+    ///
+    /// Fonts without a `post` table are assumed to be upright.
+    #[inline]
+    pub fn italic_angle(&self) -> f32 {
+        match self.tables.post {
+            Some(ref table) => table.italic_angle,
+            None => 0.0,
+        }
+    }
+
+    /// Returns the slope and offset of the text cursor in font units.
+    ///
+    /// If the `hhea` table doesn't specify a valid slope, it's computed from `italic_angle()`.
+    #[inline]
+    pub fn caret_metrics(&self) -> CaretMetrics {
+        self.caret_metrics_with_variations(&[])
+    }
+
+    /// Returns the caret metrics at the instance of this variable font with the given normalized
+    /// coordinates, varied according to the font's `MVAR` table.
+    ///
+    /// See `for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn caret_metrics_with_variations(&self, coords: &[f32]) -> CaretMetrics {
+        let hhea = &self.tables.hhea;
+        let slope_rise = self.vary_metric(hhea.caret_slope_rise,
+                                          mvar::HORIZONTAL_CARET_RISE,
+                                          coords);
+        let (slope_rise, slope_run) = if slope_rise != 0 {
+            (slope_rise, self.vary_metric(hhea.caret_slope_run, mvar::HORIZONTAL_CARET_RUN, coords))
+        } else if self.italic_angle() != 0.0 {
+            let rise = self.tables.head.units_per_em;
+            let run = -self.italic_angle().to_radians().tan() * rise as f32;
+            (rise as i16, run.round().max(i16::MIN as f32).min(i16::MAX as f32) as i16)
+        } else {
+            (1, 0)
+        };
+
+        CaretMetrics {
+            slope_rise: slope_rise,
+            slope_run: slope_run,
+            offset: self.vary_metric(hhea.caret_offset, mvar::HORIZONTAL_CARET_OFFSET, coords),
+        }
+    }
+
+    /// Returns the visual weight of the font, from 1 to 1000, where 400 is regular and 700 is bold.
+    ///
+    /// Fonts without an `OS/2` table are assumed to be regular.
+    #[inline]
+    pub fn weight_class(&self) -> u16 {
+        match self.tables.os_2 {
+            Some(ref table) => table.weight_class,
+            None => 400,
+        }
+    }
+
+    /// Returns the relative width of the font, from 1 (ultra-condensed) to 9 (ultra-expanded),
+    /// where 5 is normal.
+    ///
+    /// Fonts without an `OS/2` table are assumed to be of normal width.
+    #[inline]
+    pub fn width_class(&self) -> u16 {
+        match self.tables.os_2 {
+            Some(ref table) => table.width_class,
+            None => 5,
+        }
+    }
+
+    /// Returns true if the font is italic or oblique.
+    #[inline]
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection().contains(os_2::ITALIC)
+    }
+
+    /// Returns true if the font is bold.
+    #[inline]
+    pub fn is_bold(&self) -> bool {
+        self.fs_selection().contains(os_2::BOLD)
+    }
+
+    /// Returns true if the font is oblique, that is, slanted rather than having cursive forms.
+    ///
+    /// Fonts whose `OS/2` table is older than version 4 never say that they are oblique.
+    #[inline]
+    pub fn is_oblique(&self) -> bool {
+        self.fs_selection().contains(os_2::OBLIQUE)
+    }
+
+    /// Returns the height of lowercase letters such as "x" in font units, if the font says.
+    #[inline]
+    pub fn x_height(&self) -> Option<i16> {
+        self.tables.os_2.as_ref().and_then(|table| table.x_height)
+    }
+
+    /// Returns the height of capital letters such as "H" in font units, if the font says.
+    #[inline]
+    pub fn cap_height(&self) -> Option<i16> {
+        self.tables.os_2.as_ref().and_then(|table| table.cap_height)
+    }
+
+    /// Returns the licensing restrictions on embedding this font in documents.
+    ///
+    /// Fonts without an `OS/2` table have no restrictions.
+    #[inline]
+    pub fn embedding_permissions(&self) -> EmbeddingPermissions {
+        match self.tables.os_2 {
+            Some(ref table) => table.embedding_permissions,
+            None => EmbeddingPermissions::empty(),
+        }
+    }
+
+    /// Returns the bits of the `OS/2` table specifying the Unicode blocks that the font
+    /// functionally covers.
+    ///
+    /// Bit 0 of the first element is the first bit of `ulUnicodeRange1` (Basic Latin), and so on.
+    /// Fonts without an `OS/2` table cover no blocks as far as this is concerned.
+    #[inline]
+    pub fn unicode_ranges(&self) -> [u32; 4] {
+        match self.tables.os_2 {
+            Some(ref table) => table.unicode_ranges,
+            None => [0; 4],
+        }
+    }
+
+    /// Returns the bits of the `OS/2` table specifying the code pages that the font functionally
+    /// covers.
+    ///
+    /// Fonts without an `OS/2` table of at least version 1 cover no code pages as far as this is
+    /// concerned.
+    #[inline]
+    pub fn code_page_ranges(&self) -> [u32; 2] {
+        match self.tables.os_2 {
+            Some(ref table) => table.code_page_ranges,
+            None => [0; 2],
+        }
+    }
+
+    /// Returns the PANOSE classification of the font's visual style.
+    ///
+    /// Fonts without an `OS/2` table are classified as "any" in every respect.
+    #[inline]
+    pub fn panose(&self) -> [u8; 10] {
+        match self.tables.os_2 {
+            Some(ref table) => table.panose,
+            None => [0; 10],
+        }
+    }
+
+    // Returns the style flags of the font, which are empty if it has no `OS/2` table.
+    #[inline]
+    fn fs_selection(&self) -> FsSelection {
+        match self.tables.os_2 {
+            Some(ref table) => table.fs_selection,
+            None => FsSelection::empty(),
+        }
+    }
+
+    // Applies the `MVAR` delta for the metric with the given value tag. Malformed tables leave the
+    // metric alone.
+    fn vary_metric(&self, value: i16, value_tag: u32, coords: &[f32]) -> i16 {
+        let mvar = match self.tables.mvar {
+            Some(mvar) if !coords.is_empty() => mvar,
+            _ => return value,
+        };
+
+        let delta = self.map_variation_coords(coords).and_then(|coords| {
+            mvar.delta(value_tag, &coords)
+        });
+        match delta {
+            Ok(delta) => (value as f32 + delta).round() as i16,
+            Err(_) => value,
+        }
+    }
+}
+
+// Reads the big-endian 16-bit value at the given offset of a table, if the table is long enough.
+fn read_u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..).and_then(|mut reader| reader.read_u16::<BigEndian>().ok())
+}
+
+// Overwrites the big-endian 16-bit value at the given offset of a table, if the table is long
+// enough to have one there.
+fn write_u16_at(data: &mut [u8], offset: usize, value: u16) {
+    if let Some(mut slot) = data.get_mut(offset..) {
+        // Failure means that the table is too short, which we ignore.
+        drop(slot.write_u16::<BigEndian>(value))
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Point {
     /// Where the point is located in glyph space.
-    pub position: Point2D<i16>,
+    ///
+    /// TrueType outlines always lie on integer coordinates, but CFF outlines may not.
+    pub position: Point2D<f32>,
 
     /// The index of the point in this contour.
     ///
@@ -230,3 +1149,60 @@ pub enum PointKind {
     SecondCubicControl,
 }
 
+/// The positions and thicknesses of text decorations in font units.
+///
+/// Positions are the distances from the baseline to the tops of the strokes, and are negative
+/// below the baseline.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DecorationMetrics {
+    /// The position of the top of the underline.
+    pub underline_position: i16,
+    /// The thickness of the underline.
+    pub underline_thickness: i16,
+    /// The position of the top of the strikeout stroke.
+    pub strikeout_position: i16,
+    /// The thickness of the strikeout stroke.
+    pub strikeout_size: i16,
+}
+
+/// The slope and offset of the text cursor in font units.
+///
+/// The slope is given as a rise and run, so an upright caret has a rise of 1 and a run of 0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CaretMetrics {
+    /// The vertical component of the slope.
+    pub slope_rise: i16,
+    /// The horizontal component of the slope.
+    pub slope_run: i16,
+    /// How far to shift the caret horizontally so that it looks good on slanted glyphs.
+    pub offset: i16,
+}
+
+/// Which of a font's sets of line metrics `Font::ascender()`, `Font::descender()`, and
+/// `Font::line_gap()` report.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineMetricsPolicy {
+    /// The typographic metrics if the font asks for them to be used, and the `hhea` metrics
+    /// otherwise. This is what most platforms do.
+    Default,
+    /// The typographic metrics from the `OS/2` table.
+    Typographic,
+    /// The metrics from the `hhea` table, as used on macOS.
+    Horizontal,
+    /// The clipping metrics from the `OS/2` table, as used by Windows GDI. The line gap is
+    /// whatever of the `hhea` line height they leave over.
+    Windows,
+}
+
+/// The class of a glyph, as used by the OpenType layout tables.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GlyphClass {
+    /// A single character, spacing glyph.
+    Base,
+    /// A glyph that represents multiple characters.
+    Ligature,
+    /// A non-spacing combining glyph, such as an accent.
+    Mark,
+    /// Part of a single character, spacing glyph.
+    Component,
+}
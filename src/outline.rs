@@ -19,8 +19,8 @@ use std::mem;
 use std::os::raw::c_void;
 
 static DUMMY_VERTEX: Vertex = Vertex {
-    x: 0,
-    y: 0,
+    x: 0.0,
+    y: 0.0,
     glyph_index: 0,
 };
 
@@ -44,7 +44,17 @@ impl OutlineBuilder {
 
     /// Adds a new glyph to the outline builder. Returns the glyph index, which is useful for later
     /// calls to `Atlas::pack_glyph()`.
+    #[inline]
     pub fn add_glyph(&mut self, font: &Font, glyph_id: u16) -> Result<u16, FontError> {
+        self.add_glyph_with_variations(font, glyph_id, &[])
+    }
+
+    /// Adds a new glyph to the outline builder at the instance of a variable font with the given
+    /// normalized coordinates. Returns the glyph index, as `add_glyph()` does.
+    ///
+    /// See `Font::for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn add_glyph_with_variations(&mut self, font: &Font, glyph_id: u16, coords: &[f32])
+                                     -> Result<u16, FontError> {
         let glyph_index = self.descriptors.len() as u16;
 
         let mut point_index = self.vertices.len() as u32;
@@ -52,7 +62,7 @@ impl OutlineBuilder {
         let start_point = point_index;
         let mut last_point_kind = PointKind::OnCurve;
 
-        try!(font.for_each_point(glyph_id, |point| {
+        try!(font.for_each_point_with_variations(glyph_id, coords, |point| {
             self.vertices.push(Vertex {
                 x: point.position.x,
                 y: point.position.y,
@@ -79,7 +89,7 @@ impl OutlineBuilder {
 
         // Add a glyph descriptor.
         self.descriptors.push(GlyphDescriptor {
-            bounds: try!(font.glyph_bounds(glyph_id)),
+            bounds: try!(font.glyph_bounds_with_variations(glyph_id, coords)),
             units_per_em: font.units_per_em() as u32,
             start_point: start_point as u32,
             start_index: start_index,
@@ -233,8 +243,8 @@ impl GlyphDescriptor {
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct Vertex {
-    x: i16,
-    y: i16,
+    x: f32,
+    y: f32,
     glyph_index: u16,
 }
 
@@ -258,16 +258,17 @@ impl Rasterizer {
 
             // Set up the buffer layout.
             gl::BindBuffer(gl::ARRAY_BUFFER, outlines.vertices_buffer());
-            gl::VertexAttribIPointer(self.draw_position_attribute as GLuint,
-                                     2,
-                                     gl::SHORT,
-                                     mem::size_of::<Vertex>() as GLint,
-                                     0 as *const GLvoid);
+            gl::VertexAttribPointer(self.draw_position_attribute as GLuint,
+                                    2,
+                                    gl::FLOAT,
+                                    gl::FALSE,
+                                    mem::size_of::<Vertex>() as GLint,
+                                    0 as *const GLvoid);
             gl::VertexAttribIPointer(self.draw_glyph_index_attribute as GLuint,
                                      1,
                                      gl::UNSIGNED_SHORT,
                                      mem::size_of::<Vertex>() as GLint,
-                                     mem::size_of::<(i16, i16)>() as *const GLvoid);
+                                     mem::size_of::<(f32, f32)>() as *const GLvoid);
             gl::EnableVertexAttribArray(self.draw_position_attribute as GLuint);
             gl::EnableVertexAttribArray(self.draw_glyph_index_attribute as GLuint);
 
@@ -10,50 +10,120 @@
 
 //! A very basic text shaper for simple needs.
 //!
-//! Do not use this for international or high-quality text. This shaper does not do kerning,
-//! ligation, or advanced typography features (`GSUB`, `GPOS`, text morphing). Consider HarfBuzz or
-//! the system shaper instead.
+//! Do not use this for international or high-quality text. This shaper applies only the `GSUB`
+//! features that are on by default for the default script and language (ligatures and contextual
+//! forms), simple pair kerning, and mark attachment. It does no script-specific processing or
+//! reordering. Consider HarfBuzz or the system shaper instead.
 
 use charmap::GlyphMapping;
-use font::Font;
+use font::{Font, GlyphClass};
 
 /// Shapes the given Unicode text in the given font, returning the proper position for each glyph.
 ///
 /// See the description of this module for caveats.
 ///
 /// For proper operation, the given `glyph_mapping` must include all the glyphs necessary to render
-/// the string.
+/// the string. Variation selectors are applied to the characters they follow according to the
+/// font's variation sequences and produce no glyphs of their own.
 pub fn shape_text(font: &Font, glyph_mapping: &GlyphMapping, string: &str) -> Vec<GlyphPos> {
-    let mut chars = string.chars().peekable();
-    let mut next_glyph_id = None;
-    let mut result = vec![];
+    shape_text_with_variations(font, glyph_mapping, string, &[])
+}
 
+/// Shapes the given Unicode text in the instance of the given variable font with the given
+/// normalized coordinates, returning the proper position for each glyph.
+///
+/// See `Font::for_each_point_with_variations()` for the meaning of the coordinates.
+pub fn shape_text_with_variations(font: &Font,
+                                  glyph_mapping: &GlyphMapping,
+                                  string: &str,
+                                  coords: &[f32])
+                                  -> Vec<GlyphPos> {
+    // Variation selectors choose a variant of the preceding character's glyph and don't get
+    // glyphs of their own.
+    let mut glyph_ids = vec![];
+    let mut chars = string.chars().peekable();
     while let Some(ch) = chars.next() {
-        let glyph_id = match next_glyph_id.take() {
+        if is_variation_selector(ch) {
+            continue
+        }
+
+        let mut variant = None;
+        while let Some(&selector) = chars.peek() {
+            if !is_variation_selector(selector) {
+                break
+            }
+            chars.next();
+            if variant.is_none() {
+                variant = font.glyph_for_variation_sequence(ch as u32, selector as u32)
+                              .unwrap_or(None)
+            }
+        }
+
+        glyph_ids.push(match variant {
+            Some(glyph_id) => glyph_id,
             None => glyph_mapping.glyph_for(ch as u32).unwrap_or(0),
-            Some(next_glyph_id) => next_glyph_id,
-        };
+        })
+    }
+
+    // If the substitution tables are malformed, just go with the glyphs we have.
+    let glyph_ids = font.substitute_glyphs(&glyph_ids).unwrap_or(glyph_ids);
+    let attachments = match font.mark_attachments(&glyph_ids) {
+        Ok(attachments) => attachments,
+        Err(_) => vec![None; glyph_ids.len()],
+    };
 
-        let mut advance = match font.metrics_for_glyph(glyph_id) {
+    let mut result = Vec::with_capacity(glyph_ids.len());
+    for (index, &glyph_id) in glyph_ids.iter().enumerate() {
+        let mut advance = match font.metrics_for_glyph_with_variations(glyph_id, coords) {
             Err(_) => 0,
             Ok(metrics) => metrics.advance_width as i16,
         };
 
-        if let Some(&next_char) = chars.peek() {
-            let next_glyph = glyph_mapping.glyph_for(next_char as u32).unwrap_or(0);
-            next_glyph_id = Some(next_glyph);
-            advance += font.kerning_for_glyph_pair(glyph_id, next_glyph)
+        // Kern with the next glyph that isn't a mark, so that accents don't break up kerning
+        // pairs.
+        if font.glyph_class(glyph_id) != Some(GlyphClass::Mark) {
+            let next_glyph_id = glyph_ids[(index + 1)..].iter().find(|&&next_glyph_id| {
+                font.glyph_class(next_glyph_id) != Some(GlyphClass::Mark)
+            });
+            if let Some(&next_glyph_id) = next_glyph_id {
+                advance += font.kerning_for_glyph_pair(glyph_id, next_glyph_id)
+            }
         }
 
         result.push(GlyphPos {
             glyph_id: glyph_id,
             advance: advance,
+            x_offset: 0,
+            y_offset: 0,
         })
     }
 
+    // Position marks relative to the glyphs they attach to. Marks never advance the pen. Since
+    // marks only attach to earlier glyphs, a single forward pass suffices, even for marks stacked
+    // on other marks.
+    for (index, attachment) in attachments.iter().enumerate() {
+        if let Some(attachment) = *attachment {
+            let base = result[attachment.base_index];
+            let distance_from_base: i16 =
+                result[attachment.base_index..index].iter().map(|pos| pos.advance).sum();
+            let mark = &mut result[index];
+            mark.advance = 0;
+            mark.x_offset = base.x_offset + attachment.x_offset - distance_from_base;
+            mark.y_offset = base.y_offset + attachment.y_offset;
+        }
+    }
+
     result
 }
 
+// Returns true if the given character selects a variant of the glyph of the preceding character.
+fn is_variation_selector(ch: char) -> bool {
+    match ch as u32 {
+        0x180b...0x180d | 0x180f | 0xfe00...0xfe0f | 0xe0100...0xe01ef => true,
+        _ => false,
+    }
+}
+
 /// The position of a glyph after shaping.
 #[derive(Clone, Copy, Debug)]
 pub struct GlyphPos {
@@ -61,5 +131,11 @@ pub struct GlyphPos {
     pub glyph_id: u16,
     /// The amount to move the cursor forward *after* emitting this glyph.
     pub advance: i16,
+    /// The horizontal distance to shift this glyph from the cursor position, without moving the
+    /// cursor.
+    pub x_offset: i16,
+    /// The vertical distance to shift this glyph from the cursor position, without moving the
+    /// cursor. Positive values move the glyph up.
+    pub y_offset: i16,
 }
 
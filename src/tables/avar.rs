@@ -0,0 +1,99 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The axis variations table, which adjusts how normalized coordinates map onto the design space.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/avar.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use tables::variations;
+use util::Jump;
+
+pub const TAG: u32 = ((b'a' as u32) << 24) |
+                      ((b'v' as u32) << 16) |
+                      ((b'a' as u32) << 8)  |
+                       (b'r' as u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct AvarTable<'a> {
+    axis_count: u16,
+    table: FontTable<'a>,
+}
+
+impl<'a> AvarTable<'a> {
+    pub fn new(table: FontTable) -> Result<AvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedAvarVersion)
+        }
+
+        try!(reader.jump(mem::size_of::<u16>()).map_err(FontError::eof));
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        Ok(AvarTable {
+            axis_count: axis_count,
+            table: table,
+        })
+    }
+
+    /// Applies the font's segment maps to the given normalized coordinates in place.
+    pub fn map_coords(&self, coords: &mut [f32]) -> Result<(), FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(mem::size_of::<u16>() * 4).map_err(FontError::eof));
+
+        for axis in 0..(self.axis_count as usize) {
+            let position_map_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if let Some(coord) = coords.get_mut(axis) {
+                *coord = try!(map_coord(reader, position_map_count, *coord))
+            }
+
+            try!(reader.jump(mem::size_of::<i16>() * 2 * position_map_count as usize)
+                       .map_err(FontError::eof));
+        }
+
+        Ok(())
+    }
+}
+
+// Maps a coordinate through the segment map that `reader` points to, interpolating linearly
+// between the map's entries.
+fn map_coord(mut reader: &[u8], position_map_count: u16, coord: f32) -> Result<f32, FontError> {
+    let mut prev = None;
+    for _ in 0..position_map_count {
+        let from = try!(variations::read_f2dot14(&mut reader));
+        let to = try!(variations::read_f2dot14(&mut reader));
+        if from == coord {
+            return Ok(to)
+        }
+
+        if from > coord {
+            return match prev {
+                None => Ok(coord + to - from),
+                Some((prev_from, prev_to)) => {
+                    Ok(prev_to + (coord - prev_from) * (to - prev_to) / (from - prev_from))
+                }
+            }
+        }
+
+        prev = Some((from, to))
+    }
+
+    match prev {
+        None => Ok(coord),
+        Some((prev_from, prev_to)) => Ok(coord + prev_to - prev_from),
+    }
+}
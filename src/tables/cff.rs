@@ -13,7 +13,10 @@ use error::FontError;
 use euclid::Point2D;
 use font::{FontTable, Point, PointKind};
 use outline::GlyphBounds;
+use tables::head::HeadTable;
+use tables::variations::ItemVariationStore;
 use std::cmp;
+use std::str;
 use std::u16;
 use util::Jump;
 
@@ -22,485 +25,1701 @@ pub const TAG: u32 = ((b'C' as u32) << 24) |
                       ((b'F' as u32) << 8)  |
                        (b' ' as u32);
 
+// Top DICT operators. Two-byte operators are stored with the escape byte 12 in the low byte.
+const OPERATOR_CHARSET: u16 = 15;
+const OPERATOR_ENCODING: u16 = 16;
+pub const OPERATOR_CHAR_STRINGS: u16 = 17;
+const OPERATOR_PRIVATE: u16 = 18;
+const OPERATOR_FONT_MATRIX: u16 = (7 << 8) | 12;
+pub const OPERATOR_FD_ARRAY: u16 = (36 << 8) | 12;
+pub const OPERATOR_FD_SELECT: u16 = (37 << 8) | 12;
+
+// Charset offsets that instead refer to the predefined charsets.
+const CHARSET_ISO_ADOBE: u32 = 0;
+const CHARSET_EXPERT: u32 = 1;
+const CHARSET_EXPERT_SUBSET: u32 = 2;
+
+// The ISOAdobe charset maps each glyph ID up to this one to the string ID with the same value.
+const ISO_ADOBE_CHARSET_LAST_SID: u16 = 228;
+
+// Encoding offsets that instead refer to the predefined encodings.
+const ENCODING_STANDARD: u32 = 0;
+const ENCODING_EXPERT: u32 = 1;
+
+// Private DICT operators.
+const OPERATOR_SUBRS: u16 = 19;
+const OPERATOR_DEFAULT_WIDTH_X: u16 = 20;
+const OPERATOR_NOMINAL_WIDTH_X: u16 = 21;
+const OPERATOR_VSINDEX: u16 = 22;
+
+// The Type 2 CharString spec limits subroutine nesting to this depth.
+const MAX_SUBR_NESTING_DEPTH: usize = 10;
+
+// The number of elements in the transient array used by the `put` and `get` operators.
+const TRANSIENT_ARRAY_SIZE: usize = 32;
+
+// The maximum depths of the evaluation stack for `CFF ` and `CFF2` CharStrings, respectively.
+const MAX_STACK_SIZE: usize = 48;
+const CFF2_MAX_STACK_SIZE: usize = 513;
+
 #[derive(Clone, Copy, Debug)]
 pub struct CffTable<'a> {
     // The offset of the char strings INDEX.
     char_strings: u32,
+    // The number of glyphs in the font.
+    glyph_count: u16,
+    // The offset of the charset, or the ID of one of the predefined charsets.
+    charset: u32,
+    // The offset of the encoding, or the ID of one of the predefined encodings.
+    encoding: u32,
+    // The offset of the String INDEX.
+    strings: u32,
+    // The offset of the global subroutine INDEX.
+    global_subrs: u32,
+    // The Private DICT of the font, if it isn't CID-keyed.
+    private_dict: Option<PrivateDict<'a>>,
+    // The offsets of the Font DICT INDEX and the FDSelect table, if the font is CID-keyed.
+    fd_array: Option<u32>,
+    fd_select: Option<u32>,
+    // The transform from CharString coordinates to ems, if the font specifies one.
+    font_matrix: Option<[f32; 6]>,
+    // The name of the font in the FontSet.
+    name: &'a [u8],
     table: FontTable<'a>,
 }
 
 impl<'a> CffTable<'a> {
+    /// Reads the first font in the FontSet that the table contains.
+    ///
+    /// The `CFF ` table of an OpenType font always contains exactly one font.
     #[inline]
     pub fn new(table: FontTable) -> Result<CffTable, FontError> {
-        let mut reader = table.bytes;
+        CffTable::from_font_set_index(table, 0)
+    }
+
+    /// Returns the number of fonts in the FontSet that the table contains.
+    pub fn font_count(table: FontTable) -> Result<u16, FontError> {
+        let mut reader = try!(name_index(table.bytes));
+        reader.read_u16::<BigEndian>().map_err(FontError::eof)
+    }
 
-        // Check version.
-        let major = try!(reader.read_u8().map_err(FontError::eof));
-        let minor = try!(reader.read_u8().map_err(FontError::eof));
-        if major != 1 || minor != 0 {
-            return Err(FontError::UnsupportedCffVersion)
+    /// Returns the index of the font with the given name in the FontSet that the table contains.
+    pub fn font_index_for_name(table: FontTable, name: &str) -> Result<u16, FontError> {
+        let reader = try!(name_index(table.bytes));
+        let count = try!((&reader[..]).read_u16::<BigEndian>().map_err(FontError::eof));
+        for index in 0..count {
+            if try!(index_element(reader, index)) == Some(name.as_bytes()) {
+                return Ok(index)
+            }
         }
+        Err(FontError::CffFontNotFound)
+    }
 
-        // Skip the header.
-        let hdr_size = try!(reader.read_u8().map_err(FontError::eof));
-        try!(reader.jump(hdr_size as usize - 3).map_err(FontError::eof));
+    /// Reads the font with the given index from the FontSet that the table contains.
+    pub fn from_font_set_index<'b>(table: FontTable<'b>, index: u16)
+                                   -> Result<CffTable<'b>, FontError> {
+        let mut reader = try!(name_index(table.bytes));
 
-        // Skip the name INDEX.
-        //
-        // TODO(pcwalton): What to do if there are multiple fonts here?
+        // Get the name of our font, and skip the name INDEX.
+        let name = match try!(index_element(reader, index)) {
+            Some(name) => name,
+            None => return Err(FontError::FontIndexOutOfBounds),
+        };
         try!(skip_index(&mut reader));
 
         // Get the top DICT for our font.
-        if try!(find_in_index(&mut reader, 0)).is_none() {
-            return Err(FontError::CffTopDictNotFound)
-        }
+        let top_dict = match try!(index_element(reader, index)) {
+            Some(top_dict) => top_dict,
+            None => return Err(FontError::CffTopDictNotFound),
+        };
+        try!(skip_index(&mut reader));
 
         // Find the CharStrings offset within the top DICT.
-        let char_strings = try!(get_integer_in_dict(&mut reader, 17));
+        let char_strings = try!(get_offset_in_dict(top_dict, OPERATOR_CHAR_STRINGS));
+        let glyph_count = match table.bytes.get(char_strings as usize..) {
+            Some(mut char_strings) => {
+                try!(char_strings.read_u16::<BigEndian>().map_err(FontError::eof))
+            }
+            None => return Err(FontError::UnexpectedEof),
+        };
 
-        // Skip the string INDEX.
+        let charset = match try!(get_operands_in_dict(top_dict, OPERATOR_CHARSET)) {
+            Some(_) => try!(get_offset_in_dict(top_dict, OPERATOR_CHARSET)),
+            None => CHARSET_ISO_ADOBE,
+        };
+        let encoding = match try!(get_operands_in_dict(top_dict, OPERATOR_ENCODING)) {
+            Some(_) => try!(get_offset_in_dict(top_dict, OPERATOR_ENCODING)),
+            None => ENCODING_STANDARD,
+        };
+
+        // Skip the string INDEX. The global subr INDEX follows it.
+        let strings = table.bytes.len() - reader.len();
         try!(skip_index(&mut reader));
+        let global_subrs = table.bytes.len() - reader.len();
+
+        // CID-keyed fonts have a Private DICT per Font DICT, chosen for each glyph by the
+        // FDSelect table. Other fonts have a single Private DICT.
+        let (private_dict, fd_array, fd_select) =
+            match try!(get_operands_in_dict(top_dict, OPERATOR_FD_ARRAY)) {
+                Some(_) => {
+                    (None,
+                     Some(try!(get_offset_in_dict(top_dict, OPERATOR_FD_ARRAY))),
+                     Some(try!(get_offset_in_dict(top_dict, OPERATOR_FD_SELECT))))
+                }
+                None => (try!(PrivateDict::find(table.bytes, top_dict)), None, None),
+            };
 
-        // Ignore the global subr INDEX for now.
-        //
-        // TODO(pcwalton): Support global subroutines.
+        let font_matrix = try!(font_matrix(top_dict));
 
         Ok(CffTable {
-            char_strings: char_strings as u32,
+            char_strings: char_strings,
+            glyph_count: glyph_count,
+            charset: charset,
+            encoding: encoding,
+            strings: strings as u32,
+            global_subrs: global_subrs as u32,
+            private_dict: private_dict,
+            fd_array: fd_array,
+            fd_select: fd_select,
+            font_matrix: font_matrix,
+            name: name,
             table: table,
         })
     }
 
-    pub fn for_each_point<F>(&self, glyph_id: u16, mut callback: F)
+    /// Returns the name of the font within its FontSet, which is usually its PostScript name.
+    #[inline]
+    pub fn font_name(&self) -> Option<&'a str> {
+        str::from_utf8(self.name).ok()
+    }
+
+    /// Returns the number of glyphs in the font.
+    #[inline]
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    pub fn for_each_point<F>(&self, head_table: &HeadTable, glyph_id: u16, mut callback: F)
                              -> Result<(), FontError> where F: FnMut(&Point) {
+        let transform = font_unit_transform(self.font_matrix, head_table);
+        let mut callback = |point: &Point| {
+            match transform {
+                None => callback(point),
+                Some(ref transform) => callback(&transform_point(transform, point)),
+            }
+        };
+
+        let origin = Point2D::new(0.0, 0.0);
+        self.for_each_point_in_char_string(glyph_id, &origin, true, &mut callback).map(drop)
+    }
+
+    /// Returns the advance width of the given glyph as its CharString specifies it, in the units
+    /// of the `head` table.
+    ///
+    /// This should agree with the advance width in the `hmtx` table.
+    pub fn advance_width(&self, head_table: &HeadTable, glyph_id: u16) -> Result<f32, FontError> {
+        let origin = Point2D::new(0.0, 0.0);
+        let width = try!(self.for_each_point_in_char_string(glyph_id, &origin, true, &mut |_| {}));
+        match self.font_matrix {
+            None => Ok(width),
+            Some(ref matrix) => Ok(width * matrix[0] * head_table.units_per_em as f32),
+        }
+    }
+
+    // Runs the CharString for the given glyph, offsetting its outline by `origin`, and returns the
+    // advance width of the glyph.
+    //
+    // `allow_accents` is false when drawing the components of an accented character, which may not
+    // themselves be accented characters.
+    fn for_each_point_in_char_string<F>(&self,
+                                        glyph_id: u16,
+                                        origin: &Point2D<f32>,
+                                        allow_accents: bool,
+                                        callback: &mut F)
+                                        -> Result<f32, FontError> where F: FnMut(&Point) {
         let mut reader = self.table.bytes;
         try!(reader.jump(self.char_strings as usize).map_err(FontError::eof));
 
-        let char_string_length = match try!(find_in_index(&mut reader, glyph_id)) {
-            Some(char_string_length) => char_string_length,
+        let reader = match try!(index_element(reader, glyph_id)) {
+            Some(char_string) => char_string,
             None => return Err(FontError::UnexpectedEof),
         };
 
-        let mut reader = &reader[0..char_string_length as usize];
-        let mut stack = EvaluationStack::new();
-        let (mut start, mut pos) = (Point2D::new(0, 0), Point2D::new(0, 0));
-        let mut index_in_contour = 0;
-        let mut hint_count = 0;
-
-        // FIXME(pcwalton): This shouldn't panic on stack bounds check failures.
-        while let Ok(b0) = reader.read_u8() {
-            match b0 {
-                32...246 => try!(stack.push(b0 as i32 - 139)),
-                247...250 => {
-                    let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                    try!(stack.push((b0 as i32 - 247) * 256 + b1 as i32 + 108))
-                }
-                251...254 => {
-                    let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                    try!(stack.push((b0 as i32 - 251) * -256 - b1 as i32 - 108))
-                }
-                255 => {
-                    // FIXME(pcwalton): Don't truncate the lower 16 bits.
-                    try!(stack.push(try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)) >>
-                         16))
-                }
-                28 => {
-                    let number = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as i32;
-                    try!(stack.push(number))
-                }
+        let (local_subrs, default_width_x, nominal_width_x) =
+            match try!(self.private_dict_for_glyph(glyph_id)) {
+                Some(private_dict) => {
+                    (try!(private_dict.local_subrs()),
+                     try!(private_dict.default_width_x()),
+                     try!(private_dict.nominal_width_x()))
+                }
+                None => (None, 0.0, 0.0),
+            };
+
+        let global_subrs = try!(SubrIndex::new(self.table.bytes, self.global_subrs, false));
+        let local_subrs = match local_subrs {
+            Some(local_subrs) => Some(try!(SubrIndex::new(self.table.bytes, local_subrs, false))),
+            None => None,
+        };
+        let context = CharStringContext {
+            global_subrs: global_subrs,
+            local_subrs: local_subrs,
+            widths: Some((default_width_x, nominal_width_x)),
+            variations: None,
+        };
+        let info = try!(run_char_string(reader, &context, origin, callback));
+
+        // Draw the components of an accented character.
+        if let Some(accent) = info.accent {
+            if allow_accents {
+                let base_glyph_id = try!(self.glyph_id_for_standard_code(accent.base_code));
+                let accent_glyph_id = try!(self.glyph_id_for_standard_code(accent.accent_code));
+                try!(self.for_each_point_in_char_string(base_glyph_id, origin, false, callback));
+
+                let accent_origin = *origin + accent.offset;
+                try!(self.for_each_point_in_char_string(accent_glyph_id,
+                                                        &accent_origin,
+                                                        false,
+                                                        callback));
+            }
+        }
+
+        Ok(info.width.unwrap_or(default_width_x))
+    }
+
+    // Returns the Private DICT that applies to the given glyph, if there is one.
+    fn private_dict_for_glyph(&self, glyph_id: u16)
+                              -> Result<Option<PrivateDict<'a>>, FontError> {
+        let (fd_array, fd_select) = match (self.fd_array, self.fd_select) {
+            (Some(fd_array), Some(fd_select)) => (fd_array, fd_select),
+            _ => return Ok(self.private_dict),
+        };
+
+        let fd_index = try!(fd_index(self.table.bytes, fd_select, glyph_id));
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(fd_array as usize).map_err(FontError::eof));
+        match try!(index_element(reader, fd_index)) {
+            Some(font_dict) => PrivateDict::find(self.table.bytes, font_dict),
+            None => Err(FontError::CffFontDictNotFound),
+        }
+    }
+
+    // Returns the glyph that has the given code in the Standard Encoding.
+    fn glyph_id_for_standard_code(&self, code: f32) -> Result<u16, FontError> {
+        let sid = match STANDARD_ENCODING.get(code as usize) {
+            Some(&sid) if code >= 0.0 && sid != 0 => sid,
+            _ => return Err(FontError::CffGlyphNotFound),
+        };
+        match try!(self.glyph_id_for_sid(sid)) {
+            Some(glyph_id) => Ok(glyph_id),
+            None => Err(FontError::CffGlyphNotFound),
+        }
+    }
+
+    /// Returns the PostScript name of the given glyph, or `None` if the font doesn't name it.
+    ///
+    /// The glyphs of CID-keyed fonts are identified by number rather than by name, so they never
+    /// have names.
+    pub fn glyph_name(&self, glyph_id: u16) -> Result<Option<&'a str>, FontError> {
+        match try!(self.sid_for_glyph_id(glyph_id)) {
+            Some(sid) => self.string(sid),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the ID of the glyph with the given PostScript name, if there is one.
+    pub fn glyph_id_for_name(&self, name: &str) -> Result<Option<u16>, FontError> {
+        match try!(self.sid_for_string(name)) {
+            Some(sid) => self.glyph_id_for_sid(sid),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the glyph that the font's built-in encoding maps the given character code to, if
+    /// any.
+    ///
+    /// CID-keyed fonts don't have built-in encodings.
+    pub fn glyph_id_for_code(&self, code: u8) -> Result<Option<u16>, FontError> {
+        if self.fd_array.is_some() {
+            return Ok(None)
+        }
+
+        let encoding = match self.encoding {
+            ENCODING_STANDARD => &STANDARD_ENCODING,
+            ENCODING_EXPERT => &EXPERT_ENCODING,
+            encoding => return self.glyph_id_for_code_in_custom_encoding(encoding, code),
+        };
+        match encoding[code as usize] {
+            0 => Ok(None),
+            sid => self.glyph_id_for_sid(sid),
+        }
+    }
+
+    // Looks up the given character code in the encoding at the given offset.
+    fn glyph_id_for_code_in_custom_encoding(&self, encoding: u32, code: u8)
+                                            -> Result<Option<u16>, FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(encoding as usize).map_err(FontError::eof));
+
+        // The high bit of the format indicates that supplementary codes follow the encoding
+        // proper.
+        let format = try!(reader.read_u8().map_err(FontError::eof));
+        match format & 0x7f {
+            0 => {
+                // A code per glyph, starting at glyph 1.
+                let code_count = try!(reader.read_u8().map_err(FontError::eof));
+                for glyph_id in 1..(code_count as u16 + 1) {
+                    if try!(reader.read_u8().map_err(FontError::eof)) == code {
+                        return Ok(Some(glyph_id))
+                    }
+                }
+            }
+            1 => {
+                // Ranges of consecutive codes, starting at glyph 1.
+                let range_count = try!(reader.read_u8().map_err(FontError::eof));
+                let mut glyph_id = 1;
+                for _ in 0..range_count {
+                    let first = try!(reader.read_u8().map_err(FontError::eof)) as u32;
+                    let left = try!(reader.read_u8().map_err(FontError::eof)) as u32;
+                    if code as u32 >= first && code as u32 <= first + left {
+                        return Ok(Some((glyph_id + code as u32 - first) as u16))
+                    }
+                    glyph_id += left + 1
+                }
+            }
+            _ => return Err(FontError::UnknownFormat),
+        }
+
+        if format & 0x80 == 0 {
+            return Ok(None)
+        }
+
+        // Each supplement maps an additional code to a glyph by its string ID.
+        let supplement_count = try!(reader.read_u8().map_err(FontError::eof));
+        for _ in 0..supplement_count {
+            let supplement_code = try!(reader.read_u8().map_err(FontError::eof));
+            let sid = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if supplement_code == code {
+                return self.glyph_id_for_sid(sid)
+            }
+        }
+        Ok(None)
+    }
+
+    // Looks up the glyph with the given string ID in the charset.
+    fn glyph_id_for_sid(&self, sid: u16) -> Result<Option<u16>, FontError> {
+        // The charsets of CID-keyed fonts map glyphs to CIDs, not string IDs.
+        if self.fd_array.is_some() {
+            return Ok(None)
+        }
+
+        // The `.notdef` glyph always comes first and isn't in the charset.
+        if sid == 0 {
+            return Ok(Some(0))
+        }
+
+        let glyph_id = match self.charset {
+            CHARSET_ISO_ADOBE if sid <= ISO_ADOBE_CHARSET_LAST_SID => Some(sid as u32),
+            CHARSET_ISO_ADOBE => None,
+            CHARSET_EXPERT => EXPERT_CHARSET.iter().position(|&s| s == sid).map(|g| g as u32),
+            CHARSET_EXPERT_SUBSET => {
+                EXPERT_SUBSET_CHARSET.iter().position(|&s| s == sid).map(|g| g as u32)
+            }
+            _ => {
+                let sid = sid as u32;
+                try!(self.search_charset(|glyph_id, first, count| {
+                    if sid >= first && sid < first + count {
+                        Some(glyph_id + sid - first)
+                    } else {
+                        None
+                    }
+                }))
+            }
+        };
+
+        match glyph_id {
+            Some(glyph_id) if glyph_id < self.glyph_count as u32 => Ok(Some(glyph_id as u16)),
+            _ => Ok(None),
+        }
+    }
+
+    // Looks up the string ID of the given glyph in the charset.
+    fn sid_for_glyph_id(&self, glyph_id: u16) -> Result<Option<u16>, FontError> {
+        if self.fd_array.is_some() || glyph_id >= self.glyph_count {
+            return Ok(None)
+        }
+        if glyph_id == 0 {
+            return Ok(Some(0))
+        }
+
+        match self.charset {
+            CHARSET_ISO_ADOBE if glyph_id <= ISO_ADOBE_CHARSET_LAST_SID => Ok(Some(glyph_id)),
+            CHARSET_ISO_ADOBE => Ok(None),
+            CHARSET_EXPERT => Ok(EXPERT_CHARSET.get(glyph_id as usize).cloned()),
+            CHARSET_EXPERT_SUBSET => Ok(EXPERT_SUBSET_CHARSET.get(glyph_id as usize).cloned()),
+            _ => {
+                let glyph_id = glyph_id as u32;
+                let sid = try!(self.search_charset(|first_glyph_id, first, count| {
+                    if glyph_id >= first_glyph_id && glyph_id < first_glyph_id + count {
+                        Some(first + glyph_id - first_glyph_id)
+                    } else {
+                        None
+                    }
+                }));
+                Ok(sid.map(|sid| sid as u16))
+            }
+        }
+    }
+
+    // Walks the ranges of consecutive string IDs in the font's custom charset, calling `f` with
+    // the first glyph ID, first string ID, and length of each, until `f` returns a value.
+    //
+    // Format 0 stores a string ID per glyph, which we treat as a range of length one.
+    fn search_charset<F>(&self, mut f: F) -> Result<Option<u32>, FontError>
+                         where F: FnMut(u32, u32, u32) -> Option<u32> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.charset as usize).map_err(FontError::eof));
+
+        let format = try!(reader.read_u8().map_err(FontError::eof));
+        if format > 2 {
+            return Err(FontError::UnknownFormat)
+        }
+
+        // The `.notdef` glyph isn't in the charset.
+        let mut glyph_id = 1;
+        while glyph_id < self.glyph_count as u32 {
+            let first = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32;
+            let left = match format {
+                0 => 0,
+                1 => try!(reader.read_u8().map_err(FontError::eof)) as u32,
+                _ => try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32,
+            };
+            if let Some(result) = f(glyph_id, first, left + 1) {
+                return Ok(Some(result))
+            }
+            glyph_id += left + 1
+        }
+        Ok(None)
+    }
+
+    // Returns the string with the given ID. IDs below 391 refer to the standard strings, and the
+    // rest refer to the strings in the String INDEX.
+    fn string(&self, sid: u16) -> Result<Option<&'a str>, FontError> {
+        if let Some(&string) = STANDARD_STRINGS.get(sid as usize) {
+            return Ok(Some(string))
+        }
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.strings as usize).map_err(FontError::eof));
+        match try!(index_element(reader, sid - STANDARD_STRINGS.len() as u16)) {
+            Some(string) => Ok(str::from_utf8(string).ok()),
+            None => Ok(None),
+        }
+    }
+
+    // Returns the ID of the given string, if it's a standard string or in the String INDEX.
+    fn sid_for_string(&self, string: &str) -> Result<Option<u16>, FontError> {
+        if let Some(sid) = STANDARD_STRINGS.iter().position(|&s| s == string) {
+            return Ok(Some(sid as u16))
+        }
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.strings as usize).map_err(FontError::eof));
+        let count = try!((&reader[..]).read_u16::<BigEndian>().map_err(FontError::eof));
+        for index in 0..count {
+            if try!(index_element(reader, index)) == Some(string.as_bytes()) {
+                return Ok(Some(STANDARD_STRINGS.len() as u16 + index))
+            }
+        }
+        Ok(None)
+    }
+
+    // TODO(pcwalton): Do some caching, perhaps?
+    // TODO(pcwalton): Compute this at the same time as `for_each_point`, perhaps?
+    pub fn glyph_bounds(&self, head_table: &HeadTable, glyph_id: u16)
+                        -> Result<GlyphBounds, FontError> {
+        let mut bounds = None;
+        try!(self.for_each_point(head_table, glyph_id, |point| {
+            bounds = Some(add_point_to_bounds(bounds, &point.position))
+        }));
+        Ok(bounds.unwrap_or_default())
+    }
+}
+
+/// Returns the given bounds, if any, grown to include the given point, rounding outward to whole
+/// font units.
+///
+/// Start from `None` rather than from empty bounds, which would stretch to include the origin.
+pub fn add_point_to_bounds(bounds: Option<GlyphBounds>, point: &Point2D<f32>) -> GlyphBounds {
+    let (x, y) = (point.x, point.y);
+    match bounds {
+        None => {
+            GlyphBounds {
+                left: x.floor() as i32,
+                bottom: y.floor() as i32,
+                right: x.ceil() as i32,
+                top: y.ceil() as i32,
+            }
+        }
+        Some(bounds) => {
+            GlyphBounds {
+                left: cmp::min(bounds.left, x.floor() as i32),
+                bottom: cmp::min(bounds.bottom, y.floor() as i32),
+                right: cmp::max(bounds.right, x.ceil() as i32),
+                top: cmp::max(bounds.top, y.ceil() as i32),
+            }
+        }
+    }
+}
+
+/// Returns the transform from CharString coordinates to the units that the `head` table
+/// specifies, or `None` if they're the same.
+///
+/// The FontMatrix maps CharString coordinates to ems. It almost always agrees with the `head`
+/// table, in which case there's nothing to do.
+pub fn font_unit_transform(font_matrix: Option<[f32; 6]>, head_table: &HeadTable)
+                           -> Option<[f32; 6]> {
+    let units_per_em = head_table.units_per_em as f32;
+    font_matrix.map(|matrix| {
+        [matrix[0] * units_per_em, matrix[1] * units_per_em,
+         matrix[2] * units_per_em, matrix[3] * units_per_em,
+         matrix[4] * units_per_em, matrix[5] * units_per_em]
+    }).and_then(|transform| {
+        let identity = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        if transform.iter().zip(identity.iter()).all(|(a, b)| (a - b).abs() < 1e-4) {
+            None
+        } else {
+            Some(transform)
+        }
+    })
+}
+
+/// Applies a transform returned by `font_unit_transform` to a point.
+pub fn transform_point(transform: &[f32; 6], point: &Point) -> Point {
+    let (m, p) = (transform, point.position);
+    Point {
+        position: Point2D::new(m[0] * p.x + m[2] * p.y + m[4], m[1] * p.x + m[3] * p.y + m[5]),
+        ..*point
+    }
+}
+
+/// Reads the FontMatrix from the given Top DICT, if it has one.
+pub fn font_matrix(top_dict: &[u8]) -> Result<Option<[f32; 6]>, FontError> {
+    match try!(get_operands_in_dict(top_dict, OPERATOR_FONT_MATRIX)) {
+        Some(ref matrix) if matrix.len() == 6 => {
+            Ok(Some([matrix[0] as f32, matrix[1] as f32,
+                     matrix[2] as f32, matrix[3] as f32,
+                     matrix[4] as f32, matrix[5] as f32]))
+        }
+        _ => Ok(None),
+    }
+}
+
+// Checks the version of the CFF table and returns a reader positioned at its Name INDEX, which
+// follows the header.
+fn name_index(table: &[u8]) -> Result<&[u8], FontError> {
+    let mut reader = table;
+
+    // Check version.
+    let major = try!(reader.read_u8().map_err(FontError::eof));
+    let minor = try!(reader.read_u8().map_err(FontError::eof));
+    if major != 1 || minor != 0 {
+        return Err(FontError::UnsupportedCffVersion)
+    }
+
+    // Skip the header.
+    let hdr_size = try!(reader.read_u8().map_err(FontError::eof));
+    try!(reader.jump(hdr_size as usize - 3).map_err(FontError::eof));
+    Ok(reader)
+}
+
+// Moves the reader to the location of the given element in the index. Returns the length of the
+// element if the element was found or `None` otherwise.
+fn find_in_index(reader: &mut &[u8], index: u16) -> Result<Option<u32>, FontError> {
+    let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    find_in_index_with_count(reader, count as u32, index as u32)
+}
+
+// Like `find_in_index`, but for the INDEX format of `CFF2` tables, which has a 32-bit count.
+fn find_in_cff2_index(reader: &mut &[u8], index: u32) -> Result<Option<u32>, FontError> {
+    let count = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+    find_in_index_with_count(reader, count, index)
+}
+
+// Moves the reader, which points just past the count of an INDEX, to the location of the given
+// element in the INDEX.
+fn find_in_index_with_count(reader: &mut &[u8], count: u32, index: u32)
+                            -> Result<Option<u32>, FontError> {
+    if count == 0 {
+        return Ok(None)
+    }
+
+    let off_size = try!(reader.read_u8().map_err(FontError::eof));
+
+    let mut offset_reader = *reader;
+    try!(offset_reader.jump(off_size as usize * cmp::min(index, count) as usize)
+                      .map_err(FontError::eof));
+    let offset = try!(read_offset(&mut offset_reader, off_size));
+
+    let next_offset = if index < count {
+        Some(try!(read_offset(&mut offset_reader, off_size)) - offset)
+    } else {
+        None
+    };
+
+    try!(reader.jump(off_size as usize * (count as usize + 1) + offset as usize - 1)
+               .map_err(FontError::eof));
+    return Ok(next_offset)
+}
+
+// A Private DICT, which holds the hinting parameters and local subroutines for a font or, in
+// CID-keyed fonts, for a group of glyphs.
+#[derive(Clone, Copy, Debug)]
+pub struct PrivateDict<'a> {
+    // The offset of the DICT from the start of the CFF table.
+    offset: u32,
+    dict: &'a [u8],
+}
+
+impl<'a> PrivateDict<'a> {
+    /// Finds the Private DICT that the given Top DICT or Font DICT points to.
+    pub fn find(table: &'a [u8], dict: &[u8]) -> Result<Option<PrivateDict<'a>>, FontError> {
+        // The operands are the size and offset of the Private DICT.
+        let operands = match try!(get_operands_in_dict(dict, OPERATOR_PRIVATE)) {
+            None => return Ok(None),
+            Some(operands) => operands,
+        };
+        if operands.len() != 2 || operands[0] < 0.0 || operands[1] < 0.0 {
+            return Err(FontError::CffBadOffset)
+        }
+
+        let (size, offset) = (operands[0] as usize, operands[1] as usize);
+        match table.get(offset..(offset + size)) {
+            Some(private_dict) => {
+                Ok(Some(PrivateDict {
+                    offset: offset as u32,
+                    dict: private_dict,
+                }))
+            }
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+
+    /// Returns the offset of the local subr INDEX from the start of the CFF table, if there is
+    /// one. The DICT stores it relative to the start of the Private DICT.
+    pub fn local_subrs(&self) -> Result<Option<u32>, FontError> {
+        match try!(get_operands_in_dict(self.dict, OPERATOR_SUBRS)) {
+            None => Ok(None),
+            Some(_) => {
+                let subrs = try!(get_offset_in_dict(self.dict, OPERATOR_SUBRS));
+                Ok(Some(self.offset + subrs))
+            }
+        }
+    }
+
+    // Returns the width of glyphs whose CharStrings don't specify one.
+    fn default_width_x(&self) -> Result<f32, FontError> {
+        get_number_in_dict(self.dict, OPERATOR_DEFAULT_WIDTH_X, 0.0).map(|width| width as f32)
+    }
+
+    // Returns the width that the widths in CharStrings are relative to.
+    fn nominal_width_x(&self) -> Result<f32, FontError> {
+        get_number_in_dict(self.dict, OPERATOR_NOMINAL_WIDTH_X, 0.0).map(|width| width as f32)
+    }
+
+    /// Returns the index of the item variation data that the `blend` operator uses by default, in
+    /// `CFF2` tables.
+    pub fn vsindex(&self) -> Result<u16, FontError> {
+        get_number_in_dict(self.dict, OPERATOR_VSINDEX, 0.0).map(|vsindex| vsindex as u16)
+    }
+}
+
+// Returns the given element of the INDEX that `reader` points to, or `None` if there is no such
+// element.
+fn index_element(mut reader: &[u8], index: u16) -> Result<Option<&[u8]>, FontError> {
+    let length = try!(find_in_index(&mut reader, index));
+    index_element_at(reader, length)
+}
+
+/// Returns the given element of the `CFF2` INDEX that `reader` points to, or `None` if there is
+/// no such element.
+pub fn cff2_index_element(mut reader: &[u8], index: u32) -> Result<Option<&[u8]>, FontError> {
+    let length = try!(find_in_cff2_index(&mut reader, index));
+    index_element_at(reader, length)
+}
+
+// Returns the element with the given length that `reader` points to.
+fn index_element_at(reader: &[u8], length: Option<u32>) -> Result<Option<&[u8]>, FontError> {
+    match length {
+        None => Ok(None),
+        Some(length) => {
+            match reader.get(0..(length as usize)) {
+                Some(element) => Ok(Some(element)),
+                None => Err(FontError::UnexpectedEof),
+            }
+        }
+    }
+}
+
+// Skips over an INDEX by reading the last element in the offset array and seeking the appropriate
+// number of bytes forward.
+fn skip_index(reader: &mut &[u8]) -> Result<(), FontError> {
+    find_in_index(reader, u16::MAX).map(drop)
+}
+
+/// Looks up the index of the Font DICT for the given glyph in the FDSelect table at the given
+/// offset.
+pub fn fd_index(table: &[u8], fd_select: u32, glyph_id: u16) -> Result<u16, FontError> {
+    let mut reader = table;
+    try!(reader.jump(fd_select as usize).map_err(FontError::eof));
+
+    let format = try!(reader.read_u8().map_err(FontError::eof));
+    match format {
+        0 => {
+            // One Font DICT index per glyph.
+            try!(reader.jump(glyph_id as usize).map_err(FontError::eof));
+            Ok(try!(reader.read_u8().map_err(FontError::eof)) as u16)
+        }
+        3 => {
+            // Ranges of glyphs, followed by a sentinel glyph ID that ends the last range.
+            let range_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let mut first = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            for _ in 0..range_count {
+                let fd_index = try!(reader.read_u8().map_err(FontError::eof));
+                let next = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                if glyph_id >= first && glyph_id < next {
+                    return Ok(fd_index as u16)
+                }
+                first = next
+            }
+            Err(FontError::CffFontDictNotFound)
+        }
+        4 => {
+            // Like format 3, but with wider fields. Only `CFF2` tables use this format.
+            let range_count = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let mut first = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            for _ in 0..range_count {
+                let fd_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let next = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+                if glyph_id as u32 >= first && (glyph_id as u32) < next {
+                    return Ok(fd_index)
+                }
+                first = next
+            }
+            Err(FontError::CffFontDictNotFound)
+        }
+        _ => Err(FontError::UnknownFormat),
+    }
+}
+
+/// A Subrs or Global Subrs INDEX.
+#[derive(Clone, Copy, Debug)]
+pub struct SubrIndex<'a> {
+    // The INDEX, starting at its count.
+    index: &'a [u8],
+    // True if the INDEX is in the `CFF2` format.
+    cff2: bool,
+}
+
+impl<'a> SubrIndex<'a> {
+    /// Returns the subroutine INDEX at the given offset in the table.
+    pub fn new(table: &'a [u8], offset: u32, cff2: bool) -> Result<SubrIndex<'a>, FontError> {
+        match table.get(offset as usize..) {
+            Some(index) => {
+                Ok(SubrIndex {
+                    index: index,
+                    cff2: cff2,
+                })
+            }
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+
+    // Returns the subroutine with the given number.
+    //
+    // Subroutine numbers are biased so that small operands can reach as many of them as possible.
+    fn subr(&self, number: i32) -> Result<&'a [u8], FontError> {
+        let mut reader = self.index;
+        let count = if self.cff2 {
+            try!(reader.read_u32::<BigEndian>().map_err(FontError::eof))
+        } else {
+            try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32
+        };
+        let bias = if count < 1240 {
+            107
+        } else if count < 33900 {
+            1131
+        } else {
+            32768
+        };
+
+        let index = number + bias;
+        if index < 0 || index as u32 >= count {
+            return Err(FontError::CffSubrNotFound)
+        }
+
+        let subr = if self.cff2 {
+            try!(cff2_index_element(self.index, index as u32))
+        } else {
+            try!(index_element(self.index, index as u16))
+        };
+        match subr {
+            Some(subr) => Ok(subr),
+            None => Err(FontError::CffSubrNotFound),
+        }
+    }
+}
+
+/// The font-wide data that a CharString refers to.
+pub struct CharStringContext<'a, 'b> {
+    pub global_subrs: SubrIndex<'a>,
+    pub local_subrs: Option<SubrIndex<'a>>,
+    /// The default and nominal widths from the Private DICT. `CFF2` CharStrings have no widths.
+    pub widths: Option<(f32, f32)>,
+    /// What the `blend` operator needs, for `CFF2` CharStrings.
+    pub variations: Option<CharStringVariations<'a, 'b>>,
+}
+
+/// The variation data and instance that `CFF2` CharStrings are evaluated with.
+pub struct CharStringVariations<'a, 'b> {
+    pub store: ItemVariationStore<'a>,
+    /// The item variation data that `blend` uses until a `vsindex` operator says otherwise.
+    pub vsindex: u16,
+    /// The normalized coordinates of the instance.
+    pub coords: &'b [f32],
+}
+
+/// What running a CharString found out besides the outline.
+pub struct CharStringInfo {
+    /// The advance width of the glyph, if the CharString specified one.
+    pub width: Option<f32>,
+    /// The components of an accented character, if the CharString is one.
+    pub accent: Option<AccentedCharacter>,
+}
+
+/// An accented character that a CharString draws by combining two glyphs, in the manner of the
+/// Type 1 `seac` operator.
+pub struct AccentedCharacter {
+    /// The offset of the accent from the origin of the base glyph.
+    pub offset: Point2D<f32>,
+    /// The codes of the base glyph and the accent in the Standard Encoding.
+    pub base_code: f32,
+    pub accent_code: f32,
+}
+
+// Returns the last operand of the given operator in the DICT, which must be an integer.
+fn get_integer_in_dict(dict: &[u8], operator: u16) -> Result<i32, FontError> {
+    match try!(get_operands_in_dict(dict, operator)) {
+        Some(ref operands) if !operands.is_empty() => Ok(operands[operands.len() - 1] as i32),
+        _ => Err(FontError::CffIntegerNotFound),
+    }
+}
+
+// Returns the last operand of the given operator in the DICT, or the given default value if the
+// DICT doesn't contain the operator.
+fn get_number_in_dict(dict: &[u8], operator: u16, default: f64) -> Result<f64, FontError> {
+    match try!(get_operands_in_dict(dict, operator)) {
+        None => Ok(default),
+        Some(ref operands) if !operands.is_empty() => Ok(operands[operands.len() - 1]),
+        Some(_) => Err(FontError::CffIntegerNotFound),
+    }
+}
+
+/// Returns the nonnegative offset that is the operand of the given operator in the DICT.
+pub fn get_offset_in_dict(dict: &[u8], operator: u16) -> Result<u32, FontError> {
+    let offset = try!(get_integer_in_dict(dict, operator));
+    if offset < 0 {
+        return Err(FontError::CffBadOffset)
+    }
+    Ok(offset as u32)
+}
+
+/// Returns the operands of the given operator in the DICT, or `None` if the DICT doesn't contain
+/// the operator.
+///
+/// Integers and real numbers are both returned as `f64`, which represents all of them exactly.
+pub fn get_operands_in_dict(mut reader: &[u8], operator: u16)
+                        -> Result<Option<Vec<f64>>, FontError> {
+    let mut operands = vec![];
+    while let Ok(b0) = reader.read_u8() {
+        match b0 {
+            32...246 => operands.push((b0 as i32 - 139) as f64),
+            247...250 => {
+                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                operands.push(((b0 as i32 - 247) * 256 + b1 as i32 + 108) as f64)
+            }
+            251...254 => {
+                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                operands.push((-(b0 as i32 - 251) * 256 - b1 as i32 - 108) as f64)
+            }
+            28 => {
+                operands.push(try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as f64)
+            }
+            29 => {
+                operands.push(try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)) as f64)
+            }
+            30 => operands.push(try!(read_real_in_dict(&mut reader))),
+            12 => {
+                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                if operator == (((b1 as u16) << 8) | (b0 as u16)) {
+                    return Ok(Some(operands))
+                }
+                operands.clear()
+            }
+            _ => {
+                if operator == b0 as u16 {
+                    return Ok(Some(operands))
+                }
+                operands.clear()
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// Runs the given CharString, calling `callback` for each point of its outline, offset by `origin`.
+//
+// The `CFF ` and `CFF2` tables share this interpreter; the context holds what differs between
+// them.
+pub fn run_char_string<'a, F>(mut reader: &'a [u8],
+                              context: &CharStringContext<'a, 'a>,
+                              origin: &Point2D<f32>,
+                              callback: &mut F)
+                              -> Result<CharStringInfo, FontError> where F: FnMut(&Point) {
+    // The width of the glyph. This is only known once the header of the CharString has been read.
+    let mut width = None;
+
+    // The scalars of the variation regions that `blend` applies, for the current `vsindex`.
+    let mut scalars = match context.variations {
+        Some(ref variations) => {
+            try!(variations.store.region_scalars(variations.vsindex, variations.coords))
+        }
+        None => vec![],
+    };
+
+    let mut stack = EvaluationStack::new(if context.variations.is_some() {
+        CFF2_MAX_STACK_SIZE
+    } else {
+        MAX_STACK_SIZE
+    });
+
+    let (mut start, mut pos) = (*origin, *origin);
+    let mut index_in_contour = 0;
+    let mut hint_count = 0;
+
+    // The remainder of each CharString or subroutine that called the one being executed.
+    let mut callers = Vec::new();
+
+    // Storage for the `put` and `get` operators.
+    let mut transient_array = [0.0; TRANSIENT_ARRAY_SIZE];
+
+    // FIXME(pcwalton): This shouldn't panic on stack bounds check failures.
+    loop {
+        // Running off the end of a subroutine returns to its caller.
+        let b0 = match reader.read_u8() {
+            Ok(b0) => b0,
+            Err(_) => {
+                match callers.pop() {
+                    Some(caller) => {
+                        reader = caller;
+                        continue
+                    }
+                    None => break,
+                }
+            }
+        };
+
+        // The first stack-clearing operator of the CharString may have an extra operand at
+        // the bottom of the stack: the difference between the width of the glyph and the
+        // nominal width. If there's no such operand, the glyph has the default width.
+        if let (None, Some((default_width_x, nominal_width_x))) = (width, context.widths) {
+            let has_width = match b0 {
+                // hstem, vstem, hstemhm, vstemhm, hintmask, cntrmask
+                1 | 3 | 18 | 23 | 19 | 20 => Some(stack.size % 2 == 1),
+                // rmoveto
+                21 => Some(stack.size > 2),
+                // vmoveto, hmoveto
+                4 | 22 => Some(stack.size > 1),
+                // endchar
+                14 => Some(stack.size == 1 || stack.size == 5),
+                _ => None,
+            };
+            match has_width {
+                Some(true) => width = Some(nominal_width_x + try!(stack.shift())),
+                Some(false) => width = Some(default_width_x),
+                None => {}
+            }
+        }
+
+        match b0 {
+            32...246 => try!(stack.push((b0 as i32 - 139) as f32)),
+            247...250 => {
+                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                try!(stack.push(((b0 as i32 - 247) * 256 + b1 as i32 + 108) as f32))
+            }
+            251...254 => {
+                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                try!(stack.push(((b0 as i32 - 251) * -256 - b1 as i32 - 108) as f32))
+            }
+            255 => {
+                // A 16.16 fixed-point number.
+                let number = try!(reader.read_i32::<BigEndian>().map_err(FontError::eof));
+                try!(stack.push(number as f32 / 65536.0))
+            }
+            28 => {
+                let number = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+                try!(stack.push(number as f32))
+            }
 
-                4 => {
-                    // |- dy1 vmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos.y += stack.array[0] as i16;
+            4 => {
+                // |- dy1 vmoveto
+                close_path_if_necessary(&start, index_in_contour, &mut *callback);
+                pos.y += stack.array[0];
+                callback(&Point {
+                    position: pos,
+                    index_in_contour: 0,
+                    kind: PointKind::OnCurve,
+                });
+                start = pos;
+                index_in_contour = 1;
+                stack.clear()
+            }
+            5 => {
+                // |- {dxa dya}+ rlineto
+                for points in stack.array[0..stack.size as usize].chunks(2) {
+                    pos = pos + Point2D::new(points[0], points[1]);
                     callback(&Point {
                         position: pos,
-                        index_in_contour: 0,
+                        index_in_contour: index_in_contour,
                         kind: PointKind::OnCurve,
                     });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                    index_in_contour += 1
                 }
-                5 => {
-                    // |- {dxa dya}+ rlineto
-                    for points in stack.array[0..stack.size as usize].chunks(2) {
-                        pos = pos + Point2D::new(points[0] as i16, points[1] as i16);
-                        callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
-                            kind: PointKind::OnCurve,
-                        });
-                        index_in_contour += 1
+                stack.clear()
+            }
+            6 => {
+                // |- dx1 {dya dxb}* hlineto
+                // |- {dxa dyb}* hlineto
+                for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
+                    if i % 2 == 0 {
+                        pos.x += *length
+                    } else {
+                        pos.y += *length
                     }
-                    stack.clear()
+                    callback(&Point {
+                        position: pos,
+                        index_in_contour: index_in_contour,
+                        kind: PointKind::OnCurve,
+                    });
+                    index_in_contour += 1
                 }
-                6 => {
-                    // |- dx1 {dya dxb}* hlineto
-                    // |- {dxa dyb}* hlineto
-                    for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
-                        if i % 2 == 0 {
-                            pos.x += *length as i16
-                        } else {
-                            pos.y += *length as i16
-                        }
-                        callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
-                            kind: PointKind::OnCurve,
-                        });
-                        index_in_contour += 1
+                stack.clear()
+            }
+            7 => {
+                // |- dy1 {dxa dyb}* vlineto
+                // |- {dya dxb}* vlineto
+                for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
+                    if i % 2 == 0 {
+                        pos.y += *length
+                    } else {
+                        pos.x += *length
                     }
-                    stack.clear()
+                    callback(&Point {
+                        position: pos,
+                        index_in_contour: index_in_contour,
+                        kind: PointKind::OnCurve,
+                    });
+                    index_in_contour += 1
                 }
-                7 => {
-                    // |- dy1 {dxa dyb}* vlineto
-                    // |- {dya dxb}* vlineto
-                    for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
-                        if i % 2 == 0 {
-                            pos.y += *length as i16
-                        } else {
-                            pos.x += *length as i16
-                        }
-                        callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
-                            kind: PointKind::OnCurve,
-                        });
-                        index_in_contour += 1
-                    }
-                    stack.clear()
+                stack.clear()
+            }
+            8 => {
+                // |- {dxa dya dxb dyb dxc dyc}+ rrcurveto (8)
+                for chunk in stack.array[0..stack.size as usize].chunks(6) {
+                    add_curve(chunk[0], chunk[1],
+                              chunk[2], chunk[3],
+                              chunk[4], chunk[5],
+                              &mut pos,
+                              &mut index_in_contour,
+                              &mut *callback)
                 }
-                8 => {
-                    // |- {dxa dya dxb dyb dxc dyc}+ rrcurveto (8)
-                    for chunk in stack.array[0..stack.size as usize].chunks(6) {
-                        add_curve(chunk[0] as i16, chunk[1] as i16,
-                                  chunk[2] as i16, chunk[3] as i16,
-                                  chunk[4] as i16, chunk[5] as i16,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
-                    }
-                    stack.clear()
+                stack.clear()
+            }
+            24 => {
+                // |- {dxa dya dxb dyb dxc dyc}+ dxd dyd rcurveline (24)
+                for chunk in stack.array[0..stack.size as usize - 2].chunks(6) {
+                    add_curve(chunk[0], chunk[1],
+                              chunk[2], chunk[3],
+                              chunk[4], chunk[5],
+                              &mut pos,
+                              &mut index_in_contour,
+                              &mut *callback)
                 }
-                24 => {
-                    // |- {dxa dya dxb dyb dxc dyc}+ dxd dyd rcurveline (24)
-                    for chunk in stack.array[0..stack.size as usize - 2].chunks(6) {
-                        add_curve(chunk[0] as i16, chunk[1] as i16,
-                                  chunk[2] as i16, chunk[3] as i16,
-                                  chunk[4] as i16, chunk[5] as i16,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
-                    }
-                    pos = pos + Point2D::new(stack.array[stack.size as usize - 2] as i16,
-                                             stack.array[stack.size as usize - 1] as i16);
+                pos = pos + Point2D::new(stack.array[stack.size as usize - 2],
+                                         stack.array[stack.size as usize - 1]);
+                callback(&Point {
+                    position: pos,
+                    index_in_contour: index_in_contour,
+                    kind: PointKind::OnCurve,
+                });
+                index_in_contour += 1;
+                stack.clear()
+            }
+            25 => {
+                // |- {dxa dya}+ dxb dyb dxc dyc dxd dyd rlinecurve (25)
+                for chunk in stack.array[0..stack.size as usize - 6].chunks(2) {
+                    pos = pos + Point2D::new(chunk[0], chunk[1]);
                     callback(&Point {
                         position: pos,
                         index_in_contour: index_in_contour,
                         kind: PointKind::OnCurve,
                     });
                     index_in_contour += 1;
-                    stack.clear()
                 }
-                25 => {
-                    // |- {dxa dya}+ dxb dyb dxc dyc dxd dyd rlinecurve (25)
-                    for chunk in stack.array[0..stack.size as usize - 6].chunks(2) {
-                        pos = pos + Point2D::new(chunk[0] as i16, chunk[1] as i16);
-                        callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
-                            kind: PointKind::OnCurve,
-                        });
-                        index_in_contour += 1;
+                add_curve(stack.array[stack.size as usize - 6],
+                          stack.array[stack.size as usize - 5],
+                          stack.array[stack.size as usize - 4],
+                          stack.array[stack.size as usize - 3],
+                          stack.array[stack.size as usize - 2],
+                          stack.array[stack.size as usize - 1],
+                          &mut pos,
+                          &mut index_in_contour,
+                          &mut *callback);
+                stack.clear()
+            }
+            30 => {
+                // |- dy1 dx2 dy2 dx3 {dxa dxb dyb dyc dyd dxe dye dxf}* dyf? vhcurveto (30)
+                // |- {dya dxb dyb dxc dxd dxe dye dyf}+ dxf? vhcurveto (30)
+                for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
+                    if chunk.len() != 4 {
+                        break
                     }
-                    add_curve(stack.array[stack.size as usize - 6] as i16,
-                              stack.array[stack.size as usize - 5] as i16,
-                              stack.array[stack.size as usize - 4] as i16,
-                              stack.array[stack.size as usize - 3] as i16,
-                              stack.array[stack.size as usize - 2] as i16,
-                              stack.array[stack.size as usize - 1] as i16,
-                              &mut pos,
-                              &mut index_in_contour,
-                              &mut callback);
-                    stack.clear()
-                }
-                30 => {
-                    // |- dy1 dx2 dy2 dx3 {dxa dxb dyb dyc dyd dxe dye dxf}* dyf? vhcurveto (30)
-                    // |- {dya dxb dyb dxc dxd dxe dye dyf}+ dxf? vhcurveto (30)
-                    for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
-                        if chunk.len() != 4 {
-                            break
-                        }
 
-                        let dxyf = if i * 4 + 5 == stack.size as usize {
-                            stack.array[stack.size as usize - 1]
-                        } else {
-                            0
-                        };
+                    let dxyf = if i * 4 + 5 == stack.size as usize {
+                        stack.array[stack.size as usize - 1]
+                    } else {
+                        0.0
+                    };
 
-                        if i % 2 == 0 {
-                            add_curve(0, chunk[0] as i16,
-                                      chunk[1] as i16, chunk[2] as i16,
-                                      chunk[3] as i16, dxyf as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
-                        } else {
-                            add_curve(chunk[0] as i16, 0,
-                                      chunk[1] as i16, chunk[2] as i16,
-                                      dxyf as i16, chunk[3] as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
-                        }
+                    if i % 2 == 0 {
+                        add_curve(0.0, chunk[0],
+                                  chunk[1], chunk[2],
+                                  chunk[3], dxyf,
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback)
+                    } else {
+                        add_curve(chunk[0], 0.0,
+                                  chunk[1], chunk[2],
+                                  dxyf, chunk[3],
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback)
                     }
-                    stack.clear()
                 }
-                31 => {
-                    // |- dx1 dx2 dy2 dy3 {dya dxb dyb dxc dxd dxe dye dyf}* dxf? hvcurveto (31)
-                    // |- {dxa dxb dyb dyc dyd dxe dye dxf}+ dyf? hvcurveto (31)
-                    for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
-                        if chunk.len() != 4 {
-                            break
-                        }
-
-                        let dxyf = if i * 4 + 5 == stack.size as usize {
-                            stack.array[stack.size as usize - 1]
-                        } else {
-                            0
-                        };
-
-                        if i % 2 == 0 {
-                            add_curve(chunk[0] as i16, 0,
-                                      chunk[1] as i16, chunk[2] as i16,
-                                      dxyf as i16, chunk[3] as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
-                        } else {
-                            add_curve(0, chunk[0] as i16,
-                                      chunk[1] as i16, chunk[2] as i16,
-                                      chunk[3] as i16, dxyf as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
-                        }
+                stack.clear()
+            }
+            31 => {
+                // |- dx1 dx2 dy2 dy3 {dya dxb dyb dxc dxd dxe dye dyf}* dxf? hvcurveto (31)
+                // |- {dxa dxb dyb dyc dyd dxe dye dxf}+ dyf? hvcurveto (31)
+                for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
+                    if chunk.len() != 4 {
+                        break
                     }
-                    stack.clear()
-                }
-                26 => {
-                    // |- dx1? {dya dxb dyb dyc}+ vvcurveto (26)
-                    let start;
-                    if stack.size % 2 == 0 {
-                        start = 0
+
+                    let dxyf = if i * 4 + 5 == stack.size as usize {
+                        stack.array[stack.size as usize - 1]
                     } else {
-                        pos.x += stack.array[0] as i16;
-                        start = 1
-                    }
+                        0.0
+                    };
 
-                    for chunk in stack.array[start..stack.size as usize].chunks(4) {
-                        add_curve(0, chunk[0] as i16,
-                                  chunk[1] as i16, chunk[2] as i16,
-                                  0, chunk[3] as i16,
+                    if i % 2 == 0 {
+                        add_curve(chunk[0], 0.0,
+                                  chunk[1], chunk[2],
+                                  dxyf, chunk[3],
                                   &mut pos,
                                   &mut index_in_contour,
-                                  &mut callback)
-                    }
-                    stack.clear()
-                }
-                27 => {
-                    // |- dy1? {dxa dxb dyb dxc}+ hhcurveto (27)
-                    let start;
-                    if stack.size % 2 == 0 {
-                        start = 0
+                                  &mut *callback)
                     } else {
-                        pos.y += stack.array[0] as i16;
-                        start = 1
-                    }
-
-                    for chunk in stack.array[start..stack.size as usize].chunks(4) {
-                        add_curve(chunk[0] as i16, 0,
-                                  chunk[1] as i16, chunk[2] as i16,
-                                  chunk[3] as i16, 0,
+                        add_curve(0.0, chunk[0],
+                                  chunk[1], chunk[2],
+                                  chunk[3], dxyf,
                                   &mut pos,
                                   &mut index_in_contour,
-                                  &mut callback)
+                                  &mut *callback)
                     }
-                    stack.clear()
                 }
-                14 => {
-                    // endchar
-                    break
-                }
-                1 | 18 => {
-                    // hstem hint (ignored)
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear()
+                stack.clear()
+            }
+            26 => {
+                // |- dx1? {dya dxb dyb dyc}+ vvcurveto (26)
+                let start;
+                if stack.size % 2 == 0 {
+                    start = 0
+                } else {
+                    pos.x += stack.array[0];
+                    start = 1
                 }
-                3 | 23 => {
-                    // vstem hint (ignored)
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear()
+
+                for chunk in stack.array[start..stack.size as usize].chunks(4) {
+                    add_curve(0.0, chunk[0],
+                              chunk[1], chunk[2],
+                              0.0, chunk[3],
+                              &mut pos,
+                              &mut index_in_contour,
+                              &mut *callback)
                 }
-                19 => {
-                    // hintmask (ignored)
-                    //
-                    // First, process an implicit vstem hint.
-                    //
-                    // FIXME(pcwalton): Should only do that if we're in the header.
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear();
-
-                    // Now skip ⌈hint_count / 8⌉ bytes.
-                    let hint_byte_count = (hint_count as usize + 7) / 8;
-                    try!(reader.jump(hint_byte_count).map_err(FontError::eof));
+                stack.clear()
+            }
+            27 => {
+                // |- dy1? {dxa dxb dyb dxc}+ hhcurveto (27)
+                let start;
+                if stack.size % 2 == 0 {
+                    start = 0
+                } else {
+                    pos.y += stack.array[0];
+                    start = 1
                 }
-                20 => {
-                    // Skip ⌈hint_count / 8⌉ bytes.
-                    stack.clear();
-                    let hint_byte_count = (hint_count as usize + 7) / 8;
-                    try!(reader.jump(hint_byte_count).map_err(FontError::eof));
+
+                for chunk in stack.array[start..stack.size as usize].chunks(4) {
+                    add_curve(chunk[0], 0.0,
+                              chunk[1], chunk[2],
+                              chunk[3], 0.0,
+                              &mut pos,
+                              &mut index_in_contour,
+                              &mut *callback)
                 }
-                21 => {
-                    // |- dx1 dy1 rmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos = pos + Point2D::new(stack.array[0] as i16, stack.array[1] as i16);
-                    callback(&Point {
-                        position: pos,
-                        index_in_contour: 0,
-                        kind: PointKind::OnCurve,
-                    });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                stack.clear()
+            }
+            14 => {
+                // endchar
+                // adx ady bchar achar endchar
+                //
+                // The second form draws an accented character out of the base and accent
+                // glyphs with the given Standard Encoding codes, like the Type 1 `seac`
+                // operator. The accent is offset by `adx` and `ady`.
+                if stack.size == 4 {
+                    close_path_if_necessary(&start, index_in_contour, &mut *callback);
+
+                    let args = &stack.array;
+                    return Ok(CharStringInfo {
+                        width: width,
+                        accent: Some(AccentedCharacter {
+                            offset: Point2D::new(args[0], args[1]),
+                            base_code: args[2],
+                            accent_code: args[3],
+                        }),
+                    })
                 }
-                22 => {
-                    // |- dx1 hmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos.x += stack.array[0] as i16;
-                    callback(&Point {
-                        position: pos,
-                        index_in_contour: 0,
-                        kind: PointKind::OnCurve,
-                    });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                break
+            }
+            10 | 29 => {
+                // subr# callsubr (10)
+                // globalsubr# callgsubr (29)
+                if callers.len() >= MAX_SUBR_NESTING_DEPTH {
+                    return Err(FontError::CffSubrNestingTooDeep)
                 }
 
-                12 => {
-                    // TODO(pcwalton): Support these extended operators.
-                    let _operator = (12 << 8) |
-                        (try!(reader.read_u8().map_err(FontError::eof)) as u32);
-                    stack.clear();
-                    return Err(FontError::CffUnimplementedOperator)
+                let subrs = if b0 == 10 {
+                    match context.local_subrs {
+                        Some(ref local_subrs) => local_subrs,
+                        None => return Err(FontError::CffSubrNotFound),
+                    }
+                } else {
+                    &context.global_subrs
+                };
+
+                let subr = try!(subrs.subr(try!(stack.pop()) as i32));
+                callers.push(reader);
+                reader = subr
+            }
+            15 if context.variations.is_some() => {
+                // ivs vsindex (15)
+                let vsindex = try!(stack.pop());
+                if let Some(ref variations) = context.variations {
+                    scalars = try!(variations.store.region_scalars(vsindex as u16,
+                                                                   variations.coords))
                 }
-                _ => {
-                    stack.clear();
-                    return Err(FontError::CffUnimplementedOperator)
+                stack.clear()
+            }
+            16 if context.variations.is_some() => {
+                // num(0)...num(n-1), delta(0,0)...delta(k-1,0), ...,
+                // delta(0,n-1)...delta(k-1,n-1) n blend (16) value(0)...value(n-1)
+                //
+                // Each value is its default plus its deltas, one per region, scaled by how much
+                // the region applies.
+                //
+                // Malformed CharStrings can ask for absurdly many values, so guard the product.
+                let (n, k) = (try!(stack.pop()) as usize, scalars.len());
+                let operand_count = match n.checked_mul(k + 1) {
+                    Some(operand_count) if operand_count <= stack.size as usize => operand_count,
+                    _ => return Err(FontError::CffStackUnderflow),
+                };
+                let first = stack.size as usize - operand_count;
+                for i in 0..n {
+                    let deltas = first + n + i * k;
+                    let mut value = stack.array[first + i];
+                    for (j, scalar) in scalars.iter().enumerate() {
+                        value += stack.array[deltas + j] * *scalar
+                    }
+                    stack.array[first + i] = value
                 }
+                stack.size = (first + n) as u16
             }
-        }
-
-        close_path_if_necessary(&start, index_in_contour, &mut callback);
-        Ok(())
-    }
-
-    // TODO(pcwalton): Do some caching, perhaps?
-    // TODO(pcwalton): Compute this at the same time as `for_each_point`, perhaps?
-    pub fn glyph_bounds(&self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
-        let mut bounds = GlyphBounds::default();
-        try!(self.for_each_point(glyph_id, |point| {
-            bounds.left = cmp::min(bounds.left, point.position.x as i32);
-            bounds.bottom = cmp::min(bounds.bottom, point.position.y as i32);
-            bounds.right = cmp::max(bounds.right, point.position.x as i32);
-            bounds.top = cmp::max(bounds.top, point.position.y as i32);
-        }));
-        Ok(bounds)
-    }
-}
-
-// Moves the reader to the location of the given element in the index. Returns the length of the
-// element if the element was found or `None` otherwise.
-fn find_in_index(reader: &mut &[u8], index: u16) -> Result<Option<u32>, FontError> {
-    let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-    if count == 0 {
-        return Ok(None)
-    }
-
-    let off_size = try!(reader.read_u8().map_err(FontError::eof));
-
-    let mut offset_reader = *reader;
-    try!(offset_reader.jump(off_size as usize * cmp::min(index, count) as usize)
-                      .map_err(FontError::eof));
-    let offset = try!(read_offset(&mut offset_reader, off_size));
-
-    let next_offset = if index < count {
-        Some(try!(read_offset(&mut offset_reader, off_size)) - offset)
-    } else {
-        None
-    };
-
-    try!(reader.jump(off_size as usize * (count as usize + 1) + offset as usize - 1)
-               .map_err(FontError::eof));
-    return Ok(next_offset)
-}
-
-// Skips over an INDEX by reading the last element in the offset array and seeking the appropriate
-// number of bytes forward.
-fn skip_index(reader: &mut &[u8]) -> Result<(), FontError> {
-    find_in_index(reader, u16::MAX).map(drop)
-}
-
-// Returns the integer with the given operator.
-fn get_integer_in_dict(reader: &mut &[u8], operator: u16) -> Result<i32, FontError> {
-    let mut last_integer_operand = None;
-    loop {
-        let b0 = try!(reader.read_u8().map_err(FontError::eof));
-        match b0 {
-            32...246 => last_integer_operand = Some(b0 as i32 - 139),
-            247...250 => {
-                let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                last_integer_operand = Some((b0 as i32 - 247) * 256 + b1 as i32 + 108)
+            11 => {
+                // return
+                match callers.pop() {
+                    Some(caller) => reader = caller,
+                    None => break,
+                }
             }
-            251...254 => {
-                let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                last_integer_operand = Some(-(b0 as i32 - 251) * 256 - b1 as i32 - 108)
+            1 | 18 => {
+                // hstem hint (ignored)
+                hint_count += stack.size as u16 / 2;
+                stack.clear()
             }
-            28 => {
-                last_integer_operand =
-                    Some(try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as i32)
+            3 | 23 => {
+                // vstem hint (ignored)
+                hint_count += stack.size as u16 / 2;
+                stack.clear()
             }
-            29 => {
-                last_integer_operand =
-                    Some(try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)) as i32)
+            19 => {
+                // hintmask (ignored)
+                //
+                // First, process an implicit vstem hint.
+                hint_count += stack.size as u16 / 2;
+                stack.clear();
+
+                // Now skip ⌈hint_count / 8⌉ bytes.
+                let hint_byte_count = (hint_count as usize + 7) / 8;
+                try!(reader.jump(hint_byte_count).map_err(FontError::eof));
             }
-            30 => {
-                // TODO(pcwalton): Real numbers.
-                while (try!(reader.read_u8().map_err(FontError::eof)) & 0xf) != 0xf {}
+            20 => {
+                // Skip ⌈hint_count / 8⌉ bytes.
+                stack.clear();
+                let hint_byte_count = (hint_count as usize + 7) / 8;
+                try!(reader.jump(hint_byte_count).map_err(FontError::eof));
             }
+            21 => {
+                // |- dx1 dy1 rmoveto
+                close_path_if_necessary(&start, index_in_contour, &mut *callback);
+                pos = pos + Point2D::new(stack.array[0], stack.array[1]);
+                callback(&Point {
+                    position: pos,
+                    index_in_contour: 0,
+                    kind: PointKind::OnCurve,
+                });
+                start = pos;
+                index_in_contour = 1;
+                stack.clear()
+            }
+            22 => {
+                // |- dx1 hmoveto
+                close_path_if_necessary(&start, index_in_contour, &mut *callback);
+                pos.x += stack.array[0];
+                callback(&Point {
+                    position: pos,
+                    index_in_contour: 0,
+                    kind: PointKind::OnCurve,
+                });
+                start = pos;
+                index_in_contour = 1;
+                stack.clear()
+            }
+
             12 => {
                 let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                if operator == (((b1 as u16) << 8) | (b0 as u16)) {
-                    match last_integer_operand {
-                        Some(last_integer_operand) => return Ok(last_integer_operand),
-                        None => return Err(FontError::CffIntegerNotFound),
+                match b1 {
+                    35 => {
+                        // |- dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 dx6 dy6 fd flex (12 35)
+                        //
+                        // We always draw flexes as curves, so the flex depth is ignored.
+                        if stack.size < 13 {
+                            return Err(FontError::CffStackUnderflow)
+                        }
+                        for chunk in stack.array[0..12].chunks(6) {
+                            add_curve(chunk[0], chunk[1],
+                                      chunk[2], chunk[3],
+                                      chunk[4], chunk[5],
+                                      &mut pos,
+                                      &mut index_in_contour,
+                                      &mut *callback)
+                        }
+                        stack.clear()
+                    }
+                    34 => {
+                        // |- dx1 dx2 dy2 dx3 dx4 dx5 dx6 hflex (12 34)
+                        if stack.size < 7 {
+                            return Err(FontError::CffStackUnderflow)
+                        }
+                        let args = &stack.array;
+                        add_curve(args[0], 0.0,
+                                  args[1], args[2],
+                                  args[3], 0.0,
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        add_curve(args[4], 0.0,
+                                  args[5], -args[2],
+                                  args[6], 0.0,
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        stack.clear()
+                    }
+                    36 => {
+                        // |- dx1 dy1 dx2 dy2 dx3 dx4 dx5 dy5 dx6 hflex1 (12 36)
+                        if stack.size < 9 {
+                            return Err(FontError::CffStackUnderflow)
+                        }
+                        let args = &stack.array;
+                        add_curve(args[0], args[1],
+                                  args[2], args[3],
+                                  args[4], 0.0,
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        add_curve(args[5], 0.0,
+                                  args[6], args[7],
+                                  args[8], -(args[1] + args[3] + args[7]),
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        stack.clear()
+                    }
+                    37 => {
+                        // |- dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 d6 flex1 (12 37)
+                        //
+                        // The last point returns to the starting point along whichever axis
+                        // the flex travels least along.
+                        if stack.size < 11 {
+                            return Err(FontError::CffStackUnderflow)
+                        }
+                        let args = &stack.array;
+                        let (mut dx, mut dy) = (0.0, 0.0);
+                        for point in args[0..10].chunks(2) {
+                            dx += point[0];
+                            dy += point[1];
+                        }
+                        let (dx6, dy6) = if dx.abs() > dy.abs() {
+                            (args[10], -dy)
+                        } else {
+                            (-dx, args[10])
+                        };
+                        add_curve(args[0], args[1],
+                                  args[2], args[3],
+                                  args[4], args[5],
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        add_curve(args[6], args[7],
+                                  args[8], args[9],
+                                  dx6, dy6,
+                                  &mut pos,
+                                  &mut index_in_contour,
+                                  &mut *callback);
+                        stack.clear()
+                    }
+                    0 => {
+                        // dotsection (deprecated; ignored)
+                        stack.clear()
                     }
+                    _ => try!(execute_escaped_operator(b1, &mut stack, &mut transient_array)),
                 }
-                last_integer_operand = None
             }
             _ => {
-                if operator == b0 as u16 {
-                    match last_integer_operand {
-                        Some(last_integer_operand) => return Ok(last_integer_operand),
-                        None => return Err(FontError::CffIntegerNotFound),
-                    }
+                stack.clear();
+                return Err(FontError::CffUnimplementedOperator)
+            }
+        }
+    }
+
+    close_path_if_necessary(&start, index_in_contour, &mut *callback);
+    Ok(CharStringInfo {
+        width: width,
+        accent: None,
+    })
+}
+
+// Executes one of the arithmetic, storage, and conditional operators that are prefixed with the
+// escape byte 12. None of these affect the outline.
+fn execute_escaped_operator(operator: u8,
+                            stack: &mut EvaluationStack,
+                            transient_array: &mut [f32; TRANSIENT_ARRAY_SIZE])
+                            -> Result<(), FontError> {
+    match operator {
+        3 => {
+            // num1 num2 and (12 3) num3
+            let (num1, num2) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(boolean(num1 != 0.0 && num2 != 0.0))
+        }
+        4 => {
+            // num1 num2 or (12 4) num3
+            let (num1, num2) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(boolean(num1 != 0.0 || num2 != 0.0))
+        }
+        5 => {
+            // num1 not (12 5) num2
+            let num1 = try!(stack.pop());
+            stack.push(boolean(num1 == 0.0))
+        }
+        9 => {
+            // num abs (12 9) num2
+            let num = try!(stack.pop());
+            stack.push(num.abs())
+        }
+        10 => {
+            // num1 num2 add (12 10) sum
+            let (num2, num1) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(num1 + num2)
+        }
+        11 => {
+            // num1 num2 sub (12 11) difference
+            let (num2, num1) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(num1 - num2)
+        }
+        12 => {
+            // num1 num2 div (12 12) quotient
+            let (num2, num1) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(if num2 == 0.0 { 0.0 } else { num1 / num2 })
+        }
+        14 => {
+            // num neg (12 14) num2
+            let num = try!(stack.pop());
+            stack.push(-num)
+        }
+        15 => {
+            // num1 num2 eq (12 15) num3
+            let (num1, num2) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(boolean(num1 == num2))
+        }
+        18 => {
+            // num drop (12 18)
+            stack.pop().map(drop)
+        }
+        20 => {
+            // val i put (12 20)
+            let (i, val) = (try!(stack.pop()), try!(stack.pop()));
+            if i >= 0.0 {
+                if let Some(element) = transient_array.get_mut(i as usize) {
+                    *element = val
+                }
+            }
+            Ok(())
+        }
+        21 => {
+            // i get (12 21) val
+            let i = try!(stack.pop());
+            let val = if i >= 0.0 {
+                transient_array.get(i as usize).cloned().unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            stack.push(val)
+        }
+        22 => {
+            // s1 s2 v1 v2 ifelse (12 22) s1_or_s2
+            let (v2, v1) = (try!(stack.pop()), try!(stack.pop()));
+            let (s2, s1) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(if v1 <= v2 { s1 } else { s2 })
+        }
+        23 => {
+            // random (12 23) num2
+            //
+            // The result must be in the range (0, 1]; since the outline mustn't depend on it, we
+            // just pick the top of that range.
+            stack.push(1.0)
+        }
+        24 => {
+            // num1 num2 mul (12 24) product
+            let (num2, num1) = (try!(stack.pop()), try!(stack.pop()));
+            stack.push(num1 * num2)
+        }
+        26 => {
+            // num sqrt (12 26) num2
+            let num = try!(stack.pop());
+            stack.push(num.max(0.0).sqrt())
+        }
+        27 => {
+            // any dup (12 27) any any
+            let any = try!(stack.pop());
+            try!(stack.push(any));
+            stack.push(any)
+        }
+        28 => {
+            // num1 num2 exch (12 28) num2 num1
+            let (num2, num1) = (try!(stack.pop()), try!(stack.pop()));
+            try!(stack.push(num2));
+            stack.push(num1)
+        }
+        29 => {
+            // num(N-1) ... num(0) i index (12 29) num(N-1) ... num(0) num(i)
+            //
+            // Negative indices copy the top element.
+            let i = cmp::max(try!(stack.pop()) as i32, 0) as usize;
+            if i >= stack.size as usize {
+                return Err(FontError::CffStackUnderflow)
+            }
+            let element = stack.array[stack.size as usize - 1 - i];
+            stack.push(element)
+        }
+        30 => {
+            // num(N-1) ... num(0) N J roll (12 30) num((J-1) mod N) ... num(0) num(N-1) ...
+            // num(J mod N)
+            let (j, n) = (try!(stack.pop()) as i32, try!(stack.pop()) as i32);
+            if n < 0 || n > stack.size as i32 {
+                return Err(FontError::CffStackUnderflow)
+            }
+            if n > 0 {
+                let start = stack.size as usize - n as usize;
+                let elements = stack.array[start..(stack.size as usize)].to_vec();
+                let shift = (((j % n) + n) % n) as usize;
+                for (i, &element) in elements.iter().enumerate() {
+                    stack.array[start + (i + shift) % n as usize] = element
                 }
-                last_integer_operand = None
+            }
+            Ok(())
+        }
+        _ => {
+            stack.clear();
+            Err(FontError::CffUnimplementedOperator)
+        }
+    }
+}
+
+// Converts a boolean to the number that the conditional operators push for it.
+#[inline]
+fn boolean(value: bool) -> f32 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+// Reads a real number operand from a DICT. The reader must point just past the initial byte 30.
+//
+// Real numbers are stored as strings of 4-bit nibbles, each of which is a decimal digit or one of
+// a few other characters. The string ends with the nibble 0xf.
+fn read_real_in_dict(reader: &mut &[u8]) -> Result<f64, FontError> {
+    let mut string = String::new();
+    'bytes: loop {
+        let byte = try!(reader.read_u8().map_err(FontError::eof));
+        for &nibble in &[byte >> 4, byte & 0xf] {
+            match nibble {
+                0...9 => string.push((b'0' + nibble) as char),
+                0xa => string.push('.'),
+                0xb => string.push('E'),
+                0xc => string.push_str("E-"),
+                0xe => string.push('-'),
+                0xf => break 'bytes,
+                _ => return Err(FontError::CffBadRealNumber),
             }
         }
     }
+
+    string.parse().map_err(|_| FontError::CffBadRealNumber)
 }
 
 // Reads an Offset with the given size.
@@ -520,20 +1739,23 @@ fn read_offset(reader: &mut &[u8], size: u8) -> Result<u32, FontError> {
 
 // The CFF evaluation stack used during CharString reading.
 struct EvaluationStack {
-    array: [i32; 48],
-    size: u8,
+    array: [f32; CFF2_MAX_STACK_SIZE],
+    size: u16,
+    // The number of elements the stack may hold, which depends on the CharString format.
+    limit: u16,
 }
 
 impl EvaluationStack {
-    fn new() -> EvaluationStack {
+    fn new(limit: usize) -> EvaluationStack {
         EvaluationStack {
-            array: [0; 48],
+            array: [0.0; CFF2_MAX_STACK_SIZE],
             size: 0,
+            limit: limit as u16,
         }
     }
 
-    fn push(&mut self, value: i32) -> Result<(), FontError> {
-        if (self.size as usize) < self.array.len() {
+    fn push(&mut self, value: f32) -> Result<(), FontError> {
+        if self.size < self.limit {
             self.array[self.size as usize] = value;
             self.size += 1;
             Ok(())
@@ -542,12 +1764,33 @@ impl EvaluationStack {
         }
     }
 
+    fn pop(&mut self) -> Result<f32, FontError> {
+        if self.size == 0 {
+            return Err(FontError::CffStackUnderflow)
+        }
+        self.size -= 1;
+        Ok(self.array[self.size as usize])
+    }
+
+    // Removes the element at the bottom of the stack and returns it.
+    fn shift(&mut self) -> Result<f32, FontError> {
+        if self.size == 0 {
+            return Err(FontError::CffStackUnderflow)
+        }
+        let value = self.array[0];
+        for i in 1..(self.size as usize) {
+            self.array[i - 1] = self.array[i]
+        }
+        self.size -= 1;
+        Ok(value)
+    }
+
     fn clear(&mut self) {
         self.size = 0
     }
 }
 
-fn close_path_if_necessary<F>(start: &Point2D<i16>, index_in_contour: u16, mut callback: F)
+fn close_path_if_necessary<F>(start: &Point2D<f32>, index_in_contour: u16, mut callback: F)
                               where F: FnMut(&Point) {
     if index_in_contour == 0 {
         // No path to close.
@@ -561,10 +1804,10 @@ fn close_path_if_necessary<F>(start: &Point2D<i16>, index_in_contour: u16, mut c
     });
 }
 
-fn add_curve<F>(dx0: i16, dy0: i16,
-                dx1: i16, dy1: i16,
-                dx2: i16, dy2: i16,
-                pos: &mut Point2D<i16>,
+fn add_curve<F>(dx0: f32, dy0: f32,
+                dx1: f32, dy1: f32,
+                dx2: f32, dy2: f32,
+                pos: &mut Point2D<f32>,
                 index_in_contour: &mut u16,
                 mut callback: F)
                 where F: FnMut(&Point) {
@@ -595,3 +1838,124 @@ fn add_curve<F>(dx0: i16, dy0: i16,
     *index_in_contour += 3
 }
 
+
+// Maps each character code in the Standard Encoding to its string ID.
+static STANDARD_ENCODING: [u16; 256] = [
+    // 0x00
+    0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    // 0x20
+    1,   2,   3,   4,   5,   6,   7,   8,   9,   10,  11,  12,  13,  14,  15,  16,
+    17,  18,  19,  20,  21,  22,  23,  24,  25,  26,  27,  28,  29,  30,  31,  32,
+    // 0x40
+    33,  34,  35,  36,  37,  38,  39,  40,  41,  42,  43,  44,  45,  46,  47,  48,
+    49,  50,  51,  52,  53,  54,  55,  56,  57,  58,  59,  60,  61,  62,  63,  64,
+    // 0x60
+    65,  66,  67,  68,  69,  70,  71,  72,  73,  74,  75,  76,  77,  78,  79,  80,
+    81,  82,  83,  84,  85,  86,  87,  88,  89,  90,  91,  92,  93,  94,  95,  0,
+    // 0x80
+    0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    // 0xa0
+    0,   96,  97,  98,  99,  100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110,
+    0,   111, 112, 113, 114, 0,   115, 116, 117, 118, 119, 120, 121, 122, 0,   123,
+    // 0xc0
+    0,   124, 125, 126, 127, 128, 129, 130, 131, 0,   132, 133, 0,   134, 135, 136,
+    137, 0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    // 0xe0
+    0,   138, 0,   139, 0,   0,   0,   0,   140, 141, 142, 143, 0,   0,   0,   0,
+    0,   144, 0,   0,   0,   145, 0,   0,   146, 147, 148, 149, 0,   0,   0,   0,
+];
+
+// The strings that string IDs below 391 refer to.
+static STANDARD_STRINGS: [&'static str; 391] = [
+    ".notdef", "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand",
+    "quoteright", "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period",
+    "slash", "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "colon", "semicolon", "less", "equal", "greater", "question", "at", "A", "B", "C", "D", "E",
+    "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X",
+    "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore", "quoteleft",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s",
+    "t", "u", "v", "w", "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "exclamdown",
+    "cent", "sterling", "fraction", "yen", "florin", "section", "currency", "quotesingle",
+    "quotedblleft", "guillemotleft", "guilsinglleft", "guilsinglright", "fi", "fl", "endash",
+    "dagger", "daggerdbl", "periodcentered", "paragraph", "bullet", "quotesinglbase",
+    "quotedblbase", "quotedblright", "guillemotright", "ellipsis", "perthousand", "questiondown",
+    "grave", "acute", "circumflex", "tilde", "macron", "breve", "dotaccent", "dieresis", "ring",
+    "cedilla", "hungarumlaut", "ogonek", "caron", "emdash", "AE", "ordfeminine", "Lslash", "Oslash",
+    "OE", "ordmasculine", "ae", "dotlessi", "lslash", "oslash", "oe", "germandbls", "onesuperior",
+    "logicalnot", "mu", "trademark", "Eth", "onehalf", "plusminus", "Thorn", "onequarter", "divide",
+    "brokenbar", "degree", "thorn", "threequarters", "twosuperior", "registered", "minus", "eth",
+    "multiply", "threesuperior", "copyright", "Aacute", "Acircumflex", "Adieresis", "Agrave",
+    "Aring", "Atilde", "Ccedilla", "Eacute", "Ecircumflex", "Edieresis", "Egrave", "Iacute",
+    "Icircumflex", "Idieresis", "Igrave", "Ntilde", "Oacute", "Ocircumflex", "Odieresis", "Ograve",
+    "Otilde", "Scaron", "Uacute", "Ucircumflex", "Udieresis", "Ugrave", "Yacute", "Ydieresis",
+    "Zcaron", "aacute", "acircumflex", "adieresis", "agrave", "aring", "atilde", "ccedilla",
+    "eacute", "ecircumflex", "edieresis", "egrave", "iacute", "icircumflex", "idieresis", "igrave",
+    "ntilde", "oacute", "ocircumflex", "odieresis", "ograve", "otilde", "scaron", "uacute",
+    "ucircumflex", "udieresis", "ugrave", "yacute", "ydieresis", "zcaron", "exclamsmall",
+    "Hungarumlautsmall", "dollaroldstyle", "dollarsuperior", "ampersandsmall", "Acutesmall",
+    "parenleftsuperior", "parenrightsuperior", "twodotenleader", "onedotenleader", "zerooldstyle",
+    "oneoldstyle", "twooldstyle", "threeoldstyle", "fouroldstyle", "fiveoldstyle", "sixoldstyle",
+    "sevenoldstyle", "eightoldstyle", "nineoldstyle", "commasuperior", "threequartersemdash",
+    "periodsuperior", "questionsmall", "asuperior", "bsuperior", "centsuperior", "dsuperior",
+    "esuperior", "isuperior", "lsuperior", "msuperior", "nsuperior", "osuperior", "rsuperior",
+    "ssuperior", "tsuperior", "ff", "ffi", "ffl", "parenleftinferior", "parenrightinferior",
+    "Circumflexsmall", "hyphensuperior", "Gravesmall", "Asmall", "Bsmall", "Csmall", "Dsmall",
+    "Esmall", "Fsmall", "Gsmall", "Hsmall", "Ismall", "Jsmall", "Ksmall", "Lsmall", "Msmall",
+    "Nsmall", "Osmall", "Psmall", "Qsmall", "Rsmall", "Ssmall", "Tsmall", "Usmall", "Vsmall",
+    "Wsmall", "Xsmall", "Ysmall", "Zsmall", "colonmonetary", "onefitted", "rupiah", "Tildesmall",
+    "exclamdownsmall", "centoldstyle", "Lslashsmall", "Scaronsmall", "Zcaronsmall", "Dieresissmall",
+    "Brevesmall", "Caronsmall", "Dotaccentsmall", "Macronsmall", "figuredash", "hypheninferior",
+    "Ogoneksmall", "Ringsmall", "Cedillasmall", "questiondownsmall", "oneeighth", "threeeighths",
+    "fiveeighths", "seveneighths", "onethird", "twothirds", "zerosuperior", "foursuperior",
+    "fivesuperior", "sixsuperior", "sevensuperior", "eightsuperior", "ninesuperior", "zeroinferior",
+    "oneinferior", "twoinferior", "threeinferior", "fourinferior", "fiveinferior", "sixinferior",
+    "seveninferior", "eightinferior", "nineinferior", "centinferior", "dollarinferior",
+    "periodinferior", "commainferior", "Agravesmall", "Aacutesmall", "Acircumflexsmall",
+    "Atildesmall", "Adieresissmall", "Aringsmall", "AEsmall", "Ccedillasmall", "Egravesmall",
+    "Eacutesmall", "Ecircumflexsmall", "Edieresissmall", "Igravesmall", "Iacutesmall",
+    "Icircumflexsmall", "Idieresissmall", "Ethsmall", "Ntildesmall", "Ogravesmall", "Oacutesmall",
+    "Ocircumflexsmall", "Otildesmall", "Odieresissmall", "OEsmall", "Oslashsmall", "Ugravesmall",
+    "Uacutesmall", "Ucircumflexsmall", "Udieresissmall", "Yacutesmall", "Thornsmall",
+    "Ydieresissmall", "001.000", "001.001", "001.002", "001.003", "Black", "Bold", "Book", "Light",
+    "Medium", "Regular", "Roman", "Semibold",
+];
+
+// Maps each character code in the Expert Encoding to its string ID.
+static EXPERT_ENCODING: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 229, 230, 0, 231, 232, 233, 234, 235, 236, 237, 238, 13, 14, 15, 99, 239, 240, 241, 242, 243,
+    244, 245, 246, 247, 248, 27, 28, 249, 250, 251, 252, 0, 253, 254, 255, 256, 257, 0, 0, 0, 258,
+    0, 0, 259, 260, 261, 262, 0, 0, 263, 264, 265, 0, 266, 109, 110, 267, 268, 269, 0, 270, 271,
+    272, 273, 274, 275, 276, 277, 278, 279, 280, 281, 282, 283, 284, 285, 286, 287, 288, 289, 290,
+    291, 292, 293, 294, 295, 296, 297, 298, 299, 300, 301, 302, 303, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 304, 305, 306, 0, 0,
+    307, 308, 309, 310, 311, 0, 312, 0, 0, 313, 0, 0, 314, 315, 0, 0, 316, 317, 318, 0, 0, 0, 158,
+    155, 163, 319, 320, 321, 322, 323, 324, 325, 0, 0, 326, 150, 164, 169, 327, 328, 329, 330, 331,
+    332, 333, 334, 335, 336, 337, 338, 339, 340, 341, 342, 343, 344, 345, 346, 347, 348, 349, 350,
+    351, 352, 353, 354, 355, 356, 357, 358, 359, 360, 361, 362, 363, 364, 365, 366, 367, 368, 369,
+    370, 371, 372, 373, 374, 375, 376, 377, 378,
+];
+
+// The string IDs of the glyphs in the predefined Expert charset.
+static EXPERT_CHARSET: [u16; 166] = [
+    0, 1, 229, 230, 231, 232, 233, 234, 235, 236, 237, 238, 13, 14, 15, 99, 239, 240, 241, 242, 243,
+    244, 245, 246, 247, 248, 27, 28, 249, 250, 251, 252, 253, 254, 255, 256, 257, 258, 259, 260,
+    261, 262, 263, 264, 265, 266, 109, 110, 267, 268, 269, 270, 271, 272, 273, 274, 275, 276, 277,
+    278, 279, 280, 281, 282, 283, 284, 285, 286, 287, 288, 289, 290, 291, 292, 293, 294, 295, 296,
+    297, 298, 299, 300, 301, 302, 303, 304, 305, 306, 307, 308, 309, 310, 311, 312, 313, 314, 315,
+    316, 317, 318, 158, 155, 163, 319, 320, 321, 322, 323, 324, 325, 326, 150, 164, 169, 327, 328,
+    329, 330, 331, 332, 333, 334, 335, 336, 337, 338, 339, 340, 341, 342, 343, 344, 345, 346, 347,
+    348, 349, 350, 351, 352, 353, 354, 355, 356, 357, 358, 359, 360, 361, 362, 363, 364, 365, 366,
+    367, 368, 369, 370, 371, 372, 373, 374, 375, 376, 377, 378,
+];
+
+// The string IDs of the glyphs in the predefined Expert Subset charset.
+static EXPERT_SUBSET_CHARSET: [u16; 87] = [
+    0, 1, 231, 232, 235, 236, 237, 238, 13, 14, 15, 99, 239, 240, 241, 242, 243, 244, 245, 246, 247,
+    248, 27, 28, 249, 250, 251, 253, 254, 255, 256, 257, 258, 259, 260, 261, 262, 263, 264, 265,
+    266, 109, 110, 267, 268, 269, 270, 272, 300, 301, 302, 305, 314, 315, 158, 155, 163, 320, 321,
+    322, 323, 324, 325, 326, 150, 164, 169, 327, 328, 329, 330, 331, 332, 333, 334, 335, 336, 337,
+    338, 339, 340, 341, 342, 343, 344, 345, 346,
+];
@@ -0,0 +1,183 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `CFF2` table, which holds the PostScript outlines of variable fonts.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/cff2.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use euclid::Point2D;
+use font::{FontTable, Point};
+use outline::GlyphBounds;
+use std::mem;
+use tables::cff::{self, CharStringContext, CharStringVariations, PrivateDict, SubrIndex};
+use tables::head::HeadTable;
+use tables::variations::ItemVariationStore;
+use util::Jump;
+
+pub const TAG: u32 = ((b'C' as u32) << 24) |
+                      ((b'F' as u32) << 16) |
+                      ((b'F' as u32) << 8)  |
+                       (b'2' as u32);
+
+// Top DICT operators that only `CFF2` tables have.
+const OPERATOR_VSTORE: u16 = 24;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Cff2Table<'a> {
+    // The offset of the char strings INDEX.
+    char_strings: u32,
+    // The offset of the global subroutine INDEX.
+    global_subrs: u32,
+    // The offset of the Font DICT INDEX.
+    fd_array: u32,
+    // The offset of the FDSelect table. Fonts with only one Font DICT may omit it.
+    fd_select: Option<u32>,
+    // The deltas that the `blend` operator applies, if the font has any.
+    variation_store: Option<ItemVariationStore<'a>>,
+    // The transform from CharString coordinates to ems, if the font specifies one.
+    font_matrix: Option<[f32; 6]>,
+    table: FontTable<'a>,
+}
+
+impl<'a> Cff2Table<'a> {
+    pub fn new(table: FontTable) -> Result<Cff2Table, FontError> {
+        let mut reader = table.bytes;
+
+        // Check version.
+        let major = try!(reader.read_u8().map_err(FontError::eof));
+        let _minor = try!(reader.read_u8().map_err(FontError::eof));
+        if major != 2 {
+            return Err(FontError::UnsupportedCffVersion)
+        }
+
+        // Unlike in `CFF ` tables, the Top DICT isn't in an INDEX; it directly follows the header,
+        // and the global subr INDEX directly follows it.
+        let hdr_size = try!(reader.read_u8().map_err(FontError::eof)) as usize;
+        let top_dict_length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as usize;
+        let top_dict = match table.bytes.get(hdr_size..(hdr_size + top_dict_length)) {
+            Some(top_dict) => top_dict,
+            None => return Err(FontError::CffTopDictNotFound),
+        };
+        let global_subrs = hdr_size + top_dict_length;
+
+        let char_strings = try!(cff::get_offset_in_dict(top_dict, cff::OPERATOR_CHAR_STRINGS));
+        let fd_array = try!(cff::get_offset_in_dict(top_dict, cff::OPERATOR_FD_ARRAY));
+        let fd_select = match try!(cff::get_operands_in_dict(top_dict, cff::OPERATOR_FD_SELECT)) {
+            Some(_) => Some(try!(cff::get_offset_in_dict(top_dict, cff::OPERATOR_FD_SELECT))),
+            None => None,
+        };
+
+        // The variation store is preceded by its length.
+        let variation_store = match try!(cff::get_operands_in_dict(top_dict, OPERATOR_VSTORE)) {
+            None => None,
+            Some(_) => {
+                let offset = try!(cff::get_offset_in_dict(top_dict, OPERATOR_VSTORE)) as usize;
+                match table.bytes.get((offset + mem::size_of::<u16>())..) {
+                    Some(variation_store) => Some(try!(ItemVariationStore::new(variation_store))),
+                    None => return Err(FontError::UnexpectedEof),
+                }
+            }
+        };
+
+        Ok(Cff2Table {
+            char_strings: char_strings,
+            global_subrs: global_subrs as u32,
+            fd_array: fd_array,
+            fd_select: fd_select,
+            variation_store: variation_store,
+            font_matrix: try!(cff::font_matrix(top_dict)),
+            table: table,
+        })
+    }
+
+    /// Calls `callback` with each point of the outline of the given glyph at the instance with
+    /// the given normalized coordinates.
+    ///
+    /// Pass empty coordinates for the default instance.
+    pub fn for_each_point<F>(&self,
+                             head_table: &HeadTable,
+                             glyph_id: u16,
+                             coords: &[f32],
+                             mut callback: F)
+                             -> Result<(), FontError> where F: FnMut(&Point) {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.char_strings as usize).map_err(FontError::eof));
+        let char_string = match try!(cff::cff2_index_element(reader, glyph_id as u32)) {
+            Some(char_string) => char_string,
+            None => return Err(FontError::UnexpectedEof),
+        };
+
+        let private_dict = try!(self.private_dict_for_glyph(glyph_id));
+        let local_subrs = match try!(private_dict.local_subrs()) {
+            Some(local_subrs) => Some(try!(SubrIndex::new(self.table.bytes, local_subrs, true))),
+            None => None,
+        };
+        let variations = match self.variation_store {
+            Some(store) => {
+                Some(CharStringVariations {
+                    store: store,
+                    vsindex: try!(private_dict.vsindex()),
+                    coords: coords,
+                })
+            }
+            None => None,
+        };
+        let context = CharStringContext {
+            global_subrs: try!(SubrIndex::new(self.table.bytes, self.global_subrs, true)),
+            local_subrs: local_subrs,
+            widths: None,
+            variations: variations,
+        };
+
+        let transform = cff::font_unit_transform(self.font_matrix, head_table);
+        let mut callback = |point: &Point| {
+            match transform {
+                None => callback(point),
+                Some(ref transform) => callback(&cff::transform_point(transform, point)),
+            }
+        };
+
+        let origin = Point2D::new(0.0, 0.0);
+        try!(cff::run_char_string(char_string, &context, &origin, &mut callback));
+        Ok(())
+    }
+
+    // Returns the Private DICT that applies to the given glyph.
+    fn private_dict_for_glyph(&self, glyph_id: u16) -> Result<PrivateDict<'a>, FontError> {
+        let fd_index = match self.fd_select {
+            Some(fd_select) => try!(cff::fd_index(self.table.bytes, fd_select, glyph_id)),
+            None => 0,
+        };
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.fd_array as usize).map_err(FontError::eof));
+        let font_dict = match try!(cff::cff2_index_element(reader, fd_index as u32)) {
+            Some(font_dict) => font_dict,
+            None => return Err(FontError::CffFontDictNotFound),
+        };
+        match try!(PrivateDict::find(self.table.bytes, font_dict)) {
+            Some(private_dict) => Ok(private_dict),
+            None => Err(FontError::CffFontDictNotFound),
+        }
+    }
+
+    /// Returns the boundaries of the given glyph at the instance with the given normalized
+    /// coordinates.
+    pub fn glyph_bounds(&self, head_table: &HeadTable, glyph_id: u16, coords: &[f32])
+                        -> Result<GlyphBounds, FontError> {
+        let mut bounds = None;
+        try!(self.for_each_point(head_table, glyph_id, coords, |point| {
+            bounds = Some(cff::add_point_to_bounds(bounds, &point.position))
+        }));
+        Ok(bounds.unwrap_or_default())
+    }
+}
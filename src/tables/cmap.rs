@@ -15,7 +15,7 @@ use font::FontTable;
 use std::cmp;
 use std::mem;
 use std::u16;
-use util::Jump;
+use util::{self, Jump};
 
 pub const TAG: u32 = ((b'c' as u32) << 24) |
                       ((b'm' as u32) << 16) |
@@ -23,16 +23,58 @@ pub const TAG: u32 = ((b'c' as u32) << 24) |
                        (b'p' as u32);
 
 const PLATFORM_ID_UNICODE: u16 = 0;
+const PLATFORM_ID_MACINTOSH: u16 = 1;
 const PLATFORM_ID_MICROSOFT: u16 = 3;
 
+const UNICODE_ENCODING_ID_1_0: u16 = 0;
+const UNICODE_ENCODING_ID_1_1: u16 = 1;
+const UNICODE_ENCODING_ID_ISO_10646: u16 = 2;
+const UNICODE_ENCODING_ID_2_0_BMP: u16 = 3;
+const UNICODE_ENCODING_ID_2_0_FULL: u16 = 4;
+const UNICODE_ENCODING_ID_VARIATION_SEQUENCES: u16 = 5;
+const UNICODE_ENCODING_ID_FULL_REPERTOIRE: u16 = 6;
+
+const MACINTOSH_ENCODING_ID_ROMAN: u16 = 0;
+
+const MICROSOFT_ENCODING_ID_SYMBOL: u16 = 0;
 const MICROSOFT_ENCODING_ID_UNICODE_BMP: u16 = 1;
 const MICROSOFT_ENCODING_ID_UNICODE_UCS4: u16 = 10;
 
+// The subtables we support, best first: full Unicode, then the BMP, then encodings that we have
+// to translate Unicode into.
+static SUBTABLE_PREFERENCE: [((u16, u16), SubtableEncoding); 10] = [
+    ((PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_UCS4), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_2_0_FULL), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_FULL_REPERTOIRE), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_2_0_BMP), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_ISO_10646), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_1_1), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_1_0), SubtableEncoding::Unicode),
+    ((PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_SYMBOL), SubtableEncoding::Symbol),
+    ((PLATFORM_ID_MACINTOSH, MACINTOSH_ENCODING_ID_ROMAN), SubtableEncoding::MacRoman),
+];
+
+// Symbol subtables map U+F020 through U+F0FF, standing in for U+0020 through U+00FF.
+const SYMBOL_CODEPOINT_OFFSET: u32 = 0xf000;
+const SYMBOL_CODEPOINT_MAX: u32 = 0xff;
+
+const FORMAT_BYTE_ENCODING_TABLE: u16 = 0;
+const FORMAT_HIGH_BYTE_MAPPING_THROUGH_TABLE: u16 = 2;
 const FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES: u16 = 4;
+const FORMAT_TRIMMED_TABLE_MAPPING: u16 = 6;
+const FORMAT_TRIMMED_ARRAY: u16 = 10;
 const FORMAT_SEGMENTED_COVERAGE: u16 = 12;
+const FORMAT_MANY_TO_ONE_RANGE_MAPPINGS: u16 = 13;
+const FORMAT_UNICODE_VARIATION_SEQUENCES: u16 = 14;
 
 const MISSING_GLYPH: u16 = 0;
 
+// The sizes of the records in format 14 subtables, which use 24-bit integers.
+const VAR_SELECTOR_RECORD_SIZE: usize = 11;
+const UNICODE_RANGE_SIZE: usize = 4;
+const UVS_MAPPING_SIZE: usize = 5;
+
 #[derive(Clone, Copy)]
 pub struct CmapTable<'a> {
     table: FontTable<'a>,
@@ -56,46 +98,370 @@ impl<'a> CmapTable<'a> {
 
         let num_tables = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
 
-        // Check platform ID and encoding.
-        // TODO(pcwalton): Handle more.
-        let mut table_found = false;
+        // Pick the subtable that comes first in our order of preference, regardless of the order
+        // the font lists them in.
+        let mut best_subtable = None;
         for _ in 0..num_tables {
             let platform_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let encoding_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let offset = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
-            match (platform_id, encoding_id) {
-                (PLATFORM_ID_UNICODE, _) |
-                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP) |
-                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_UCS4) => {
-                    // Move to the mapping table.
-                    cmap_reader = self.table.bytes;
-                    try!(cmap_reader.jump(offset as usize).map_err(FontError::eof));
-                    table_found = true;
-                    break
-                }
-                _ => {}
+            let rank = match SUBTABLE_PREFERENCE.iter().position(|&(preferred_encoding, _)| {
+                preferred_encoding == (platform_id, encoding_id)
+            }) {
+                Some(rank) => rank,
+                None => continue,
+            };
+            match best_subtable {
+                Some((best_rank, _)) if best_rank <= rank => {}
+                _ => best_subtable = Some((rank, offset)),
+            }
+        }
+
+        let (rank, offset) = match best_subtable {
+            Some(best_subtable) => best_subtable,
+            None => return Err(FontError::UnsupportedCmapEncoding),
+        };
+        let subtable = try!(self.table.bytes.get(offset as usize..)
+                                            .ok_or(FontError::UnexpectedEof));
+
+        match SUBTABLE_PREFERENCE[rank].1 {
+            SubtableEncoding::Unicode => {
+                self.glyph_mapping_for_codepoint_ranges_in_subtable(subtable, codepoint_ranges)
+            }
+            SubtableEncoding::Symbol => {
+                self.glyph_mapping_for_codepoint_ranges_symbol(subtable, codepoint_ranges)
             }
+            SubtableEncoding::MacRoman => {
+                self.glyph_mapping_for_codepoint_ranges_mac_roman(subtable, codepoint_ranges)
+            }
+        }
+    }
+
+    // Symbol fonts put their characters in the Private Use Area at U+F020 through U+F0FF, but
+    // text usually refers to them by the corresponding Latin-1 codepoints, so we try both.
+    fn glyph_mapping_for_codepoint_ranges_symbol(&self,
+                                                 subtable: &[u8],
+                                                 codepoint_ranges: &[CodepointRange])
+                                                 -> Result<GlyphMapping, FontError> {
+        let glyph_mapping = try!(self.glyph_mapping_for_codepoint_ranges_in_subtable(
+            subtable,
+            codepoint_ranges));
+
+        let symbol_ranges: Vec<_> = codepoint_ranges.iter().filter(|codepoint_range| {
+            codepoint_range.start <= SYMBOL_CODEPOINT_MAX
+        }).map(|codepoint_range| {
+            CodepointRange::new(codepoint_range.start + SYMBOL_CODEPOINT_OFFSET,
+                                cmp::min(codepoint_range.end, SYMBOL_CODEPOINT_MAX) +
+                                SYMBOL_CODEPOINT_OFFSET)
+        }).collect();
+        if symbol_ranges.is_empty() {
+            return Ok(glyph_mapping)
+        }
+        let symbol_glyph_mapping =
+            try!(self.glyph_mapping_for_codepoint_ranges_in_subtable(subtable, &symbol_ranges));
+
+        let mut remapped_glyph_mapping = GlyphMapping::new();
+        for (codepoint, glyph_id) in glyph_mapping.iter() {
+            let glyph_id = if codepoint > SYMBOL_CODEPOINT_MAX {
+                glyph_id
+            } else {
+                match symbol_glyph_mapping.glyph_for(codepoint + SYMBOL_CODEPOINT_OFFSET) {
+                    Some(MISSING_GLYPH) | None => glyph_id,
+                    Some(symbol_glyph_id) => symbol_glyph_id,
+                }
+            };
+            push_glyph(&mut remapped_glyph_mapping, codepoint, glyph_id)
         }
 
-        if !table_found {
-            return Err(FontError::UnsupportedCmapEncoding)
+        Ok(remapped_glyph_mapping)
+    }
+
+    // Mac Roman subtables map single bytes, so we translate each codepoint to the Mac Roman
+    // character set first.
+    fn glyph_mapping_for_codepoint_ranges_mac_roman(&self,
+                                                    subtable: &[u8],
+                                                    codepoint_ranges: &[CodepointRange])
+                                                    -> Result<GlyphMapping, FontError> {
+        let byte_glyph_mapping = try!(self.glyph_mapping_for_codepoint_ranges_in_subtable(
+            subtable,
+            &[CodepointRange::new(0, 0xff)]));
+
+        let mut glyph_mapping = GlyphMapping::new();
+        for codepoint_range in codepoint_ranges {
+            for codepoint in codepoint_range.iter() {
+                let glyph_id = util::unicode_to_mac_roman(codepoint).and_then(|byte| {
+                    byte_glyph_mapping.glyph_for(byte as u32)
+                }).unwrap_or(MISSING_GLYPH);
+                push_glyph(&mut glyph_mapping, codepoint, glyph_id)
+            }
         }
 
+        Ok(glyph_mapping)
+    }
+
+    fn glyph_mapping_for_codepoint_ranges_in_subtable(&self,
+                                                      mut cmap_reader: &[u8],
+                                                      codepoint_ranges: &[CodepointRange])
+                                                      -> Result<GlyphMapping, FontError> {
         // Check the mapping table format.
         let format = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
         match format {
+            FORMAT_BYTE_ENCODING_TABLE => {
+                self.glyph_mapping_for_codepoint_ranges_byte_encoding(cmap_reader,
+                                                                      codepoint_ranges)
+            }
+            FORMAT_HIGH_BYTE_MAPPING_THROUGH_TABLE => {
+                self.glyph_mapping_for_codepoint_ranges_high_byte_mapping(cmap_reader,
+                                                                          codepoint_ranges)
+            }
             FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES => {
                 self.glyph_mapping_for_codepoint_ranges_segment_mapping_format(cmap_reader,
                                                                                codepoint_ranges)
             }
+            FORMAT_TRIMMED_TABLE_MAPPING => {
+                self.glyph_mapping_for_codepoint_ranges_trimmed_table(cmap_reader,
+                                                                      codepoint_ranges)
+            }
+            FORMAT_TRIMMED_ARRAY => {
+                self.glyph_mapping_for_codepoint_ranges_trimmed_array(cmap_reader,
+                                                                      codepoint_ranges)
+            }
             FORMAT_SEGMENTED_COVERAGE => {
                 self.glyph_mapping_for_codepoint_ranges_segmented_coverage(cmap_reader,
-                                                                           codepoint_ranges)
+                                                                           codepoint_ranges,
+                                                                           false)
+            }
+            FORMAT_MANY_TO_ONE_RANGE_MAPPINGS => {
+                self.glyph_mapping_for_codepoint_ranges_segmented_coverage(cmap_reader,
+                                                                           codepoint_ranges,
+                                                                           true)
             }
             _ => Err(FontError::UnsupportedCmapFormat),
         }
     }
 
+    /// Returns the glyph that the given base character followed by the given variation selector
+    /// maps to, according to the font's Unicode Variation Sequences subtable.
+    ///
+    /// Returns `None` if the font doesn't define the sequence.
+    pub fn glyph_for_variation_sequence(&self, base: u32, selector: u32)
+                                        -> Result<Option<u16>, FontError> {
+        let mut cmap_reader = self.table.bytes;
+
+        // Check version.
+        if try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 0 {
+            return Err(FontError::UnsupportedCmapVersion)
+        }
+
+        // Find the variation sequences subtable.
+        let num_tables = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut subtable = None;
+        for _ in 0..num_tables {
+            let platform_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let encoding_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            if (platform_id, encoding_id) ==
+                    (PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_VARIATION_SEQUENCES) {
+                subtable = Some(try!(self.table.bytes.get(offset as usize..)
+                                                     .ok_or(FontError::UnexpectedEof)));
+                break
+            }
+        }
+        let subtable = match subtable {
+            Some(subtable) => subtable,
+            None => return Ok(None),
+        };
+
+        let mut reader = subtable;
+        if try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) !=
+                FORMAT_UNICODE_VARIATION_SEQUENCES {
+            return Err(FontError::UnsupportedCmapFormat)
+        }
+        let _length = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_var_selector_records = try!(reader.read_u32::<BigEndian>()
+                                                  .map_err(FontError::eof));
+
+        // Binary search to find the selector. Each record is a 24-bit selector followed by the
+        // offsets of the default and non-default UVS tables.
+        let (mut low, mut high) = (0, num_var_selector_records);
+        let mut uvs_offsets = None;
+        while low < high {
+            let mid = (low + high) / 2;
+
+            let mut record = reader;
+            try!(record.jump(mid as usize * VAR_SELECTOR_RECORD_SIZE).map_err(FontError::eof));
+            let var_selector = try!(read_u24(&mut record));
+            if selector < var_selector {
+                high = mid
+            } else if selector > var_selector {
+                low = mid + 1
+            } else {
+                let default_uvs_offset = try!(record.read_u32::<BigEndian>()
+                                                    .map_err(FontError::eof));
+                let non_default_uvs_offset = try!(record.read_u32::<BigEndian>()
+                                                        .map_err(FontError::eof));
+                uvs_offsets = Some((default_uvs_offset, non_default_uvs_offset));
+                break
+            }
+        }
+        let (default_uvs_offset, non_default_uvs_offset) = match uvs_offsets {
+            Some(uvs_offsets) => uvs_offsets,
+            None => return Ok(None),
+        };
+
+        // Sequences in the default UVS table map to the base character's usual glyph.
+        if default_uvs_offset != 0 {
+            let mut reader = subtable;
+            try!(reader.jump(default_uvs_offset as usize).map_err(FontError::eof));
+            let num_unicode_value_ranges = try!(reader.read_u32::<BigEndian>()
+                                                      .map_err(FontError::eof));
+
+            let (mut low, mut high) = (0, num_unicode_value_ranges);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut range = reader;
+                try!(range.jump(mid as usize * UNICODE_RANGE_SIZE).map_err(FontError::eof));
+                let start_unicode_value = try!(read_u24(&mut range));
+                let additional_count = try!(range.read_u8().map_err(FontError::eof));
+                if base < start_unicode_value {
+                    high = mid
+                } else if base > start_unicode_value + additional_count as u32 {
+                    low = mid + 1
+                } else {
+                    let codepoint_range = CodepointRange::new(base, base);
+                    let glyph_mapping =
+                        try!(self.glyph_mapping_for_codepoint_ranges(&[codepoint_range]));
+                    return Ok(match glyph_mapping.glyph_for(base) {
+                        Some(MISSING_GLYPH) | None => None,
+                        Some(glyph_id) => Some(glyph_id),
+                    })
+                }
+            }
+        }
+
+        // Sequences in the non-default UVS table map to glyphs of their own.
+        if non_default_uvs_offset != 0 {
+            let mut reader = subtable;
+            try!(reader.jump(non_default_uvs_offset as usize).map_err(FontError::eof));
+            let num_uvs_mappings = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+            let (mut low, mut high) = (0, num_uvs_mappings);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut mapping = reader;
+                try!(mapping.jump(mid as usize * UVS_MAPPING_SIZE).map_err(FontError::eof));
+                let unicode_value = try!(read_u24(&mut mapping));
+                if base < unicode_value {
+                    high = mid
+                } else if base > unicode_value {
+                    low = mid + 1
+                } else {
+                    let glyph_id = try!(mapping.read_u16::<BigEndian>().map_err(FontError::eof));
+                    return Ok(Some(glyph_id))
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn glyph_mapping_for_codepoint_ranges_byte_encoding(&self,
+                                                        mut cmap_reader: &[u8],
+                                                        codepoint_ranges: &[CodepointRange])
+                                                        -> Result<GlyphMapping, FontError> {
+        let _length = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // The glyph IDs are bytes, one for each of the first 256 codepoints.
+        let glyph_ids = try!(cmap_reader.get(..256).ok_or(FontError::UnexpectedEof));
+
+        let mut glyph_mapping = GlyphMapping::new();
+        for codepoint_range in codepoint_ranges {
+            for codepoint in codepoint_range.iter() {
+                let glyph_id = match glyph_ids.get(codepoint as usize) {
+                    Some(&glyph_id) => glyph_id as u16,
+                    None => MISSING_GLYPH,
+                };
+                push_glyph(&mut glyph_mapping, codepoint, glyph_id)
+            }
+        }
+
+        Ok(glyph_mapping)
+    }
+
+    fn glyph_mapping_for_codepoint_ranges_high_byte_mapping(&self,
+                                                            mut cmap_reader: &[u8],
+                                                            codepoint_ranges: &[CodepointRange])
+                                                            -> Result<GlyphMapping, FontError> {
+        let _length = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // The sub-header keys map the first byte of each code to the offset of a sub-header.
+        let sub_header_keys = cmap_reader;
+        let mut sub_headers = cmap_reader;
+        try!(sub_headers.jump(mem::size_of::<u16>() * 256).map_err(FontError::eof));
+
+        let mut glyph_mapping = GlyphMapping::new();
+        for codepoint_range in codepoint_ranges {
+            for codepoint in codepoint_range.iter() {
+                if codepoint > u16::MAX as u32 {
+                    push_glyph(&mut glyph_mapping, codepoint, MISSING_GLYPH);
+                    continue
+                }
+
+                // Find the sub-header. Single-byte codes use sub-header 0, but only if their
+                // byte doesn't start a two-byte code.
+                let (key_byte, low_byte) = if codepoint < 256 {
+                    (codepoint, codepoint as u16)
+                } else {
+                    (codepoint >> 8, (codepoint & 0xff) as u16)
+                };
+                let mut reader = sub_header_keys;
+                try!(reader.jump(mem::size_of::<u16>() * key_byte as usize)
+                           .map_err(FontError::eof));
+                let sub_header_key = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                if (codepoint < 256) != (sub_header_key == 0) {
+                    push_glyph(&mut glyph_mapping, codepoint, MISSING_GLYPH);
+                    continue
+                }
+
+                let mut sub_header = sub_headers;
+                try!(sub_header.jump(mem::size_of::<[u16; 4]>() * (sub_header_key / 8) as usize)
+                               .map_err(FontError::eof));
+                let first_code = try!(sub_header.read_u16::<BigEndian>().map_err(FontError::eof));
+                let entry_count = try!(sub_header.read_u16::<BigEndian>()
+                                                 .map_err(FontError::eof));
+                let id_delta = try!(sub_header.read_i16::<BigEndian>().map_err(FontError::eof));
+
+                // `idRangeOffset` is relative to its own position.
+                let id_range_offset = sub_header;
+                let id_range_offset_value = try!(sub_header.read_u16::<BigEndian>()
+                                                           .map_err(FontError::eof));
+
+                if low_byte < first_code || low_byte - first_code >= entry_count {
+                    push_glyph(&mut glyph_mapping, codepoint, MISSING_GLYPH);
+                    continue
+                }
+
+                let mut reader = id_range_offset;
+                try!(reader.jump(id_range_offset_value as usize +
+                                 mem::size_of::<u16>() * (low_byte - first_code) as usize)
+                           .map_err(FontError::eof));
+                let glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let glyph_id = if glyph_id == MISSING_GLYPH {
+                    MISSING_GLYPH
+                } else {
+                    (glyph_id as i16).wrapping_add(id_delta) as u16
+                };
+                push_glyph(&mut glyph_mapping, codepoint, glyph_id)
+            }
+        }
+
+        Ok(glyph_mapping)
+    }
+
     fn glyph_mapping_for_codepoint_ranges_segment_mapping_format(
             &self,
             mut cmap_reader: &[u8],
@@ -254,9 +620,69 @@ impl<'a> CmapTable<'a> {
         Ok(glyph_mapping)
     }
 
+    fn glyph_mapping_for_codepoint_ranges_trimmed_table(&self,
+                                                        mut cmap_reader: &[u8],
+                                                        codepoint_ranges: &[CodepointRange])
+                                                        -> Result<GlyphMapping, FontError> {
+        let _length = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let first_code = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let entry_count = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        self.glyph_mapping_for_codepoint_ranges_trimmed(cmap_reader,
+                                                        first_code as u32,
+                                                        entry_count as u32,
+                                                        codepoint_ranges)
+    }
+
+    fn glyph_mapping_for_codepoint_ranges_trimmed_array(&self,
+                                                        mut cmap_reader: &[u8],
+                                                        codepoint_ranges: &[CodepointRange])
+                                                        -> Result<GlyphMapping, FontError> {
+        let _reserved = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _length = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let start_char_code = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_chars = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        self.glyph_mapping_for_codepoint_ranges_trimmed(cmap_reader,
+                                                        start_char_code,
+                                                        num_chars,
+                                                        codepoint_ranges)
+    }
+
+    // Formats 6 and 10 both map a single run of codepoints to an array of glyph IDs.
+    fn glyph_mapping_for_codepoint_ranges_trimmed(&self,
+                                                  glyph_ids: &[u8],
+                                                  first_code: u32,
+                                                  entry_count: u32,
+                                                  codepoint_ranges: &[CodepointRange])
+                                                  -> Result<GlyphMapping, FontError> {
+        let mut glyph_mapping = GlyphMapping::new();
+        for codepoint_range in codepoint_ranges {
+            for codepoint in codepoint_range.iter() {
+                if codepoint < first_code || codepoint - first_code >= entry_count {
+                    push_glyph(&mut glyph_mapping, codepoint, MISSING_GLYPH);
+                    continue
+                }
+
+                let mut reader = glyph_ids;
+                try!(reader.jump(mem::size_of::<u16>() * (codepoint - first_code) as usize)
+                           .map_err(FontError::eof));
+                let glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                push_glyph(&mut glyph_mapping, codepoint, glyph_id)
+            }
+        }
+
+        Ok(glyph_mapping)
+    }
+
+    // Formats 12 and 13 share a layout. In format 12, consecutive codepoints in each group map to
+    // consecutive glyphs; in format 13, they all map to the same glyph.
     fn glyph_mapping_for_codepoint_ranges_segmented_coverage(&self,
                                                              mut cmap_reader: &[u8],
-                                                             codepoint_ranges: &[CodepointRange])
+                                                             codepoint_ranges: &[CodepointRange],
+                                                             many_to_one: bool)
                                                              -> Result<GlyphMapping, FontError> {
         let _reserved = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
         let _length = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
@@ -307,6 +733,13 @@ impl<'a> CmapTable<'a> {
                         });
                         codepoint_range.start += 1
                     }
+                    Some(segment) if many_to_one => {
+                        let end = cmp::min(codepoint_range.end, segment.end_char_code);
+                        for codepoint in codepoint_range.start..(end + 1) {
+                            push_glyph(&mut glyph_mapping, codepoint, segment.start_glyph_id as u16)
+                        }
+                        codepoint_range.start = end + 1
+                    }
                     Some(segment) => {
                         let end = cmp::min(codepoint_range.end, segment.end_char_code);
                         glyph_mapping.push(MappedGlyphRange {
@@ -328,6 +761,14 @@ impl<'a> CmapTable<'a> {
     }
 }
 
+// How the character codes in a subtable relate to Unicode.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SubtableEncoding {
+    Unicode,
+    Symbol,
+    MacRoman,
+}
+
 #[derive(Clone, Copy)]
 struct Segment {
     start_char_code: u32,
@@ -335,3 +776,20 @@ struct Segment {
     start_glyph_id: u32,
 }
 
+// Maps a single codepoint to the given glyph.
+fn push_glyph(glyph_mapping: &mut GlyphMapping, codepoint: u32, glyph_id: u16) {
+    glyph_mapping.push(MappedGlyphRange {
+        codepoint_start: codepoint,
+        glyphs: GlyphRange {
+            start: glyph_id,
+            end: glyph_id,
+        },
+    })
+}
+
+// Reads a big-endian 24-bit integer, as format 14 subtables store codepoints.
+fn read_u24(reader: &mut &[u8]) -> Result<u32, FontError> {
+    let high = try!(reader.read_u8().map_err(FontError::eof)) as u32;
+    let low = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32;
+    Ok((high << 16) | low)
+}
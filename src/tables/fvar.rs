@@ -0,0 +1,225 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The font variations table, which defines the axes that a variable font varies along.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/fvar.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use tables::variations;
+use util::Jump;
+
+pub const TAG: u32 = ((b'f' as u32) << 24) |
+                      ((b'v' as u32) << 16) |
+                      ((b'a' as u32) << 8)  |
+                       (b'r' as u32);
+
+/// The tag of the weight axis, whose values are like those of `OS/2.usWeightClass`.
+pub const WEIGHT: u32 = ((b'w' as u32) << 24) |
+                         ((b'g' as u32) << 16) |
+                         ((b'h' as u32) << 8)  |
+                          (b't' as u32);
+/// The tag of the width axis, whose values are percentages of the normal width.
+pub const WIDTH: u32 = ((b'w' as u32) << 24) |
+                        ((b'd' as u32) << 16) |
+                        ((b't' as u32) << 8)  |
+                         (b'h' as u32);
+
+const AXIS_FLAG_HIDDEN: u16 = 0x0001;
+
+// The size of an axis record without any extensions in later versions of the table.
+const AXIS_RECORD_SIZE: u16 = 20;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FvarTable<'a> {
+    // The offset of the first axis record. The instance records follow the axis records.
+    axes: u16,
+    axis_count: u16,
+    axis_size: u16,
+    instance_count: u16,
+    instance_size: u16,
+    table: FontTable<'a>,
+}
+
+impl<'a> FvarTable<'a> {
+    pub fn new(table: FontTable) -> Result<FvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedFvarVersion)
+        }
+
+        let axes = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<u16>()).map_err(FontError::eof));
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let axis_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let instance_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let instance_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Make sure the records are at least as big as we expect.
+        if axis_size < AXIS_RECORD_SIZE ||
+                (instance_size as usize) < mem::size_of::<u16>() * 2 +
+                                           mem::size_of::<i32>() * axis_count as usize {
+            return Err(FontError::Failed)
+        }
+
+        Ok(FvarTable {
+            axes: axes,
+            axis_count: axis_count,
+            axis_size: axis_size,
+            instance_count: instance_count,
+            instance_size: instance_size,
+            table: table,
+        })
+    }
+
+    /// Returns the number of axes, which is also the number of coordinates that an instance has.
+    #[inline]
+    pub fn axis_count(&self) -> u16 {
+        self.axis_count
+    }
+
+    /// Returns the variation axes, in the order that coordinates are specified in.
+    pub fn axes(&self) -> Result<Vec<VariationAxis>, FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.axes as usize).map_err(FontError::eof));
+
+        let mut axes = Vec::with_capacity(self.axis_count as usize);
+        for _ in 0..self.axis_count {
+            let mut record = reader;
+            try!(reader.jump(self.axis_size as usize).map_err(FontError::eof));
+
+            let tag = try!(record.read_u32::<BigEndian>().map_err(FontError::eof));
+            let min_value = try!(variations::read_fixed(&mut record));
+            let default_value = try!(variations::read_fixed(&mut record));
+            let max_value = try!(variations::read_fixed(&mut record));
+            let flags = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            let name_id = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            axes.push(VariationAxis {
+                tag: tag,
+                min_value: min_value,
+                default_value: default_value,
+                max_value: max_value,
+                hidden: (flags & AXIS_FLAG_HIDDEN) != 0,
+                name_id: name_id,
+            })
+        }
+
+        Ok(axes)
+    }
+
+    /// Returns the named instances that the font predefines, such as "Bold" or "Condensed".
+    pub fn named_instances(&self) -> Result<Vec<NamedInstance>, FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.axes as usize +
+                         self.axis_size as usize * self.axis_count as usize)
+                   .map_err(FontError::eof));
+
+        // The PostScript name ID is optional and present only if the record has room for it.
+        let has_postscript_name_id = self.instance_size as usize >=
+            mem::size_of::<u16>() * 3 + mem::size_of::<i32>() * self.axis_count as usize;
+
+        let mut instances = Vec::with_capacity(self.instance_count as usize);
+        for _ in 0..self.instance_count {
+            let mut record = reader;
+            try!(reader.jump(self.instance_size as usize).map_err(FontError::eof));
+
+            let subfamily_name_id = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            let _flags = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let mut coordinates = Vec::with_capacity(self.axis_count as usize);
+            for _ in 0..self.axis_count {
+                coordinates.push(try!(variations::read_fixed(&mut record)))
+            }
+
+            let postscript_name_id = if has_postscript_name_id {
+                match try!(record.read_u16::<BigEndian>().map_err(FontError::eof)) {
+                    0xffff => None,
+                    name_id => Some(name_id),
+                }
+            } else {
+                None
+            };
+
+            instances.push(NamedInstance {
+                subfamily_name_id: subfamily_name_id,
+                coordinates: coordinates,
+                postscript_name_id: postscript_name_id,
+            })
+        }
+
+        Ok(instances)
+    }
+}
+
+/// An axis of the design space that a variable font varies along, such as weight or width.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VariationAxis {
+    /// The four-character tag that identifies the axis, such as `wght` or `wdth`.
+    pub tag: u32,
+    /// The smallest value that the axis can take on, in user-space units.
+    pub min_value: f32,
+    /// The value of the axis at the default instance, in user-space units.
+    pub default_value: f32,
+    /// The largest value that the axis can take on, in user-space units.
+    pub max_value: f32,
+    /// Whether the font asks that the axis not be exposed in user interfaces.
+    pub hidden: bool,
+    /// The ID of the axis's name in the font's `name` table.
+    pub name_id: u16,
+}
+
+impl VariationAxis {
+    /// Maps the given user-space value of this axis to a normalized coordinate in [-1, 1].
+    ///
+    /// The default value maps to 0, and the minimum and maximum values map to -1 and 1
+    /// respectively. Values outside the axis's range are clamped. This doesn't apply any `avar`
+    /// mapping the font may have.
+    pub fn normalize(&self, value: f32) -> f32 {
+        let value = value.max(self.min_value).min(self.max_value);
+        if value < self.default_value {
+            -(self.default_value - value) / (self.default_value - self.min_value)
+        } else if value > self.default_value {
+            (value - self.default_value) / (self.max_value - self.default_value)
+        } else {
+            0.0
+        }
+    }
+
+    /// Maps the given normalized coordinate back to a user-space value of this axis.
+    ///
+    /// This is the inverse of `normalize()` for coordinates in [-1, 1].
+    pub fn denormalize(&self, coord: f32) -> f32 {
+        let coord = coord.max(-1.0).min(1.0);
+        if coord < 0.0 {
+            self.default_value + coord * (self.default_value - self.min_value)
+        } else {
+            self.default_value + coord * (self.max_value - self.default_value)
+        }
+    }
+}
+
+/// A predefined point in the design space of a variable font, such as "SemiBold Condensed".
+#[derive(Clone, PartialEq, Debug)]
+pub struct NamedInstance {
+    /// The ID of the instance's subfamily name in the font's `name` table.
+    pub subfamily_name_id: u16,
+    /// The user-space value of each axis at this instance, in the same order as the axes.
+    pub coordinates: Vec<f32>,
+    /// The ID of the instance's PostScript name in the font's `name` table, if it has one.
+    pub postscript_name_id: Option<u16>,
+}
@@ -0,0 +1,120 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `GDEF` glyph definition table.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/gdef.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{FontTable, GlyphClass};
+use std::mem;
+use tables::layout;
+use util::Jump;
+
+pub const TAG: u32 = ((b'G' as u32) << 24) |
+                      ((b'D' as u32) << 16) |
+                      ((b'E' as u32) << 8)  |
+                       (b'F' as u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct GdefTable<'a> {
+    table: FontTable<'a>,
+    // The offsets of the various subtables. Zero means the subtable is absent.
+    glyph_class_def: u16,
+    mark_attach_class_def: u16,
+    mark_glyph_sets_def: u16,
+}
+
+impl<'a> GdefTable<'a> {
+    pub fn new(table: FontTable) -> Result<GdefTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGdefVersion)
+        }
+
+        let glyph_class_def = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Skip the attachment point list and the ligature caret list.
+        try!(reader.jump(mem::size_of::<u16>() * 2).map_err(FontError::eof));
+        let mark_attach_class_def = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Mark glyph sets were introduced in version 1.2.
+        let mark_glyph_sets_def = if minor_version >= 2 {
+            try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
+        } else {
+            0
+        };
+
+        Ok(GdefTable {
+            table: table,
+            glyph_class_def: glyph_class_def,
+            mark_attach_class_def: mark_attach_class_def,
+            mark_glyph_sets_def: mark_glyph_sets_def,
+        })
+    }
+
+    /// Returns the class of the given glyph, or `None` if the font doesn't assign it one.
+    pub fn glyph_class(&self, glyph_id: u16) -> Result<Option<GlyphClass>, FontError> {
+        if self.glyph_class_def == 0 {
+            return Ok(None)
+        }
+
+        let class_def = try!(layout::subtable_at(self.table.bytes, self.glyph_class_def as usize));
+        match try!(layout::class_of_glyph(class_def, glyph_id)) {
+            1 => Ok(Some(GlyphClass::Base)),
+            2 => Ok(Some(GlyphClass::Ligature)),
+            3 => Ok(Some(GlyphClass::Mark)),
+            4 => Ok(Some(GlyphClass::Component)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the mark attachment class of the given glyph, or zero if it has none.
+    pub fn mark_attachment_class(&self, glyph_id: u16) -> Result<u16, FontError> {
+        if self.mark_attach_class_def == 0 {
+            return Ok(0)
+        }
+
+        let class_def = try!(layout::subtable_at(self.table.bytes,
+                                                 self.mark_attach_class_def as usize));
+        layout::class_of_glyph(class_def, glyph_id)
+    }
+
+    /// Returns true if the given glyph is in the mark glyph set with the given index.
+    pub fn mark_glyph_set_contains(&self, set_index: u16, glyph_id: u16)
+                                   -> Result<bool, FontError> {
+        if self.mark_glyph_sets_def == 0 {
+            return Ok(false)
+        }
+
+        let mark_glyph_sets = try!(layout::subtable_at(self.table.bytes,
+                                                       self.mark_glyph_sets_def as usize));
+        let mut reader = mark_glyph_sets;
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if format != 1 {
+            return Err(FontError::UnknownFormat)
+        }
+
+        let set_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if set_index >= set_count {
+            return Ok(false)
+        }
+
+        try!(reader.jump(set_index as usize * mem::size_of::<u32>()).map_err(FontError::eof));
+        let coverage = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let coverage = try!(layout::subtable_at(mark_glyph_sets, coverage as usize));
+        Ok(try!(layout::coverage_index(coverage, glyph_id)).is_some())
+    }
+}
@@ -8,13 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use error::FontError;
 use euclid::Point2D;
 use font::{FontTable, Point, PointKind};
 use outline::GlyphBounds;
+use std::cmp;
+use std::i32;
 use std::mem;
 use std::ops::Mul;
+use tables::gvar::{GvarTable, PHANTOM_POINT_COUNT};
 use tables::head::HeadTable;
 use tables::loca::LocaTable;
 use util::Jump;
@@ -47,6 +50,7 @@ bitflags! {
         const MORE_COMPONENTS = 1 << 5,
         const WE_HAVE_AN_X_AND_Y_SCALE = 1 << 6,
         const WE_HAVE_A_TWO_BY_TWO = 1 << 7,
+        const WE_HAVE_INSTRUCTIONS = 1 << 8,
     }
 }
 
@@ -64,10 +68,16 @@ impl<'a> GlyfTable<'a> {
         }
     }
 
+    /// Calls `callback` with each point of the outline of the given glyph.
+    ///
+    /// If the font has a `gvar` table, the outline is varied to the instance with the given
+    /// normalized coordinates. Pass empty coordinates for the default instance.
     pub fn for_each_point<F>(&self,
                              head_table: &HeadTable,
                              loca_table: &LocaTable,
+                             gvar_table: Option<&GvarTable>,
                              glyph_id: u16,
+                             coords: &[f32],
                              callback: F)
                              -> Result<(), FontError> where F: FnMut(&Point) {
         let mut reader = self.table.bytes;
@@ -80,156 +90,62 @@ impl<'a> GlyfTable<'a> {
             Some(offset) => try!(reader.jump(offset as usize).map_err(FontError::eof)),
         }
 
+        // Don't bother with the deltas at the default instance.
+        let variations = match gvar_table {
+            Some(gvar_table) if coords.iter().any(|&coord| coord != 0.0) => {
+                Some((gvar_table, coords))
+            }
+            _ => None,
+        };
+
         let glyph_start = reader;
         let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
         if number_of_contours >= 0 {
-            self.for_each_point_in_simple_glyph(glyph_start, callback)
+            self.for_each_point_in_simple_glyph(glyph_start, glyph_id, variations, callback)
         } else {
-            self.for_each_point_in_composite_glyph(glyph_start, head_table, loca_table, callback)
+            self.for_each_point_in_composite_glyph(glyph_start,
+                                                   head_table,
+                                                   loca_table,
+                                                   glyph_id,
+                                                   variations,
+                                                   callback)
         }
     }
 
-    fn for_each_point_in_simple_glyph<F>(&self, mut reader: &[u8], mut callback: F)
+    fn for_each_point_in_simple_glyph<F>(&self,
+                                         reader: &[u8],
+                                         glyph_id: u16,
+                                         variations: Option<(&GvarTable, &[f32])>,
+                                         callback: F)
                                          -> Result<(), FontError> where F: FnMut(&Point) {
-        // Determine how many contours we have.
-        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-        if number_of_contours == 0 {
-            return Ok(())
-        }
-
-        // Skip over the rest of the header.
-        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
-
-        // Find out how many points we have.
-        let mut endpoints_reader = reader;
-        try!(reader.jump(mem::size_of::<u16>() as usize * (number_of_contours as usize - 1))
-                   .map_err(FontError::eof));
-        let number_of_points = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) + 1;
-
-        // Skip over hinting instructions.
-        let instruction_length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        try!(reader.jump(instruction_length as usize).map_err(FontError::eof));
-
-        // Find the offsets of the X and Y coordinates.
-        let flags_reader = reader;
-        let x_coordinate_length = try!(calculate_size_of_x_coordinates(&mut reader,
-                                                                       number_of_points));
-
-        // Set up the streams.
-        let mut flag_parser = try!(FlagParser::new(flags_reader));
-        let mut x_coordinate_reader = reader;
-        try!(reader.jump(x_coordinate_length as usize).map_err(FontError::eof));
-        let mut y_coordinate_reader = reader;
-
-        // Now parse the contours.
-        let (mut position, mut point_index) = (Point2D::new(0, 0), 0);
-        for _ in 0..number_of_contours {
-            let contour_point_count = try!(endpoints_reader.read_u16::<BigEndian>()
-                                                           .map_err(FontError::eof)) - point_index + 1;
-
-            let mut first_on_curve_point = None;
-            let mut initial_off_curve_point = None;
-            let mut last_point_was_off_curve = false;
-            let mut point_index_in_contour = 0;
-
-            for _ in 0..contour_point_count {
-                let flags = SimpleFlags::from_bits_truncate(*flag_parser.current);
-                try!(flag_parser.next());
-
-                let mut delta = Point2D::new(0, 0);
-                if flags.contains(X_SHORT_VECTOR) {
-                    delta.x = try!(x_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
-                    if !flags.contains(THIS_X_IS_SAME) {
-                        delta.x = -delta.x
-                    }
-                } else if !flags.contains(THIS_X_IS_SAME) {
-                    delta.x = try!(x_coordinate_reader.read_i16::<BigEndian>().map_err(FontError::eof))
-                }
-                if flags.contains(Y_SHORT_VECTOR) {
-                    delta.y = try!(y_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
-                    if !flags.contains(THIS_Y_IS_SAME) {
-                        delta.y = -delta.y
-                    }
-                } else if !flags.contains(THIS_Y_IS_SAME) {
-                    delta.y = try!(y_coordinate_reader.read_i16::<BigEndian>().map_err(FontError::eof))
-                }
-
-                if last_point_was_off_curve && !flags.contains(ON_CURVE) {
-                    let position = position + delta / 2;
-
-                    // An important edge case!
-                    if first_on_curve_point.is_none() {
-                        first_on_curve_point = Some(position)
-                    }
-
-                    callback(&Point {
-                        position: position,
-                        index_in_contour: point_index_in_contour,
-                        kind: PointKind::OnCurve,
-                    });
-                    point_index_in_contour += 1
-                }
-
-                position = position + delta;
-
-                if flags.contains(ON_CURVE) && first_on_curve_point.is_none() {
-                    first_on_curve_point = Some(position)
-                }
-
-                // Sometimes the initial point is an off curve point. In that case, save it so we
-                // can emit it later when closing the path.
-                if !flags.contains(ON_CURVE) && first_on_curve_point.is_none() {
-                    debug_assert!(initial_off_curve_point.is_none());
-                    initial_off_curve_point = Some(position)
-                } else {
-                    callback(&Point {
-                        position: position,
-                        kind: if flags.contains(ON_CURVE) {
-                            PointKind::OnCurve
-                        } else {
-                            PointKind::QuadControl
-                        },
-                        index_in_contour: point_index_in_contour,
-                    });
-                    point_index_in_contour += 1
-                }
-
-                last_point_was_off_curve = !flags.contains(ON_CURVE);
-                point_index += 1;
-            }
-
-            // We're about to close the path. Emit the initial off curve point if there was one.
-            if let Some(initial_off_curve_point) = initial_off_curve_point {
-                if last_point_was_off_curve {
-                    // Another important edge case!
-                    let position = position + (initial_off_curve_point - position) / 2;
-                    callback(&Point {
-                        position: position,
-                        index_in_contour: point_index_in_contour,
-                        kind: PointKind::OnCurve,
-                    });
-                    point_index_in_contour += 1
-                }
-
-                callback(&Point {
-                    position: initial_off_curve_point,
-                    kind: PointKind::QuadControl,
-                    index_in_contour: point_index_in_contour,
-                });
-                point_index_in_contour += 1
-            }
+        let mut glyph = match try!(SimpleGlyph::new(reader)) {
+            None => return Ok(()),
+            Some(glyph) => glyph,
+        };
 
-            // Close the path.
-            if let Some(first_on_curve_point) = first_on_curve_point {
-                callback(&Point {
-                    position: first_on_curve_point,
-                    kind: PointKind::OnCurve,
-                    index_in_contour: point_index_in_contour,
-                })
+        let (gvar_table, coords) = match variations {
+            None => {
+                return for_each_point_in_contours(glyph.end_points,
+                                                  glyph.number_of_contours,
+                                                  || glyph.point_parser.next(),
+                                                  callback)
             }
-        }
+            Some(variations) => variations,
+        };
 
-        Ok(())
+        // We need all the points up front in order to vary them.
+        let (end_points, positions, on_curve_flags) = try!(glyph.read_points());
+        let deltas = try!(gvar_table.glyph_deltas(glyph_id, coords, &positions, &end_points));
+        let mut varied_points = positions.iter()
+                                         .zip(deltas.iter())
+                                         .zip(on_curve_flags.iter())
+                                         .map(|((position, delta), &on_curve)| {
+                                             (apply_delta(position, delta), on_curve)
+                                         });
+        for_each_point_in_contours(glyph.end_points,
+                                   glyph.number_of_contours,
+                                   || varied_points.next().ok_or(FontError::UnexpectedEof),
+                                   callback)
     }
 
     // TODO(pcwalton): Consider rasterizing pieces of composite glyphs independently and
@@ -238,10 +154,13 @@ impl<'a> GlyfTable<'a> {
                                             mut reader: &[u8],
                                             head_table: &HeadTable,
                                             loca_table: &LocaTable,
+                                            glyph_id: u16,
+                                            variations: Option<(&GvarTable, &[f32])>,
                                             mut callback: F)
                                             -> Result<(), FontError> where F: FnMut(&Point) {
         try!(reader.jump(mem::size_of::<i16>() * 5).map_err(FontError::eof));
 
+        let mut components = vec![];
         loop {
             let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let flags = CompositeFlags::from_bits_truncate(flags);
@@ -276,24 +195,377 @@ impl<'a> GlyfTable<'a> {
                 transform.m11 = F2Dot14(try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)));
             }
 
+            components.push((flags, glyph_index, transform));
+
+            if !flags.contains(MORE_COMPONENTS) {
+                break
+            }
+        }
+
+        // The deltas of a composite glyph move its components around. Each component also varies
+        // according to its own deltas.
+        if let Some((gvar_table, coords)) = variations {
+            let offsets: Vec<_> = components.iter().map(|&(_, _, transform)| {
+                Point2D::new(transform.m02, transform.m12)
+            }).collect();
+            let deltas = try!(gvar_table.glyph_deltas(glyph_id, coords, &offsets, &[]));
+            for (component, delta) in components.iter_mut().zip(deltas.iter()) {
+                let (flags, _, ref mut transform) = *component;
+                if flags.contains(ARGS_ARE_XY_VALUES) {
+                    let offset = apply_delta(&Point2D::new(transform.m02, transform.m12), delta);
+                    transform.m02 = offset.x;
+                    transform.m12 = offset.y;
+                }
+            }
+        }
+
+        for &(_, glyph_index, transform) in &components {
             if let Some(offset) = try!(loca_table.location_of(head_table, glyph_index)) {
                 let mut reader = self.table.bytes;
                 try!(reader.jump(offset as usize).map_err(FontError::eof));
-                try!(self.for_each_point_in_simple_glyph(reader, |point| {
+                try!(self.for_each_point_in_simple_glyph(reader, glyph_index, variations, |point| {
                     callback(&transform.transform(&point))
                 }));
             }
+        }
+
+        Ok(())
+    }
+
+    /// Returns how much the advance width of the given glyph changes at the instance with the
+    /// given normalized coordinates, according to the glyph's phantom points.
+    ///
+    /// Fonts should supply advance width deltas in an `HVAR` table instead, but not all do.
+    pub fn advance_width_delta(&self,
+                               head_table: &HeadTable,
+                               loca_table: &LocaTable,
+                               gvar_table: &GvarTable,
+                               glyph_id: u16,
+                               coords: &[f32])
+                               -> Result<f32, FontError> {
+        // The phantom points come after the outline's points, which we can leave out, since
+        // interpolation never moves phantom points.
+        let point_count = try!(self.point_count(head_table, loca_table, glyph_id)) as usize;
+        let points = vec![Point2D::new(0, 0); point_count];
+        let deltas = try!(gvar_table.glyph_deltas(glyph_id, coords, &points, &[]));
+        Ok(deltas[point_count + 1].x - deltas[point_count].x)
+    }
+
+    // Returns the number of points in the given glyph as far as `gvar` is concerned: the number
+    // of points in the outline of a simple glyph, or the number of components of a composite
+    // glyph.
+    fn point_count(&self, head_table: &HeadTable, loca_table: &LocaTable, glyph_id: u16)
+                   -> Result<u16, FontError> {
+        let mut reader = self.table.bytes;
+        match try!(loca_table.location_of(head_table, glyph_id)) {
+            None => return Ok(0),
+            Some(offset) => try!(reader.jump(offset as usize).map_err(FontError::eof)),
+        }
+
+        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
+        if number_of_contours == 0 {
+            return Ok(0)
+        }
+        if number_of_contours > 0 {
+            try!(reader.jump(mem::size_of::<u16>() * (number_of_contours as usize - 1))
+                       .map_err(FontError::eof));
+            return Ok(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) + 1)
+        }
+
+        let mut component_count = 0;
+        loop {
+            let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let flags = CompositeFlags::from_bits_truncate(flags);
+            component_count += 1;
+
+            // Skip the glyph index, the arguments, and the transform.
+            let mut size = mem::size_of::<u16>();
+            size += if flags.contains(ARG_1_AND_2_ARE_WORDS) {
+                mem::size_of::<i16>() * 2
+            } else {
+                mem::size_of::<i8>() * 2
+            };
+            if flags.contains(WE_HAVE_A_SCALE) {
+                size += mem::size_of::<i16>()
+            } else if flags.contains(WE_HAVE_AN_X_AND_Y_SCALE) {
+                size += mem::size_of::<i16>() * 2
+            } else if flags.contains(WE_HAVE_A_TWO_BY_TWO) {
+                size += mem::size_of::<i16>() * 4
+            }
+            try!(reader.jump(size).map_err(FontError::eof));
+
+            if !flags.contains(MORE_COMPONENTS) {
+                return Ok(component_count)
+            }
+        }
+    }
+
+    /// Writes out the outlines of the first `glyph_count` glyphs at the instance with the given
+    /// normalized coordinates as a new `glyf` table.
+    ///
+    /// Hinting instructions are dropped, since the tables that they depend on aren't varied.
+    pub fn instantiate(&self,
+                       head_table: &HeadTable,
+                       loca_table: &LocaTable,
+                       gvar_table: Option<&GvarTable>,
+                       glyph_count: u16,
+                       coords: &[f32])
+                       -> Result<GlyfInstance, FontError> {
+        let mut instance = GlyfInstance {
+            glyf: vec![],
+            loca: Vec::with_capacity(glyph_count as usize + 1),
+            bounds: Vec::with_capacity(glyph_count as usize),
+            origin_deltas: Vec::with_capacity(glyph_count as usize),
+        };
+
+        for glyph_id in 0..glyph_count {
+            instance.loca.push(instance.glyf.len() as u32);
+
+            let mut reader = self.table.bytes;
+            let number_of_contours = match try!(loca_table.location_of(head_table, glyph_id)) {
+                None => 0,
+                Some(offset) => {
+                    try!(reader.jump(offset as usize).map_err(FontError::eof));
+                    try!((&reader[..]).read_i16::<BigEndian>().map_err(FontError::eof))
+                }
+            };
+
+            let (bounds, origin_delta) = if number_of_contours > 0 {
+                try!(self.instantiate_simple_glyph(reader,
+                                                   gvar_table,
+                                                   glyph_id,
+                                                   coords,
+                                                   &mut instance.glyf))
+            } else if number_of_contours < 0 {
+                try!(self.instantiate_composite_glyph(reader,
+                                                      head_table,
+                                                      loca_table,
+                                                      gvar_table,
+                                                      glyph_id,
+                                                      coords,
+                                                      &mut instance.glyf))
+            } else {
+                // Glyphs without outlines still have phantom points.
+                let origin_delta = match gvar_table {
+                    None => 0.0,
+                    Some(gvar_table) => {
+                        try!(gvar_table.glyph_deltas(glyph_id, coords, &[], &[]))[0].x
+                    }
+                };
+                (None, origin_delta)
+            };
+            instance.bounds.push(bounds);
+            instance.origin_deltas.push(origin_delta);
+
+            // Keep glyphs 4-byte aligned.
+            while instance.glyf.len() % 4 != 0 {
+                instance.glyf.push(0)
+            }
+        }
+
+        instance.loca.push(instance.glyf.len() as u32);
+        Ok(instance)
+    }
+
+    // Writes out a varied copy of the given simple glyph. Returns its bounds and how far its
+    // origin moved.
+    fn instantiate_simple_glyph(&self,
+                                reader: &[u8],
+                                gvar_table: Option<&GvarTable>,
+                                glyph_id: u16,
+                                coords: &[f32],
+                                glyf: &mut Vec<u8>)
+                                -> Result<(Option<GlyphBounds>, f32), FontError> {
+        let mut glyph = match try!(SimpleGlyph::new(reader)) {
+            None => return Ok((None, 0.0)),
+            Some(glyph) => glyph,
+        };
+
+        let (end_points, positions, on_curve_flags) = try!(glyph.read_points());
+        let deltas = match gvar_table {
+            Some(gvar_table) => {
+                try!(gvar_table.glyph_deltas(glyph_id, coords, &positions, &end_points))
+            }
+            None => vec![Point2D::new(0.0, 0.0); positions.len() + PHANTOM_POINT_COUNT],
+        };
+        let positions: Vec<_> = positions.iter()
+                                         .zip(deltas.iter())
+                                         .map(|(position, delta)| apply_delta(position, delta))
+                                         .collect();
+
+        let mut bounds = GlyphBounds {
+            left: i32::MAX,
+            bottom: i32::MAX,
+            right: i32::MIN,
+            top: i32::MIN,
+        };
+        for position in &positions {
+            bounds.left = cmp::min(bounds.left, position.x as i32);
+            bounds.bottom = cmp::min(bounds.bottom, position.y as i32);
+            bounds.right = cmp::max(bounds.right, position.x as i32);
+            bounds.top = cmp::max(bounds.top, position.y as i32);
+        }
+
+        // Writing to a `Vec` can't fail, so the `unwrap()`s below are safe.
+        glyf.write_i16::<BigEndian>(glyph.number_of_contours).unwrap();
+        write_bounds(glyf, &bounds);
+        for &end_point in &end_points {
+            glyf.write_u16::<BigEndian>(end_point).unwrap()
+        }
+        // Hinting instructions don't survive instancing; see `instantiate()`.
+        glyf.write_u16::<BigEndian>(0).unwrap();
+
+        // Write the flags, then the X coordinates, then the Y coordinates. We don't bother with
+        // repeat counts.
+        let (mut x_coordinates, mut y_coordinates) = (vec![], vec![]);
+        let mut last_position = Point2D::new(0, 0);
+        for (position, &on_curve) in positions.iter().zip(on_curve_flags.iter()) {
+            let mut flags = if on_curve { ON_CURVE } else { SimpleFlags::empty() };
+            flags = flags | write_coordinate(&mut x_coordinates,
+                                             position.x - last_position.x,
+                                             X_SHORT_VECTOR,
+                                             THIS_X_IS_SAME);
+            flags = flags | write_coordinate(&mut y_coordinates,
+                                             position.y - last_position.y,
+                                             Y_SHORT_VECTOR,
+                                             THIS_Y_IS_SAME);
+            glyf.push(flags.bits());
+            last_position = *position
+        }
+        glyf.extend_from_slice(&x_coordinates);
+        glyf.extend_from_slice(&y_coordinates);
+
+        Ok((Some(bounds), deltas[positions.len()].x))
+    }
+
+    // Writes out a copy of the given composite glyph with its components moved according to its
+    // deltas. Returns its bounds and how far its origin moved.
+    fn instantiate_composite_glyph(&self,
+                                   mut reader: &[u8],
+                                   head_table: &HeadTable,
+                                   loca_table: &LocaTable,
+                                   gvar_table: Option<&GvarTable>,
+                                   glyph_id: u16,
+                                   coords: &[f32],
+                                   glyf: &mut Vec<u8>)
+                                   -> Result<(Option<GlyphBounds>, f32), FontError> {
+        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
+
+        // Read the components. We copy the transforms verbatim.
+        let mut components = vec![];
+        loop {
+            let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let flags = CompositeFlags::from_bits_truncate(flags);
+            let glyph_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let args = if flags.contains(ARG_1_AND_2_ARE_WORDS) {
+                Point2D::new(try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)),
+                             try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)))
+            } else if flags.contains(ARGS_ARE_XY_VALUES) {
+                Point2D::new(try!(reader.read_i8().map_err(FontError::eof)) as i16,
+                             try!(reader.read_i8().map_err(FontError::eof)) as i16)
+            } else {
+                Point2D::new(try!(reader.read_u8().map_err(FontError::eof)) as i16,
+                             try!(reader.read_u8().map_err(FontError::eof)) as i16)
+            };
+
+            let transform_size = if flags.contains(WE_HAVE_A_SCALE) {
+                mem::size_of::<i16>()
+            } else if flags.contains(WE_HAVE_AN_X_AND_Y_SCALE) {
+                mem::size_of::<i16>() * 2
+            } else if flags.contains(WE_HAVE_A_TWO_BY_TWO) {
+                mem::size_of::<i16>() * 4
+            } else {
+                0
+            };
+            let transform = try!(reader.get(..transform_size).ok_or(FontError::UnexpectedEof));
+            try!(reader.jump(transform_size).map_err(FontError::eof));
+
+            components.push((flags, glyph_index, args, transform));
 
             if !flags.contains(MORE_COMPONENTS) {
                 break
             }
         }
 
-        Ok(())
+        // Move the components. Only offsets vary; anchor point numbers don't.
+        let mut origin_delta = 0.0;
+        if let Some(gvar_table) = gvar_table {
+            let offsets: Vec<_> = components.iter().map(|&(flags, _, args, _)| {
+                if flags.contains(ARGS_ARE_XY_VALUES) { args } else { Point2D::new(0, 0) }
+            }).collect();
+            let deltas = try!(gvar_table.glyph_deltas(glyph_id, coords, &offsets, &[]));
+            for (component, delta) in components.iter_mut().zip(deltas.iter()) {
+                let (flags, _, ref mut args, _) = *component;
+                if flags.contains(ARGS_ARE_XY_VALUES) {
+                    *args = apply_delta(args, delta)
+                }
+            }
+            origin_delta = deltas[components.len()].x
+        }
+
+        let bounds = try!(self.glyph_bounds(head_table,
+                                            loca_table,
+                                            gvar_table,
+                                            glyph_id,
+                                            coords));
+
+        glyf.write_i16::<BigEndian>(number_of_contours).unwrap();
+        write_bounds(glyf, &bounds);
+        for &(flags, glyph_index, args, transform) in &components {
+            // Offsets may no longer fit in bytes, so always write them as words.
+            let mut flags = flags;
+            flags.remove(WE_HAVE_INSTRUCTIONS);
+            if flags.contains(ARGS_ARE_XY_VALUES) {
+                flags = flags | ARG_1_AND_2_ARE_WORDS
+            }
+            glyf.write_u16::<BigEndian>(flags.bits()).unwrap();
+            glyf.write_u16::<BigEndian>(glyph_index).unwrap();
+            if flags.contains(ARG_1_AND_2_ARE_WORDS) {
+                glyf.write_i16::<BigEndian>(args.x).unwrap();
+                glyf.write_i16::<BigEndian>(args.y).unwrap();
+            } else {
+                glyf.push(args.x as u8);
+                glyf.push(args.y as u8);
+            }
+            glyf.extend_from_slice(transform);
+        }
+
+        Ok((Some(bounds), origin_delta))
     }
 
-    pub fn glyph_bounds(&self, head_table: &HeadTable, loca_table: &LocaTable, glyph_id: u16)
+    /// Returns the boundaries of the given glyph at the instance with the given normalized
+    /// coordinates.
+    ///
+    /// At the default instance, these come straight from the glyph header.
+    pub fn glyph_bounds(&self,
+                        head_table: &HeadTable,
+                        loca_table: &LocaTable,
+                        gvar_table: Option<&GvarTable>,
+                        glyph_id: u16,
+                        coords: &[f32])
                         -> Result<GlyphBounds, FontError> {
+        if gvar_table.is_some() && coords.iter().any(|&coord| coord != 0.0) {
+            let mut bounds: Option<GlyphBounds> = None;
+            try!(self.for_each_point(head_table, loca_table, gvar_table, glyph_id, coords, |point| {
+                let (x, y) = (point.position.x as i32, point.position.y as i32);
+                bounds = Some(match bounds {
+                    None => GlyphBounds { left: x, bottom: y, right: x, top: y },
+                    Some(bounds) => {
+                        GlyphBounds {
+                            left: cmp::min(bounds.left, x),
+                            bottom: cmp::min(bounds.bottom, y),
+                            right: cmp::max(bounds.right, x),
+                            top: cmp::max(bounds.top, y),
+                        }
+                    }
+                })
+            }));
+            return Ok(bounds.unwrap_or_default())
+        }
+
         let mut reader = self.table.bytes;
 
         match try!(loca_table.location_of(head_table, glyph_id)) {
@@ -325,6 +597,161 @@ impl<'a> GlyfTable<'a> {
     }
 }
 
+/// The `glyf` table of an instance of a variable font, along with what the other tables need to
+/// know about it.
+pub struct GlyfInstance {
+    /// The contents of the `glyf` table.
+    pub glyf: Vec<u8>,
+    /// The offset of each glyph in `glyf`, followed by the length of `glyf`, as the long version
+    /// of the `loca` table stores them.
+    pub loca: Vec<u32>,
+    /// The bounds of each glyph, or `None` if it has no outline.
+    pub bounds: Vec<Option<GlyphBounds>>,
+    /// How far the origin of each glyph moved horizontally.
+    pub origin_deltas: Vec<f32>,
+}
+
+// Calls `callback` with each point of the contours of a simple glyph. `next_point` supplies the
+// position of each point in the `glyf` table in turn, along with whether it's on the curve.
+fn for_each_point_in_contours<F, G>(mut endpoints_reader: &[u8],
+                                    number_of_contours: i16,
+                                    mut next_point: G,
+                                    mut callback: F)
+                                    -> Result<(), FontError>
+                                    where F: FnMut(&Point),
+                                          G: FnMut() -> Result<(Point2D<i16>, bool), FontError> {
+    // Now parse the contours.
+    let (mut position, mut point_index) = (Point2D::new(0, 0), 0);
+    for _ in 0..number_of_contours {
+        let contour_point_count = try!(endpoints_reader.read_u16::<BigEndian>()
+                                                       .map_err(FontError::eof)) - point_index + 1;
+
+        let mut first_on_curve_point = None;
+        let mut initial_off_curve_point = None;
+        let mut last_point_was_off_curve = false;
+        let mut point_index_in_contour = 0;
+
+        for _ in 0..contour_point_count {
+            let (next_position, on_curve) = try!(next_point());
+            let delta = next_position - position;
+
+            if last_point_was_off_curve && !on_curve {
+                let position = position + delta / 2;
+
+                // An important edge case!
+                if first_on_curve_point.is_none() {
+                    first_on_curve_point = Some(position)
+                }
+
+                callback(&Point {
+                    position: to_f32(position),
+                    index_in_contour: point_index_in_contour,
+                    kind: PointKind::OnCurve,
+                });
+                point_index_in_contour += 1
+            }
+
+            position = next_position;
+
+            if on_curve && first_on_curve_point.is_none() {
+                first_on_curve_point = Some(position)
+            }
+
+            // Sometimes the initial point is an off curve point. In that case, save it so we
+            // can emit it later when closing the path.
+            if !on_curve && first_on_curve_point.is_none() {
+                debug_assert!(initial_off_curve_point.is_none());
+                initial_off_curve_point = Some(position)
+            } else {
+                callback(&Point {
+                    position: to_f32(position),
+                    kind: if on_curve {
+                        PointKind::OnCurve
+                    } else {
+                        PointKind::QuadControl
+                    },
+                    index_in_contour: point_index_in_contour,
+                });
+                point_index_in_contour += 1
+            }
+
+            last_point_was_off_curve = !on_curve;
+            point_index += 1;
+        }
+
+        // We're about to close the path. Emit the initial off curve point if there was one.
+        if let Some(initial_off_curve_point) = initial_off_curve_point {
+            if last_point_was_off_curve {
+                // Another important edge case!
+                let position = position + (initial_off_curve_point - position) / 2;
+                callback(&Point {
+                    position: to_f32(position),
+                    index_in_contour: point_index_in_contour,
+                    kind: PointKind::OnCurve,
+                });
+                point_index_in_contour += 1
+            }
+
+            callback(&Point {
+                position: to_f32(initial_off_curve_point),
+                kind: PointKind::QuadControl,
+                index_in_contour: point_index_in_contour,
+            });
+            point_index_in_contour += 1
+        }
+
+        // Close the path.
+        if let Some(first_on_curve_point) = first_on_curve_point {
+            callback(&Point {
+                position: to_f32(first_on_curve_point),
+                kind: PointKind::OnCurve,
+                index_in_contour: point_index_in_contour,
+            })
+        }
+    }
+
+    Ok(())
+}
+
+// Writes a glyph's bounding box in the order that glyph headers store it.
+fn write_bounds(glyf: &mut Vec<u8>, bounds: &GlyphBounds) {
+    glyf.write_i16::<BigEndian>(bounds.left as i16).unwrap();
+    glyf.write_i16::<BigEndian>(bounds.bottom as i16).unwrap();
+    glyf.write_i16::<BigEndian>(bounds.right as i16).unwrap();
+    glyf.write_i16::<BigEndian>(bounds.top as i16).unwrap();
+}
+
+// Writes the delta of one coordinate of a point in the most compact form and returns the flags
+// that describe that form.
+fn write_coordinate(coordinates: &mut Vec<u8>,
+                    delta: i16,
+                    short_vector: SimpleFlags,
+                    is_same_or_positive: SimpleFlags)
+                    -> SimpleFlags {
+    if delta == 0 {
+        is_same_or_positive
+    } else if delta > -256 && delta < 256 {
+        coordinates.push((delta as i32).abs() as u8);
+        if delta > 0 { short_vector | is_same_or_positive } else { short_vector }
+    } else {
+        coordinates.write_i16::<BigEndian>(delta).unwrap();
+        SimpleFlags::empty()
+    }
+}
+
+// Moves a point in integer font units by the given delta, rounding to the nearest unit.
+#[inline]
+fn apply_delta(position: &Point2D<i16>, delta: &Point2D<f32>) -> Point2D<i16> {
+    Point2D::new((position.x as f32 + delta.x).round() as i16,
+                 (position.y as f32 + delta.y).round() as i16)
+}
+
+// Converts a position in integer font units to the representation that `Point` uses.
+#[inline]
+fn to_f32(position: Point2D<i16>) -> Point2D<f32> {
+    Point2D::new(position.x as f32, position.y as f32)
+}
+
 // Given a reader pointing to the start of the list of flags, returns the size in bytes of the list
 // of X coordinates and positions the reader at the start of that list.
 #[inline]
@@ -399,6 +826,122 @@ impl<'a> FlagParser<'a> {
     }
 }
 
+// A simple glyph with at least one contour, ready to have its points decoded.
+struct SimpleGlyph<'a> {
+    number_of_contours: i16,
+    // The index of the last point of each contour.
+    end_points: &'a [u8],
+    number_of_points: u16,
+    point_parser: PointParser<'a>,
+}
+
+impl<'a> SimpleGlyph<'a> {
+    // Reads the header of the simple glyph at the start of `reader`. Returns `None` if the glyph
+    // has no contours.
+    fn new(mut reader: &'a [u8]) -> Result<Option<SimpleGlyph<'a>>, FontError> {
+        // Determine how many contours we have.
+        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        if number_of_contours == 0 {
+            return Ok(None)
+        }
+
+        // Skip over the rest of the header.
+        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
+
+        // Find out how many points we have.
+        let end_points = reader;
+        try!(reader.jump(mem::size_of::<u16>() as usize * (number_of_contours as usize - 1))
+                   .map_err(FontError::eof));
+        let number_of_points = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) + 1;
+
+        // Skip over hinting instructions.
+        let instruction_length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(instruction_length as usize).map_err(FontError::eof));
+
+        // Find the offsets of the X and Y coordinates.
+        let flags_reader = reader;
+        let x_coordinate_length = try!(calculate_size_of_x_coordinates(&mut reader,
+                                                                       number_of_points));
+
+        // Set up the streams.
+        let flag_parser = try!(FlagParser::new(flags_reader));
+        let x_coordinate_reader = reader;
+        try!(reader.jump(x_coordinate_length as usize).map_err(FontError::eof));
+        let y_coordinate_reader = reader;
+
+        Ok(Some(SimpleGlyph {
+            number_of_contours: number_of_contours,
+            end_points: end_points,
+            number_of_points: number_of_points,
+            point_parser: PointParser {
+                flag_parser: flag_parser,
+                x_coordinate_reader: x_coordinate_reader,
+                y_coordinate_reader: y_coordinate_reader,
+                position: Point2D::new(0, 0),
+            },
+        }))
+    }
+
+    // Decodes the end points of the contours and the positions and on-curve flags of the points.
+    fn read_points(&mut self) -> Result<(Vec<u16>, Vec<Point2D<i16>>, Vec<bool>), FontError> {
+        let mut end_points_reader = self.end_points;
+        let mut end_points = Vec::with_capacity(self.number_of_contours as usize);
+        for _ in 0..self.number_of_contours {
+            end_points.push(try!(end_points_reader.read_u16::<BigEndian>()
+                                                  .map_err(FontError::eof)))
+        }
+
+        let mut positions = Vec::with_capacity(self.number_of_points as usize);
+        let mut on_curve_flags = Vec::with_capacity(self.number_of_points as usize);
+        for _ in 0..self.number_of_points {
+            let (position, on_curve) = try!(self.point_parser.next());
+            positions.push(position);
+            on_curve_flags.push(on_curve)
+        }
+
+        Ok((end_points, positions, on_curve_flags))
+    }
+}
+
+// Decodes the positions of the points of a simple glyph from its flags and coordinates.
+struct PointParser<'a> {
+    flag_parser: FlagParser<'a>,
+    x_coordinate_reader: &'a [u8],
+    y_coordinate_reader: &'a [u8],
+    position: Point2D<i16>,
+}
+
+impl<'a> PointParser<'a> {
+    // Returns the position of the next point and whether it's on the curve.
+    fn next(&mut self) -> Result<(Point2D<i16>, bool), FontError> {
+        let flags = SimpleFlags::from_bits_truncate(*self.flag_parser.current);
+        try!(self.flag_parser.next());
+
+        let mut delta = Point2D::new(0, 0);
+        if flags.contains(X_SHORT_VECTOR) {
+            delta.x = try!(self.x_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
+            if !flags.contains(THIS_X_IS_SAME) {
+                delta.x = -delta.x
+            }
+        } else if !flags.contains(THIS_X_IS_SAME) {
+            delta.x = try!(self.x_coordinate_reader.read_i16::<BigEndian>()
+                                                   .map_err(FontError::eof))
+        }
+        if flags.contains(Y_SHORT_VECTOR) {
+            delta.y = try!(self.y_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
+            if !flags.contains(THIS_Y_IS_SAME) {
+                delta.y = -delta.y
+            }
+        } else if !flags.contains(THIS_Y_IS_SAME) {
+            delta.y = try!(self.y_coordinate_reader.read_i16::<BigEndian>()
+                                                   .map_err(FontError::eof))
+        }
+
+        self.position = self.position + delta;
+        Ok((self.position, flags.contains(ON_CURVE)))
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Mat3x2 {
     m00: F2Dot14,
@@ -421,8 +964,8 @@ impl Mat3x2 {
     fn transform(&self, point: &Point) -> Point {
         let p = point.position;
         Point {
-            position: Point2D::new(self.m00 * p.x + self.m01 * p.y + self.m02,
-                                   self.m10 * p.x + self.m11 * p.y + self.m12),
+            position: Point2D::new(self.m00 * p.x + self.m01 * p.y + self.m02 as f32,
+                                   self.m10 * p.x + self.m11 * p.y + self.m12 as f32),
             ..*point
         }
     }
@@ -431,12 +974,12 @@ impl Mat3x2 {
 #[derive(Copy, Clone, Debug)]
 struct F2Dot14(i16);
 
-impl Mul<i16> for F2Dot14 {
-    type Output = i16;
+impl Mul<f32> for F2Dot14 {
+    type Output = f32;
 
     #[inline]
-    fn mul(self, other: i16) -> i16 {
-        ((self.0 as i32 * other as i32) >> 14) as i16
+    fn mul(self, other: f32) -> f32 {
+        self.0 as f32 / F2DOT14_ONE.0 as f32 * other
     }
 }
 
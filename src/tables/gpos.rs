@@ -0,0 +1,495 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `GPOS` glyph positioning table.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/gpos.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{FontTable, GlyphClass};
+use std::mem;
+use tables::gdef::GdefTable;
+use tables::layout::{self, GlyphSkipper, LayoutLists};
+use util::Jump;
+
+pub const TAG: u32 = ((b'G' as u32) << 24) |
+                      ((b'P' as u32) << 16) |
+                      ((b'O' as u32) << 8)  |
+                       (b'S' as u32);
+
+const FEATURE_KERN: u32 = ((b'k' as u32) << 24) |
+                           ((b'e' as u32) << 16) |
+                           ((b'r' as u32) << 8)  |
+                            (b'n' as u32);
+const FEATURE_MARK: u32 = ((b'm' as u32) << 24) |
+                           ((b'a' as u32) << 16) |
+                           ((b'r' as u32) << 8)  |
+                            (b'k' as u32);
+const FEATURE_MKMK: u32 = ((b'm' as u32) << 24) |
+                           ((b'k' as u32) << 16) |
+                           ((b'm' as u32) << 8)  |
+                            (b'k' as u32);
+
+const LOOKUP_TYPE_PAIR_ADJUSTMENT: u16 = 2;
+const LOOKUP_TYPE_MARK_TO_BASE: u16 = 4;
+const LOOKUP_TYPE_MARK_TO_LIGATURE: u16 = 5;
+const LOOKUP_TYPE_MARK_TO_MARK: u16 = 6;
+const LOOKUP_TYPE_EXTENSION: u16 = 9;
+
+bitflags! {
+    flags ValueFormat: u16 {
+        const X_PLACEMENT = 1 << 0,
+        const Y_PLACEMENT = 1 << 1,
+        const X_ADVANCE = 1 << 2,
+        const Y_ADVANCE = 1 << 3,
+        const X_PLACEMENT_DEVICE = 1 << 4,
+        const Y_PLACEMENT_DEVICE = 1 << 5,
+        const X_ADVANCE_DEVICE = 1 << 6,
+        const Y_ADVANCE_DEVICE = 1 << 7,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GposTable<'a> {
+    lists: LayoutLists<'a>,
+    // The lookups that the `kern` feature enables, in lookup list order.
+    kern_lookups: Vec<u16>,
+    // The lookups that the `mark` and `mkmk` features enable for any script, in lookup list order.
+    mark_lookups: Vec<u16>,
+}
+
+impl<'a> GposTable<'a> {
+    pub fn new(table: FontTable) -> Result<GposTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 1.1 only adds feature variations, which we ignore.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGposVersion)
+        }
+
+        let lists = try!(LayoutLists::new(table, &mut reader));
+        let kern_lookups = try!(lists.lookups_for_features(&[FEATURE_KERN]));
+
+        // Fonts register mark attachment under the script of the marks, so Arabic and Hebrew
+        // marks would never attach if we only looked at `latn`. Each lookup only covers the marks
+        // of its own script, so there's no harm in gathering them from every script.
+        let mark_lookups = try!(lists.lookups_for_features_in_all_scripts(&[FEATURE_MARK,
+                                                                             FEATURE_MKMK]));
+
+        Ok(GposTable {
+            lists: lists,
+            kern_lookups: kern_lookups,
+            mark_lookups: mark_lookups,
+        })
+    }
+
+    /// Returns true if this table has a `kern` feature for the default script and language.
+    #[inline]
+    pub fn has_kerning(&self) -> bool {
+        !self.kern_lookups.is_empty()
+    }
+
+    /// Returns the horizontal advance adjustment for the first glyph of the given pair.
+    ///
+    /// Adjustments to the second glyph are ignored, as are device tables.
+    pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16)
+                                  -> Result<i16, FontError> {
+        let mut kerning = 0;
+        for &lookup_index in &self.kern_lookups {
+            let lookup = try!(self.lists.lookup(lookup_index));
+            for subtable_index in 0..lookup.subtable_count {
+                let mut subtable = try!(lookup.subtable(subtable_index));
+                let mut lookup_type = lookup.lookup_type;
+                if lookup_type == LOOKUP_TYPE_EXTENSION {
+                    let (extension_lookup_type, extension) =
+                        try!(layout::resolve_extension(subtable));
+                    lookup_type = extension_lookup_type;
+                    subtable = extension;
+                }
+
+                if lookup_type != LOOKUP_TYPE_PAIR_ADJUSTMENT {
+                    continue
+                }
+
+                // The first subtable that covers the pair wins.
+                if let Some(value) = try!(pair_adjustment(subtable,
+                                                          left_glyph_id,
+                                                          right_glyph_id)) {
+                    kerning += value.x_advance;
+                    break
+                }
+            }
+        }
+
+        Ok(kerning)
+    }
+
+    /// Determines which glyphs in the given run are marks that attach to an earlier glyph and
+    /// where they attach.
+    ///
+    /// The returned vector has one entry per glyph. If a glyph is attached by more than one
+    /// lookup, the last one wins.
+    ///
+    /// If the font has a `GDEF` table, it should be supplied so that marks can be told apart from
+    /// base glyphs and so that glyphs that lookups ignore are skipped over.
+    pub fn mark_attachments(&self, glyph_ids: &[u16], gdef: Option<GdefTable>)
+                            -> Result<Vec<Option<MarkAttachment>>, FontError> {
+        let mut attachments = vec![None; glyph_ids.len()];
+        for &lookup_index in &self.mark_lookups {
+            let lookup = try!(self.lists.lookup(lookup_index));
+            let skipper = lookup.skipper(gdef);
+            for index in 1..glyph_ids.len() {
+                if try!(skipper.skips(glyph_ids[index])) {
+                    continue
+                }
+
+                for subtable_index in 0..lookup.subtable_count {
+                    let mut subtable = try!(lookup.subtable(subtable_index));
+                    let mut lookup_type = lookup.lookup_type;
+                    if lookup_type == LOOKUP_TYPE_EXTENSION {
+                        let (extension_lookup_type, extension) =
+                            try!(layout::resolve_extension(subtable));
+                        lookup_type = extension_lookup_type;
+                        subtable = extension;
+                    }
+
+                    let attachment = match lookup_type {
+                        LOOKUP_TYPE_MARK_TO_BASE | LOOKUP_TYPE_MARK_TO_LIGATURE |
+                        LOOKUP_TYPE_MARK_TO_MARK => {
+                            try!(mark_attachment(lookup_type,
+                                                 subtable,
+                                                 gdef,
+                                                 &skipper,
+                                                 glyph_ids,
+                                                 index))
+                        }
+                        _ => None,
+                    };
+
+                    if attachment.is_some() {
+                        attachments[index] = attachment;
+                        break
+                    }
+                }
+            }
+        }
+
+        Ok(attachments)
+    }
+}
+
+/// Describes how a mark glyph attaches to a preceding glyph.
+#[derive(Clone, Copy, Debug)]
+pub struct MarkAttachment {
+    /// The index in the glyph run of the glyph that this mark attaches to.
+    pub base_index: usize,
+    /// The horizontal distance from the origin of the base glyph to the origin of the mark, in
+    /// font units.
+    pub x_offset: i16,
+    /// The vertical distance from the origin of the base glyph to the origin of the mark, in font
+    /// units.
+    pub y_offset: i16,
+}
+
+/// The adjustments that a value record specifies, in font units.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ValueRecord {
+    pub x_placement: i16,
+    pub y_placement: i16,
+    pub x_advance: i16,
+    pub y_advance: i16,
+}
+
+impl ValueRecord {
+    fn read(reader: &mut &[u8], format: ValueFormat) -> Result<ValueRecord, FontError> {
+        let mut value = ValueRecord::default();
+        if format.contains(X_PLACEMENT) {
+            value.x_placement = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+        }
+        if format.contains(Y_PLACEMENT) {
+            value.y_placement = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+        }
+        if format.contains(X_ADVANCE) {
+            value.x_advance = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+        }
+        if format.contains(Y_ADVANCE) {
+            value.y_advance = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+        }
+
+        // Skip over the device table offsets.
+        let device_count = (format & (X_PLACEMENT_DEVICE | Y_PLACEMENT_DEVICE |
+                                      X_ADVANCE_DEVICE | Y_ADVANCE_DEVICE)).bits().count_ones();
+        try!(reader.jump(device_count as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+
+        Ok(value)
+    }
+}
+
+// An attachment point on a glyph, in font units.
+#[derive(Clone, Copy, Debug)]
+struct Anchor {
+    x: i16,
+    y: i16,
+}
+
+impl Anchor {
+    // All three anchor formats start with the coordinates. We ignore the contour point of format 2
+    // and the device tables of format 3.
+    fn read(mut reader: &[u8]) -> Result<Anchor, FontError> {
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if format < 1 || format > 3 {
+            return Err(FontError::UnknownFormat)
+        }
+
+        Ok(Anchor {
+            x: try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)),
+            y: try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)),
+        })
+    }
+}
+
+// Returns the size in bytes of a value record with the given format.
+#[inline]
+fn value_record_size(format: ValueFormat) -> usize {
+    format.bits().count_ones() as usize * mem::size_of::<u16>()
+}
+
+// Lookup type 2: Looks up the adjustment for the first glyph of a pair.
+fn pair_adjustment(subtable: &[u8], left_glyph_id: u16, right_glyph_id: u16)
+                   -> Result<Option<ValueRecord>, FontError> {
+    let mut reader = subtable;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage = try!(layout::subtable_at(subtable, coverage as usize));
+    let value_format_1 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let value_format_1 = ValueFormat::from_bits_truncate(value_format_1);
+    let value_format_2 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let value_format_2 = ValueFormat::from_bits_truncate(value_format_2);
+
+    let coverage_index = match try!(layout::coverage_index(coverage, left_glyph_id)) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    match format {
+        1 => {
+            // Individual glyph pairs, grouped by the first glyph.
+            let pair_set_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if coverage_index >= pair_set_count {
+                return Ok(None)
+            }
+
+            try!(reader.jump(coverage_index as usize * mem::size_of::<u16>())
+                       .map_err(FontError::eof));
+            let pair_set = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let mut pair_set = try!(layout::subtable_at(subtable, pair_set as usize));
+
+            // Binary search for the second glyph.
+            let pair_value_count = try!(pair_set.read_u16::<BigEndian>().map_err(FontError::eof));
+            let record_size = mem::size_of::<u16>() + value_record_size(value_format_1) +
+                value_record_size(value_format_2);
+            let (mut low, mut high) = (0, pair_value_count);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut record = pair_set;
+                try!(record.jump(mid as usize * record_size).map_err(FontError::eof));
+                let second_glyph = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+                if right_glyph_id < second_glyph {
+                    high = mid
+                } else if right_glyph_id > second_glyph {
+                    low = mid + 1
+                } else {
+                    return Ok(Some(try!(ValueRecord::read(&mut record, value_format_1))))
+                }
+            }
+
+            Ok(None)
+        }
+        2 => {
+            // A matrix of adjustments indexed by the classes of both glyphs.
+            let class_def_1 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_def_1 = try!(layout::subtable_at(subtable, class_def_1 as usize));
+            let class_def_2 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_def_2 = try!(layout::subtable_at(subtable, class_def_2 as usize));
+            let class_1_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_2_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let class_1 = try!(layout::class_of_glyph(class_def_1, left_glyph_id));
+            let class_2 = try!(layout::class_of_glyph(class_def_2, right_glyph_id));
+            if class_1 >= class_1_count || class_2 >= class_2_count {
+                return Ok(None)
+            }
+
+            let record_size = value_record_size(value_format_1) +
+                value_record_size(value_format_2);
+            let record_index = class_1 as usize * class_2_count as usize + class_2 as usize;
+            try!(reader.jump(record_index * record_size).map_err(FontError::eof));
+            Ok(Some(try!(ValueRecord::read(&mut reader, value_format_1))))
+        }
+        _ => Err(FontError::UnknownFormat),
+    }
+}
+
+// Lookup types 4, 5, and 6: Attaches the mark at the given index to a preceding base glyph,
+// ligature, or mark, respectively.
+//
+// All three subtable formats share the same layout, differing only in the table that holds the
+// anchors of the glyph being attached to.
+fn mark_attachment(lookup_type: u16,
+                   subtable: &[u8],
+                   gdef: Option<GdefTable>,
+                   skipper: &GlyphSkipper,
+                   glyph_ids: &[u16],
+                   index: usize)
+                   -> Result<Option<MarkAttachment>, FontError> {
+    let mut reader = subtable;
+    if try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 1 {
+        return Err(FontError::UnknownFormat)
+    }
+
+    let mark_coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mark_coverage = try!(layout::subtable_at(subtable, mark_coverage as usize));
+    let base_coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let base_coverage = try!(layout::subtable_at(subtable, base_coverage as usize));
+    let mark_class_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mark_array = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mark_array = try!(layout::subtable_at(subtable, mark_array as usize));
+    let base_array = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let base_array = try!(layout::subtable_at(subtable, base_array as usize));
+
+    let mark_index = match try!(layout::coverage_index(mark_coverage, glyph_ids[index])) {
+        None => return Ok(None),
+        Some(mark_index) => mark_index,
+    };
+
+    // Find the glyph to attach to. Marks attach to the preceding mark that the lookup doesn't
+    // ignore; otherwise, we skip over any other marks to find the base. Without a `GDEF` table,
+    // we treat the glyphs that this subtable covers as marks.
+    let base_index = if lookup_type == LOOKUP_TYPE_MARK_TO_MARK {
+        match try!(skipper.previous(glyph_ids, index)) {
+            None => return Ok(None),
+            Some(base_index) => base_index,
+        }
+    } else {
+        let mut base_index = index - 1;
+        while base_index > 0 {
+            let is_mark = match gdef {
+                Some(gdef) => try!(gdef.glyph_class(glyph_ids[base_index])) ==
+                    Some(GlyphClass::Mark),
+                None => try!(layout::coverage_index(mark_coverage,
+                                                    glyph_ids[base_index])).is_some(),
+            };
+            if !is_mark {
+                break
+            }
+            base_index -= 1
+        }
+        base_index
+    };
+
+    let base_coverage_index = match try!(layout::coverage_index(base_coverage,
+                                                                glyph_ids[base_index])) {
+        None => return Ok(None),
+        Some(base_coverage_index) => base_coverage_index,
+    };
+
+    // Look up the mark's class and anchor.
+    let mut mark_record = mark_array;
+    let mark_count = try!(mark_record.read_u16::<BigEndian>().map_err(FontError::eof));
+    if mark_index >= mark_count {
+        return Ok(None)
+    }
+    try!(mark_record.jump(mark_index as usize * mem::size_of::<[u16; 2]>())
+                    .map_err(FontError::eof));
+    let mark_class = try!(mark_record.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mark_anchor = try!(mark_record.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mark_anchor = try!(Anchor::read(try!(layout::subtable_at(mark_array,
+                                                                 mark_anchor as usize))));
+    if mark_class >= mark_class_count {
+        return Ok(None)
+    }
+
+    // Look up the base anchor for that class. For ligatures, we don't know which component the
+    // mark belongs to, so we assume the last one.
+    let base_anchor = if lookup_type == LOOKUP_TYPE_MARK_TO_LIGATURE {
+        let ligature_attach = match try!(anchor_row(base_array, base_coverage_index, 1)) {
+            None => return Ok(None),
+            Some(ligature_attach) => ligature_attach,
+        };
+
+        let mut ligature_attach_reader = ligature_attach;
+        let ligature_attach_offset = try!(ligature_attach_reader.read_u16::<BigEndian>()
+                                                                .map_err(FontError::eof));
+        let ligature_attach = try!(layout::subtable_at(base_array,
+                                                       ligature_attach_offset as usize));
+
+        let mut reader = ligature_attach;
+        let component_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if component_count == 0 {
+            return Ok(None)
+        }
+        try!(anchor_in_row(ligature_attach,
+                           try!(anchor_row(ligature_attach,
+                                           component_count - 1,
+                                           mark_class_count)),
+                           mark_class))
+    } else {
+        try!(anchor_in_row(base_array,
+                           try!(anchor_row(base_array, base_coverage_index, mark_class_count)),
+                           mark_class))
+    };
+
+    match base_anchor {
+        None => Ok(None),
+        Some(base_anchor) => {
+            Ok(Some(MarkAttachment {
+                base_index: base_index,
+                x_offset: base_anchor.x - mark_anchor.x,
+                y_offset: base_anchor.y - mark_anchor.y,
+            }))
+        }
+    }
+}
+
+// Given an array of records of `record_size` 16-bit offsets, preceded by a count, returns the
+// bytes of the record with the given index.
+fn anchor_row(array: &[u8], row: u16, record_size: u16) -> Result<Option<&[u8]>, FontError> {
+    let mut reader = array;
+    let row_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if row >= row_count {
+        return Ok(None)
+    }
+
+    try!(reader.jump(row as usize * record_size as usize * mem::size_of::<u16>())
+               .map_err(FontError::eof));
+    Ok(Some(reader))
+}
+
+// Reads the anchor for the given mark class out of a row of anchor offsets. The offsets are
+// relative to `base`. A null offset means that there is no anchor.
+fn anchor_in_row(base: &[u8], row: Option<&[u8]>, mark_class: u16)
+                 -> Result<Option<Anchor>, FontError> {
+    let mut reader = match row {
+        None => return Ok(None),
+        Some(row) => row,
+    };
+
+    try!(reader.jump(mark_class as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+    let anchor = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if anchor == 0 {
+        return Ok(None)
+    }
+
+    Ok(Some(try!(Anchor::read(try!(layout::subtable_at(base, anchor as usize))))))
+}
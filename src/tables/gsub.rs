@@ -0,0 +1,638 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `GSUB` glyph substitution table.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/gsub.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use tables::gdef::GdefTable;
+use tables::layout::{self, GlyphSkipper, LayoutLists, Lookup};
+use util::Jump;
+
+pub const TAG: u32 = ((b'G' as u32) << 24) |
+                      ((b'S' as u32) << 16) |
+                      ((b'U' as u32) << 8)  |
+                       (b'B' as u32);
+
+const FEATURE_CCMP: u32 = ((b'c' as u32) << 24) |
+                           ((b'c' as u32) << 16) |
+                           ((b'm' as u32) << 8)  |
+                            (b'p' as u32);
+const FEATURE_LOCL: u32 = ((b'l' as u32) << 24) |
+                           ((b'o' as u32) << 16) |
+                           ((b'c' as u32) << 8)  |
+                            (b'l' as u32);
+const FEATURE_RLIG: u32 = ((b'r' as u32) << 24) |
+                           ((b'l' as u32) << 16) |
+                           ((b'i' as u32) << 8)  |
+                            (b'g' as u32);
+const FEATURE_LIGA: u32 = ((b'l' as u32) << 24) |
+                           ((b'i' as u32) << 16) |
+                           ((b'g' as u32) << 8)  |
+                            (b'a' as u32);
+const FEATURE_CLIG: u32 = ((b'c' as u32) << 24) |
+                           ((b'l' as u32) << 16) |
+                           ((b'i' as u32) << 8)  |
+                            (b'g' as u32);
+const FEATURE_CALT: u32 = ((b'c' as u32) << 24) |
+                           ((b'a' as u32) << 16) |
+                           ((b'l' as u32) << 8)  |
+                            (b't' as u32);
+const FEATURE_RCLT: u32 = ((b'r' as u32) << 24) |
+                           ((b'c' as u32) << 16) |
+                           ((b'l' as u32) << 8)  |
+                            (b't' as u32);
+
+// The features that are applied to all text.
+static DEFAULT_FEATURES: [u32; 7] = [
+    FEATURE_CCMP,
+    FEATURE_LOCL,
+    FEATURE_RLIG,
+    FEATURE_LIGA,
+    FEATURE_CLIG,
+    FEATURE_CALT,
+    FEATURE_RCLT,
+];
+
+const LOOKUP_TYPE_SINGLE: u16 = 1;
+const LOOKUP_TYPE_MULTIPLE: u16 = 2;
+const LOOKUP_TYPE_ALTERNATE: u16 = 3;
+const LOOKUP_TYPE_LIGATURE: u16 = 4;
+const LOOKUP_TYPE_CHAINED_CONTEXT: u16 = 6;
+const LOOKUP_TYPE_EXTENSION: u16 = 7;
+
+// Limits how deeply contextual lookups may invoke other lookups.
+const MAX_NESTING_DEPTH: u8 = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GsubTable<'a> {
+    lists: LayoutLists<'a>,
+}
+
+impl<'a> GsubTable<'a> {
+    pub fn new(table: FontTable) -> Result<GsubTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 1.1 only adds feature variations, which we ignore.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGsubVersion)
+        }
+
+        Ok(GsubTable {
+            lists: try!(LayoutLists::new(table, &mut reader)),
+        })
+    }
+
+    /// Applies the default features for the default script and language to the given run of
+    /// glyphs.
+    ///
+    /// If the font has a `GDEF` table, it should be supplied so that glyphs that lookups ignore
+    /// (for example, marks in the middle of a ligature) are skipped over.
+    pub fn substitute(&self, glyph_ids: &mut Vec<u16>, gdef: Option<GdefTable>)
+                      -> Result<(), FontError> {
+        for lookup_index in try!(self.lists.lookups_for_features(&DEFAULT_FEATURES)) {
+            let lookup = try!(self.lists.lookup(lookup_index));
+            let skipper = lookup.skipper(gdef);
+
+            let mut index = 0;
+            while index < glyph_ids.len() {
+                if try!(skipper.skips(glyph_ids[index])) {
+                    index += 1;
+                    continue
+                }
+
+                index = match try!(self.apply_lookup(&lookup, gdef, glyph_ids, index, 0)) {
+                    Some(next_index) => next_index,
+                    None => index + 1,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Applies the first subtable of the lookup that matches at the given index.
+    //
+    // Returns the index at which to resume processing, or `None` if nothing matched.
+    fn apply_lookup(&self,
+                    lookup: &Lookup,
+                    gdef: Option<GdefTable>,
+                    glyph_ids: &mut Vec<u16>,
+                    index: usize,
+                    depth: u8)
+                    -> Result<Option<usize>, FontError> {
+        let skipper = lookup.skipper(gdef);
+        for subtable_index in 0..lookup.subtable_count {
+            let mut subtable = try!(lookup.subtable(subtable_index));
+            let mut lookup_type = lookup.lookup_type;
+            if lookup_type == LOOKUP_TYPE_EXTENSION {
+                let (extension_lookup_type, extension) = try!(layout::resolve_extension(subtable));
+                lookup_type = extension_lookup_type;
+                subtable = extension;
+            }
+
+            let result = match lookup_type {
+                LOOKUP_TYPE_SINGLE => try!(apply_single(subtable, glyph_ids, index)),
+                LOOKUP_TYPE_MULTIPLE => try!(apply_multiple(subtable, glyph_ids, index)),
+                LOOKUP_TYPE_ALTERNATE => try!(apply_alternate(subtable, glyph_ids, index)),
+                LOOKUP_TYPE_LIGATURE => {
+                    try!(apply_ligature(subtable, &skipper, glyph_ids, index))
+                }
+                LOOKUP_TYPE_CHAINED_CONTEXT => {
+                    try!(self.apply_chained_context(subtable, gdef, &skipper, glyph_ids, index,
+                                                    depth))
+                }
+                _ => None,
+            };
+
+            if result.is_some() {
+                return Ok(result)
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn apply_chained_context(&self,
+                             subtable: &[u8],
+                             gdef: Option<GdefTable>,
+                             skipper: &GlyphSkipper,
+                             glyph_ids: &mut Vec<u16>,
+                             index: usize,
+                             depth: u8)
+                             -> Result<Option<usize>, FontError> {
+        let mut reader = subtable;
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let rule = match format {
+            1 => {
+                // Rules are grouped by the coverage index of the first glyph.
+                let coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let coverage = try!(layout::subtable_at(subtable, coverage as usize));
+                let coverage_index = match try!(layout::coverage_index(coverage,
+                                                                       glyph_ids[index])) {
+                    None => return Ok(None),
+                    Some(coverage_index) => coverage_index,
+                };
+
+                try!(find_matching_rule(subtable,
+                                        reader,
+                                        coverage_index,
+                                        &Matcher::Glyph,
+                                        &Matcher::Glyph,
+                                        &Matcher::Glyph,
+                                        skipper,
+                                        glyph_ids,
+                                        index))
+            }
+            2 => {
+                // Rules are grouped by the class of the first glyph.
+                let coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let coverage = try!(layout::subtable_at(subtable, coverage as usize));
+                if try!(layout::coverage_index(coverage, glyph_ids[index])).is_none() {
+                    return Ok(None)
+                }
+
+                let mut class_defs = [subtable; 3];
+                for class_def in &mut class_defs {
+                    let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                    *class_def = try!(layout::subtable_at(subtable, offset as usize));
+                }
+
+                let class = try!(layout::class_of_glyph(class_defs[1], glyph_ids[index]));
+                try!(find_matching_rule(subtable,
+                                        reader,
+                                        class,
+                                        &Matcher::Class(class_defs[0]),
+                                        &Matcher::Class(class_defs[1]),
+                                        &Matcher::Class(class_defs[2]),
+                                        skipper,
+                                        glyph_ids,
+                                        index))
+            }
+            3 => {
+                // There is exactly one rule, whose sequences are coverage tables.
+                let matcher = Matcher::Coverage(subtable);
+                try!(match_chained_rule(reader,
+                                        &matcher,
+                                        &matcher,
+                                        &matcher,
+                                        0,
+                                        skipper,
+                                        glyph_ids,
+                                        index)).map(|positions| (reader, positions))
+            }
+            _ => return Err(FontError::UnknownFormat),
+        };
+
+        match rule {
+            None => Ok(None),
+            Some((rule, positions)) => {
+                let first_input_is_implicit = format != 3;
+                self.apply_chained_rule(rule,
+                                        first_input_is_implicit,
+                                        positions,
+                                        gdef,
+                                        glyph_ids,
+                                        index,
+                                        depth).map(Some)
+            }
+        }
+    }
+
+    // Applies the substitution lookup records of a chained rule that has already been matched.
+    // `positions` holds the index of each glyph in the input sequence.
+    //
+    // Returns the index just past the end of the input sequence.
+    fn apply_chained_rule(&self,
+                          mut reader: &[u8],
+                          first_input_is_implicit: bool,
+                          mut positions: Vec<usize>,
+                          gdef: Option<GdefTable>,
+                          glyph_ids: &mut Vec<u16>,
+                          index: usize,
+                          depth: u8)
+                          -> Result<usize, FontError> {
+        // Skip over the backtrack sequence.
+        let backtrack_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(backtrack_count as usize * mem::size_of::<u16>())
+                   .map_err(FontError::eof));
+
+        // Skip over the input sequence.
+        let input_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as usize;
+        let stored_input_count = if first_input_is_implicit {
+            input_count.saturating_sub(1)
+        } else {
+            input_count
+        };
+        try!(reader.jump(stored_input_count * mem::size_of::<u16>()).map_err(FontError::eof));
+
+        // Skip over the lookahead sequence.
+        let lookahead_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(lookahead_count as usize * mem::size_of::<u16>())
+                   .map_err(FontError::eof));
+
+        // Apply each nested lookup in turn, keeping track of any changes in the glyph count.
+        let substitution_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        for _ in 0..substitution_count {
+            let sequence_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let lookup_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if depth >= MAX_NESTING_DEPTH {
+                continue
+            }
+
+            let position = match positions.get(sequence_index as usize) {
+                Some(&position) if position < glyph_ids.len() => position,
+                _ => continue,
+            };
+
+            let lookup = try!(self.lists.lookup(lookup_index));
+            let old_length = glyph_ids.len();
+            try!(self.apply_lookup(&lookup, gdef, glyph_ids, position, depth + 1));
+
+            let new_length = glyph_ids.len();
+            for later_position in &mut positions[(sequence_index as usize + 1)..] {
+                *later_position = if new_length >= old_length {
+                    *later_position + (new_length - old_length)
+                } else {
+                    (*later_position).saturating_sub(old_length - new_length)
+                                     .max(position + 1)
+                }
+            }
+        }
+
+        let end = match positions.last() {
+            None => index + 1,
+            Some(&last_position) => last_position + 1,
+        };
+        Ok(end.max(index + 1).min(glyph_ids.len()))
+    }
+}
+
+// How the values in a backtrack, input, or lookahead sequence are compared against glyphs.
+enum Matcher<'a> {
+    // Values are glyph IDs.
+    Glyph,
+    // Values are classes in the given class definition table.
+    Class(&'a [u8]),
+    // Values are offsets to coverage tables from the start of the given subtable.
+    Coverage(&'a [u8]),
+}
+
+impl<'a> Matcher<'a> {
+    fn matches(&self, value: u16, glyph_id: u16) -> Result<bool, FontError> {
+        match *self {
+            Matcher::Glyph => Ok(value == glyph_id),
+            Matcher::Class(class_def) => {
+                Ok(try!(layout::class_of_glyph(class_def, glyph_id)) == value)
+            }
+            Matcher::Coverage(subtable) => {
+                let coverage = try!(layout::subtable_at(subtable, value as usize));
+                Ok(try!(layout::coverage_index(coverage, glyph_id)).is_some())
+            }
+        }
+    }
+}
+
+// Finds the first rule in the given rule set that matches at the given index. `reader` must
+// point to the rule set count of a format 1 or 2 chained context subtable.
+//
+// Returns the rule along with the positions of its input glyphs.
+fn find_matching_rule<'b>(subtable: &'b [u8],
+                          mut reader: &'b [u8],
+                          rule_set_index: u16,
+                          backtrack: &Matcher,
+                          input: &Matcher,
+                          lookahead: &Matcher,
+                          skipper: &GlyphSkipper,
+                          glyph_ids: &[u16],
+                          index: usize)
+                          -> Result<Option<(&'b [u8], Vec<usize>)>, FontError> {
+    let rule_set_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if rule_set_index >= rule_set_count {
+        return Ok(None)
+    }
+
+    try!(reader.jump(rule_set_index as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+    let rule_set = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if rule_set == 0 {
+        return Ok(None)
+    }
+
+    let rule_set = try!(layout::subtable_at(subtable, rule_set as usize));
+    let mut reader = rule_set;
+    let rule_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    for _ in 0..rule_count {
+        let rule = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let rule = try!(layout::subtable_at(rule_set, rule as usize));
+        if let Some(positions) = try!(match_chained_rule(rule,
+                                                         backtrack,
+                                                         input,
+                                                         lookahead,
+                                                         1,
+                                                         skipper,
+                                                         glyph_ids,
+                                                         index)) {
+            return Ok(Some((rule, positions)))
+        }
+    }
+
+    Ok(None)
+}
+
+// Determines whether the chained rule that `reader` points to matches at the given index,
+// skipping over any glyphs that the lookup ignores.
+//
+// `input_start` is 1 if the rule omits the first input glyph (because the rule set was already
+// selected by it) or 0 otherwise.
+//
+// If the rule matches, returns the positions of the glyphs in the input sequence.
+fn match_chained_rule(mut reader: &[u8],
+                      backtrack: &Matcher,
+                      input: &Matcher,
+                      lookahead: &Matcher,
+                      input_start: usize,
+                      skipper: &GlyphSkipper,
+                      glyph_ids: &[u16],
+                      index: usize)
+                      -> Result<Option<Vec<usize>>, FontError> {
+    // The backtrack sequence is stored in reverse order, starting with the glyph just before the
+    // input sequence.
+    let backtrack_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mut position = index;
+    for _ in 0..backtrack_count {
+        let value = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        position = match try!(skipper.previous(glyph_ids, position)) {
+            None => return Ok(None),
+            Some(position) => position,
+        };
+        if !try!(backtrack.matches(value, glyph_ids[position])) {
+            return Ok(None)
+        }
+    }
+
+    let input_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as usize;
+    if input_count == 0 {
+        return Ok(None)
+    }
+    let mut positions = Vec::with_capacity(input_count);
+    positions.push(index);
+    for input_index in 1..input_count {
+        let position = match try!(skipper.next(glyph_ids, positions[input_index - 1])) {
+            None => return Ok(None),
+            Some(position) => position,
+        };
+        positions.push(position)
+    }
+    for input_index in input_start..input_count {
+        let value = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if !try!(input.matches(value, glyph_ids[positions[input_index]])) {
+            return Ok(None)
+        }
+    }
+
+    let lookahead_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mut position = positions[input_count - 1];
+    for _ in 0..lookahead_count {
+        let value = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        position = match try!(skipper.next(glyph_ids, position)) {
+            None => return Ok(None),
+            Some(position) => position,
+        };
+        if !try!(lookahead.matches(value, glyph_ids[position])) {
+            return Ok(None)
+        }
+    }
+
+    Ok(Some(positions))
+}
+
+// Looks up the glyph at the given index in the subtable's coverage table. `reader` must point
+// just past the subtable format.
+fn covered_index(subtable: &[u8], reader: &mut &[u8], glyph_id: u16)
+                 -> Result<Option<u16>, FontError> {
+    let coverage = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage = try!(layout::subtable_at(subtable, coverage as usize));
+    layout::coverage_index(coverage, glyph_id)
+}
+
+// Reads the `index`th offset in an array of 16-bit offsets and returns the table it points to.
+fn offset_array_entry<'a>(base: &'a [u8], mut reader: &[u8], index: u16)
+                          -> Result<Option<&'a [u8]>, FontError> {
+    let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if index >= count {
+        return Ok(None)
+    }
+
+    try!(reader.jump(index as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+    let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    Ok(Some(try!(layout::subtable_at(base, offset as usize))))
+}
+
+// Reads a count-prefixed array of glyph IDs.
+fn read_glyph_array(mut reader: &[u8]) -> Result<Vec<u16>, FontError> {
+    let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let mut glyphs = Vec::with_capacity(glyph_count as usize);
+    for _ in 0..glyph_count {
+        glyphs.push(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+    }
+    Ok(glyphs)
+}
+
+// Replaces the glyphs in `start..end` with `replacement`.
+fn replace_glyphs(glyph_ids: &mut Vec<u16>, start: usize, end: usize, replacement: &[u16]) {
+    let tail = glyph_ids.split_off(end);
+    glyph_ids.truncate(start);
+    glyph_ids.extend_from_slice(replacement);
+    glyph_ids.extend_from_slice(&tail);
+}
+
+// Lookup type 1: Replaces one glyph with another.
+fn apply_single(subtable: &[u8], glyph_ids: &mut Vec<u16>, index: usize)
+                -> Result<Option<usize>, FontError> {
+    let mut reader = subtable;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage_index = match try!(covered_index(subtable, &mut reader, glyph_ids[index])) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    match format {
+        1 => {
+            let delta = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+            glyph_ids[index] = glyph_ids[index].wrapping_add(delta as u16)
+        }
+        2 => {
+            let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if coverage_index >= glyph_count {
+                return Ok(None)
+            }
+            try!(reader.jump(coverage_index as usize * mem::size_of::<u16>())
+                       .map_err(FontError::eof));
+            glyph_ids[index] = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
+        }
+        _ => return Err(FontError::UnknownFormat),
+    }
+
+    Ok(Some(index + 1))
+}
+
+// Lookup type 2: Replaces one glyph with a sequence of glyphs.
+fn apply_multiple(subtable: &[u8], glyph_ids: &mut Vec<u16>, index: usize)
+                  -> Result<Option<usize>, FontError> {
+    let mut reader = subtable;
+    if try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 1 {
+        return Err(FontError::UnknownFormat)
+    }
+
+    let coverage_index = match try!(covered_index(subtable, &mut reader, glyph_ids[index])) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    let sequence = match try!(offset_array_entry(subtable, reader, coverage_index)) {
+        None => return Ok(None),
+        Some(sequence) => try!(read_glyph_array(sequence)),
+    };
+
+    replace_glyphs(glyph_ids, index, index + 1, &sequence);
+    Ok(Some(index + sequence.len()))
+}
+
+// Lookup type 3: Replaces one glyph with one of several alternates.
+//
+// We have no way to select among the alternates, so the first one is always chosen.
+fn apply_alternate(subtable: &[u8], glyph_ids: &mut Vec<u16>, index: usize)
+                   -> Result<Option<usize>, FontError> {
+    let mut reader = subtable;
+    if try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 1 {
+        return Err(FontError::UnknownFormat)
+    }
+
+    let coverage_index = match try!(covered_index(subtable, &mut reader, glyph_ids[index])) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    let mut alternate_set = match try!(offset_array_entry(subtable, reader, coverage_index)) {
+        None => return Ok(None),
+        Some(alternate_set) => alternate_set,
+    };
+
+    let glyph_count = try!(alternate_set.read_u16::<BigEndian>().map_err(FontError::eof));
+    if glyph_count == 0 {
+        return Ok(None)
+    }
+
+    glyph_ids[index] = try!(alternate_set.read_u16::<BigEndian>().map_err(FontError::eof));
+    Ok(Some(index + 1))
+}
+
+// Lookup type 4: Replaces a sequence of glyphs with a single ligature glyph.
+//
+// Glyphs that the lookup ignores may appear between the components. They are kept and end up
+// after the ligature.
+fn apply_ligature(subtable: &[u8],
+                  skipper: &GlyphSkipper,
+                  glyph_ids: &mut Vec<u16>,
+                  index: usize)
+                  -> Result<Option<usize>, FontError> {
+    let mut reader = subtable;
+    if try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 1 {
+        return Err(FontError::UnknownFormat)
+    }
+
+    let coverage_index = match try!(covered_index(subtable, &mut reader, glyph_ids[index])) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    let ligature_set = match try!(offset_array_entry(subtable, reader, coverage_index)) {
+        None => return Ok(None),
+        Some(ligature_set) => ligature_set,
+    };
+
+    // Ligatures are stored in order of preference, so take the first one that matches.
+    let mut reader = ligature_set;
+    let ligature_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    'ligatures: for _ in 0..ligature_count {
+        let ligature = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut ligature = try!(layout::subtable_at(ligature_set, ligature as usize));
+
+        let ligature_glyph = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        let component_count = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        if component_count == 0 {
+            continue
+        }
+
+        let mut positions = vec![index];
+        for _ in 1..component_count {
+            let component = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+            let position = match try!(skipper.next(glyph_ids, *positions.last().unwrap())) {
+                Some(position) if glyph_ids[position] == component => position,
+                _ => continue 'ligatures,
+            };
+            positions.push(position)
+        }
+
+        glyph_ids[index] = ligature_glyph;
+        for &position in positions[1..].iter().rev() {
+            glyph_ids.remove(position);
+        }
+        return Ok(Some(index + 1))
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,394 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The glyph variations table, which holds the deltas that vary TrueType outlines.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/gvar.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use euclid::Point2D;
+use font::FontTable;
+use std::mem;
+use tables::variations;
+use util::Jump;
+
+pub const TAG: u32 = ((b'g' as u32) << 24) |
+                      ((b'v' as u32) << 16) |
+                      ((b'a' as u32) << 8)  |
+                       (b'r' as u32);
+
+/// The number of points that follow the points of each glyph's outline: the origin, the advance
+/// point, and the top and bottom origins for vertical layout, in that order.
+pub const PHANTOM_POINT_COUNT: usize = 4;
+
+const FLAG_LONG_OFFSETS: u16 = 0x0001;
+
+const SHARED_POINT_NUMBERS: u16 = 0x8000;
+const TUPLE_VARIATION_COUNT_MASK: u16 = 0x0fff;
+
+const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const INTERMEDIATE_REGION: u16 = 0x4000;
+const PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+const TUPLE_INDEX_MASK: u16 = 0x0fff;
+
+const POINTS_ARE_WORDS: u8 = 0x80;
+const POINTS_ARE_WORDS_COUNT_MASK: u8 = 0x7f;
+const POINT_RUN_COUNT_MASK: u8 = 0x7f;
+
+const DELTAS_ARE_ZERO: u8 = 0x80;
+const DELTAS_ARE_WORDS: u8 = 0x40;
+const DELTA_RUN_COUNT_MASK: u8 = 0x3f;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GvarTable<'a> {
+    axis_count: u16,
+    shared_tuple_count: u16,
+    // The offset of the shared tuple records.
+    shared_tuples: u32,
+    glyph_count: u16,
+    // Whether the glyph variation data offsets are 32-bit rather than 16-bit halves.
+    long_offsets: bool,
+    // The offset of the array of glyph variation data.
+    glyph_variation_data: u32,
+    table: FontTable<'a>,
+}
+
+impl<'a> GvarTable<'a> {
+    pub fn new(table: FontTable) -> Result<GvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGvarVersion)
+        }
+
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let shared_tuple_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let shared_tuples = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let glyph_variation_data = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        Ok(GvarTable {
+            axis_count: axis_count,
+            shared_tuple_count: shared_tuple_count,
+            shared_tuples: shared_tuples,
+            glyph_count: glyph_count,
+            long_offsets: (flags & FLAG_LONG_OFFSETS) != 0,
+            glyph_variation_data: glyph_variation_data,
+            table: table,
+        })
+    }
+
+    /// Returns how far each point of the given glyph moves at the instance with the given
+    /// normalized coordinates.
+    ///
+    /// `points` are the original positions of the glyph's points and `end_points` are the indices
+    /// of the last point of each contour, as in the `glyf` table. For composite glyphs, `points`
+    /// are the offsets of the components and `end_points` is empty. The result has a delta for
+    /// each of `points`, followed by one for each phantom point.
+    pub fn glyph_deltas(&self,
+                        glyph_id: u16,
+                        coords: &[f32],
+                        points: &[Point2D<i16>],
+                        end_points: &[u16])
+                        -> Result<Vec<Point2D<f32>>, FontError> {
+        let point_count = points.len() + PHANTOM_POINT_COUNT;
+        let mut deltas = vec![Point2D::new(0.0, 0.0); point_count];
+
+        let glyph_variation_data = match try!(self.glyph_variation_data(glyph_id)) {
+            None => return Ok(deltas),
+            Some(glyph_variation_data) => glyph_variation_data,
+        };
+
+        let mut reader = glyph_variation_data;
+        let tuple_variation_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let data_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut data = try!(glyph_variation_data.get(data_offset as usize..)
+                                                .ok_or(FontError::UnexpectedEof));
+
+        let shared_point_numbers = if (tuple_variation_count & SHARED_POINT_NUMBERS) != 0 {
+            try!(read_packed_point_numbers(&mut data))
+        } else {
+            None
+        };
+
+        for _ in 0..(tuple_variation_count & TUPLE_VARIATION_COUNT_MASK) {
+            let variation_data_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let tuple_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let peak = if (tuple_index & EMBEDDED_PEAK_TUPLE) != 0 {
+                try!(self.read_tuple(&mut reader))
+            } else {
+                try!(self.shared_tuple(tuple_index & TUPLE_INDEX_MASK))
+            };
+            let intermediate_region = if (tuple_index & INTERMEDIATE_REGION) != 0 {
+                let start = try!(self.read_tuple(&mut reader));
+                let end = try!(self.read_tuple(&mut reader));
+                Some((start, end))
+            } else {
+                None
+            };
+
+            // Find the serialized data for this tuple variation.
+            let mut tuple_data = try!(data.get(..(variation_data_size as usize))
+                                          .ok_or(FontError::UnexpectedEof));
+            try!(data.jump(variation_data_size as usize).map_err(FontError::eof));
+
+            let scalar = tuple_scalar(coords, &peak, intermediate_region.as_ref());
+            if scalar == 0.0 {
+                continue
+            }
+
+            let private_point_numbers;
+            let point_numbers = if (tuple_index & PRIVATE_POINT_NUMBERS) != 0 {
+                private_point_numbers = try!(read_packed_point_numbers(&mut tuple_data));
+                &private_point_numbers
+            } else {
+                &shared_point_numbers
+            };
+
+            let point_numbers = match *point_numbers {
+                None => {
+                    // Every point has a delta.
+                    let tuple_deltas = try!(read_packed_deltas(&mut tuple_data, point_count * 2));
+                    for (index, delta) in deltas.iter_mut().enumerate() {
+                        delta.x += tuple_deltas[index] as f32 * scalar;
+                        delta.y += tuple_deltas[point_count + index] as f32 * scalar;
+                    }
+                    continue
+                }
+                Some(ref point_numbers) => point_numbers,
+            };
+
+            let tuple_deltas = try!(read_packed_deltas(&mut tuple_data, point_numbers.len() * 2));
+            let mut touched = vec![false; point_count];
+            let mut explicit_deltas = vec![Point2D::new(0.0, 0.0); point_count];
+            for (index, &point_number) in point_numbers.iter().enumerate() {
+                if let Some(explicit_delta) = explicit_deltas.get_mut(point_number as usize) {
+                    explicit_delta.x = tuple_deltas[index] as f32;
+                    explicit_delta.y = tuple_deltas[point_numbers.len() + index] as f32;
+                    touched[point_number as usize] = true
+                }
+            }
+
+            interpolate_untouched_points(points, end_points, &mut explicit_deltas, &touched);
+
+            for (delta, explicit_delta) in deltas.iter_mut().zip(explicit_deltas.iter()) {
+                *delta = *delta + *explicit_delta * scalar
+            }
+        }
+
+        Ok(deltas)
+    }
+
+    // Returns the variation data for the given glyph, if it has any.
+    fn glyph_variation_data(&self, glyph_id: u16) -> Result<Option<&'a [u8]>, FontError> {
+        if glyph_id >= self.glyph_count {
+            return Ok(None)
+        }
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(mem::size_of::<u16>() * 6 + mem::size_of::<u32>() * 2)
+                   .map_err(FontError::eof));
+
+        let (start, end);
+        if self.long_offsets {
+            try!(reader.jump(mem::size_of::<u32>() * glyph_id as usize).map_err(FontError::eof));
+            start = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            end = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        } else {
+            try!(reader.jump(mem::size_of::<u16>() * glyph_id as usize).map_err(FontError::eof));
+            start = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32 * 2;
+            end = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32 * 2;
+        }
+
+        if start >= end {
+            return Ok(None)
+        }
+
+        let start = self.glyph_variation_data as usize + start as usize;
+        let end = self.glyph_variation_data as usize + end as usize;
+        match self.table.bytes.get(start..end) {
+            Some(glyph_variation_data) => Ok(Some(glyph_variation_data)),
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+
+    // Returns the peak coordinates of the shared tuple with the given index.
+    fn shared_tuple(&self, index: u16) -> Result<Vec<f32>, FontError> {
+        if index >= self.shared_tuple_count {
+            return Err(FontError::VariationDataNotFound)
+        }
+
+        let mut reader = self.table.bytes;
+        try!(reader.jump(self.shared_tuples as usize +
+                         mem::size_of::<i16>() * self.axis_count as usize * index as usize)
+                   .map_err(FontError::eof));
+        self.read_tuple(&mut reader)
+    }
+
+    // Reads a coordinate for each axis.
+    fn read_tuple(&self, reader: &mut &[u8]) -> Result<Vec<f32>, FontError> {
+        let mut tuple = Vec::with_capacity(self.axis_count as usize);
+        for _ in 0..self.axis_count {
+            tuple.push(try!(variations::read_f2dot14(reader)))
+        }
+        Ok(tuple)
+    }
+}
+
+// Returns how much a tuple variation with the given peak and, optionally, intermediate region
+// applies at the given coordinates.
+fn tuple_scalar(coords: &[f32], peak: &[f32], intermediate_region: Option<&(Vec<f32>, Vec<f32>)>)
+                -> f32 {
+    let mut scalar = 1.0;
+    for (axis, &peak) in peak.iter().enumerate() {
+        let (start, end) = match intermediate_region {
+            Some(&(ref start, ref end)) => (start[axis], end[axis]),
+            None => (peak.min(0.0), peak.max(0.0)),
+        };
+        let coord = coords.get(axis).cloned().unwrap_or(0.0);
+        scalar *= variations::axis_scalar(coord, start, peak, end);
+        if scalar == 0.0 {
+            break
+        }
+    }
+    scalar
+}
+
+/// Reads a packed list of point numbers. `None` means that every point is included.
+pub fn read_packed_point_numbers(reader: &mut &[u8]) -> Result<Option<Vec<u16>>, FontError> {
+    let first_byte = try!(reader.read_u8().map_err(FontError::eof));
+    let count = if first_byte == 0 {
+        return Ok(None)
+    } else if (first_byte & POINTS_ARE_WORDS) != 0 {
+        let second_byte = try!(reader.read_u8().map_err(FontError::eof));
+        (((first_byte & POINTS_ARE_WORDS_COUNT_MASK) as usize) << 8) | second_byte as usize
+    } else {
+        first_byte as usize
+    };
+
+    // Each point number is stored as the difference from the previous one.
+    let (mut point_numbers, mut point_number) = (Vec::with_capacity(count), 0u16);
+    while point_numbers.len() < count {
+        let control = try!(reader.read_u8().map_err(FontError::eof));
+        let run_count = (control & POINT_RUN_COUNT_MASK) as usize + 1;
+        for _ in 0..run_count {
+            let difference = if (control & POINTS_ARE_WORDS) != 0 {
+                try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
+            } else {
+                try!(reader.read_u8().map_err(FontError::eof)) as u16
+            };
+            point_number = point_number.wrapping_add(difference);
+            point_numbers.push(point_number)
+        }
+    }
+
+    point_numbers.truncate(count);
+    Ok(Some(point_numbers))
+}
+
+/// Reads `count` packed deltas.
+pub fn read_packed_deltas(reader: &mut &[u8], count: usize) -> Result<Vec<i16>, FontError> {
+    let mut deltas = Vec::with_capacity(count);
+    while deltas.len() < count {
+        let control = try!(reader.read_u8().map_err(FontError::eof));
+        let run_count = (control & DELTA_RUN_COUNT_MASK) as usize + 1;
+        for _ in 0..run_count {
+            let delta = if (control & DELTAS_ARE_ZERO) != 0 {
+                0
+            } else if (control & DELTAS_ARE_WORDS) != 0 {
+                try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+            } else {
+                try!(reader.read_i8().map_err(FontError::eof)) as i16
+            };
+            deltas.push(delta)
+        }
+    }
+
+    deltas.truncate(count);
+    Ok(deltas)
+}
+
+/// Infers the deltas of the points in each contour that a tuple variation doesn't move explicitly
+/// from the deltas of the nearest points before and after them that it does move.
+///
+/// This is the "interpolate untouched points" (IUP) step that the spec describes.
+pub fn interpolate_untouched_points(points: &[Point2D<i16>],
+                                    end_points: &[u16],
+                                    deltas: &mut [Point2D<f32>],
+                                    touched: &[bool]) {
+    let mut start = 0;
+    for &end in end_points {
+        let end = end as usize;
+        if end < start || end >= points.len() {
+            break
+        }
+
+        let touched_points: Vec<usize> = (start..(end + 1)).filter(|&index| touched[index])
+                                                           .collect();
+        for (position, &prev) in touched_points.iter().enumerate() {
+            let next = touched_points[(position + 1) % touched_points.len()];
+
+            // Walk forward from one touched point to the next, wrapping around the contour.
+            let mut index = prev;
+            loop {
+                index = if index == end { start } else { index + 1 };
+                if index == next {
+                    break
+                }
+
+                deltas[index] = Point2D::new(interpolate_delta(points[index].x,
+                                                               points[prev].x,
+                                                               points[next].x,
+                                                               deltas[prev].x,
+                                                               deltas[next].x),
+                                             interpolate_delta(points[index].y,
+                                                               points[prev].y,
+                                                               points[next].y,
+                                                               deltas[prev].y,
+                                                               deltas[next].y))
+            }
+        }
+
+        start = end + 1
+    }
+}
+
+/// Computes the delta of an untouched point along one axis from its two touched neighbors.
+pub fn interpolate_delta(coord: i16,
+                         prev_coord: i16,
+                         next_coord: i16,
+                         prev_delta: f32,
+                         next_delta: f32)
+                         -> f32 {
+    if prev_coord == next_coord {
+        return if prev_delta == next_delta { prev_delta } else { 0.0 }
+    }
+
+    let (low_coord, high_coord, low_delta, high_delta) = if prev_coord < next_coord {
+        (prev_coord as f32, next_coord as f32, prev_delta, next_delta)
+    } else {
+        (next_coord as f32, prev_coord as f32, next_delta, prev_delta)
+    };
+
+    let coord = coord as f32;
+    if coord <= low_coord {
+        low_delta
+    } else if coord >= high_coord {
+        high_delta
+    } else {
+        low_delta + (coord - low_coord) * (high_delta - low_delta) / (high_coord - low_coord)
+    }
+}
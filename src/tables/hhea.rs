@@ -21,7 +21,12 @@ pub const TAG: u32 = ((b'h' as u32) << 24) |
 
 #[derive(Clone, Debug)]
 pub struct HheaTable {
+    pub ascender: i16,
+    pub descender: i16,
     pub line_gap: i16,
+    pub caret_slope_rise: i16,
+    pub caret_slope_run: i16,
+    pub caret_offset: i16,
     pub number_of_h_metrics: u16,
 }
 
@@ -37,16 +42,27 @@ impl HheaTable {
         }
 
         // Read the height-related metrics.
-        let _ascender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-        let _descender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let ascender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let descender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
         let line_gap = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
 
+        // Read the caret slope and offset.
+        try!(reader.jump(mem::size_of::<u16>() * 4).map_err(FontError::eof));
+        let caret_slope_rise = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let caret_slope_run = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let caret_offset = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+
         // Read the number of `hmtx` entries.
-        try!(reader.jump(mem::size_of::<u16>() * 12).map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<u16>() * 5).map_err(FontError::eof));
         let number_of_h_metrics = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
 
         Ok(HheaTable {
+            ascender: ascender,
+            descender: descender,
             line_gap: line_gap,
+            caret_slope_rise: caret_slope_rise,
+            caret_slope_run: caret_slope_run,
+            caret_offset: caret_offset,
             number_of_h_metrics: number_of_h_metrics,
         })
     }
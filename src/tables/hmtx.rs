@@ -45,7 +45,10 @@ impl<'a> HmtxTable<'a> {
             try!(reader.jump(mem::size_of::<u16>() * 2 *
                              (hhea_table.number_of_h_metrics - 1) as usize).map_err(FontError::eof));
             advance_width = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-            try!(reader.jump(mem::size_of::<i16>() * glyph_id as usize).map_err(FontError::eof));
+
+            // The left-side bearings of the remaining glyphs follow the last long metric.
+            let lsb_index = (glyph_id - hhea_table.number_of_h_metrics) as usize + 1;
+            try!(reader.jump(mem::size_of::<i16>() * lsb_index).map_err(FontError::eof));
         }
 
         // Read the left-side bearing.
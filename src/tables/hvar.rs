@@ -0,0 +1,92 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The horizontal metrics variations table, which varies advance widths and side bearings.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/hvar.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use tables::variations::{self, ItemVariationStore};
+
+pub const TAG: u32 = ((b'H' as u32) << 24) |
+                      ((b'V' as u32) << 16) |
+                      ((b'A' as u32) << 8)  |
+                       (b'R' as u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct HvarTable<'a> {
+    item_variation_store: ItemVariationStore<'a>,
+    // The offset of the delta set index map for advance widths. If zero, glyph IDs are used as
+    // inner indices directly.
+    advance_width_mapping: u32,
+    // The offset of the delta set index map for left side bearings, if the table has one.
+    lsb_mapping: u32,
+    table: FontTable<'a>,
+}
+
+impl<'a> HvarTable<'a> {
+    pub fn new(table: FontTable) -> Result<HvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedHvarVersion)
+        }
+
+        let item_variation_store = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let advance_width_mapping = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let lsb_mapping = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        let item_variation_store = match table.bytes.get(item_variation_store as usize..) {
+            Some(item_variation_store) => try!(ItemVariationStore::new(item_variation_store)),
+            None => return Err(FontError::UnexpectedEof),
+        };
+
+        Ok(HvarTable {
+            item_variation_store: item_variation_store,
+            advance_width_mapping: advance_width_mapping,
+            lsb_mapping: lsb_mapping,
+            table: table,
+        })
+    }
+
+    /// Returns how much the advance width of the given glyph changes at the given normalized
+    /// coordinates.
+    pub fn advance_width_delta(&self, glyph_id: u16, coords: &[f32]) -> Result<f32, FontError> {
+        let (outer_index, inner_index) = if self.advance_width_mapping == 0 {
+            (0, glyph_id)
+        } else {
+            try!(self.delta_set_index(self.advance_width_mapping, glyph_id))
+        };
+        self.item_variation_store.delta(outer_index, inner_index, coords)
+    }
+
+    /// Returns how much the left side bearing of the given glyph changes at the given normalized
+    /// coordinates, if the table says.
+    pub fn lsb_delta(&self, glyph_id: u16, coords: &[f32]) -> Result<Option<f32>, FontError> {
+        if self.lsb_mapping == 0 {
+            return Ok(None)
+        }
+
+        let (outer_index, inner_index) = try!(self.delta_set_index(self.lsb_mapping, glyph_id));
+        self.item_variation_store.delta(outer_index, inner_index, coords).map(Some)
+    }
+
+    fn delta_set_index(&self, mapping: u32, glyph_id: u16) -> Result<(u16, u16), FontError> {
+        match self.table.bytes.get(mapping as usize..) {
+            Some(mapping) => variations::delta_set_index(mapping, glyph_id as u32),
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+}
@@ -0,0 +1,459 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Common table formats shared by the OpenType layout tables (`GSUB`, `GPOS`, and `GDEF`).
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/chapter2.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{FontTable, GlyphClass};
+use std::mem;
+use tables::gdef::GdefTable;
+use util::Jump;
+
+const SCRIPT_DFLT: u32 = ((b'D' as u32) << 24) |
+                          ((b'F' as u32) << 16) |
+                          ((b'L' as u32) << 8)  |
+                           (b'T' as u32);
+const SCRIPT_LATN: u32 = ((b'l' as u32) << 24) |
+                          ((b'a' as u32) << 16) |
+                          ((b't' as u32) << 8)  |
+                           (b'n' as u32);
+
+const NO_REQUIRED_FEATURE: u16 = 0xffff;
+
+bitflags! {
+    pub flags LookupFlags: u16 {
+        const RIGHT_TO_LEFT = 1 << 0,
+        const IGNORE_BASE_GLYPHS = 1 << 1,
+        const IGNORE_LIGATURES = 1 << 2,
+        const IGNORE_MARKS = 1 << 3,
+        const USE_MARK_FILTERING_SET = 1 << 4,
+    }
+}
+
+/// The script, feature, and lookup lists at the start of a `GSUB` or `GPOS` table.
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutLists<'a> {
+    table: FontTable<'a>,
+    script_list: u16,
+    feature_list: u16,
+    lookup_list: u16,
+}
+
+impl<'a> LayoutLists<'a> {
+    /// Reads the list offsets. The reader must be positioned just past the version number.
+    pub fn new(table: FontTable<'a>, reader: &mut &[u8]) -> Result<LayoutLists<'a>, FontError> {
+        let script_list = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let feature_list = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let lookup_list = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        Ok(LayoutLists {
+            table: table,
+            script_list: script_list,
+            feature_list: feature_list,
+            lookup_list: lookup_list,
+        })
+    }
+
+    /// Returns the indices of the lookups that the given features enable for the default language
+    /// system of the default script, in lookup list order.
+    ///
+    /// Since our shaper is geared toward Latin text, the script used is `latn` if present, falling
+    /// back to `DFLT`. Many fonts only list Latin features under `latn`.
+    pub fn lookups_for_features(&self, features: &[u32]) -> Result<Vec<u16>, FontError> {
+        let mut lookup_indices = vec![];
+        if let Some(lang_sys) = try!(self.default_lang_sys()) {
+            try!(self.add_lookups_for_lang_sys(lang_sys, features, &mut lookup_indices))
+        }
+
+        lookup_indices.sort();
+        lookup_indices.dedup();
+        Ok(lookup_indices)
+    }
+
+    /// Returns the indices of the lookups that the given features enable for the default language
+    /// system of any script, in lookup list order.
+    ///
+    /// This suits features whose lookups only act on glyphs of their own script, such as mark
+    /// attachment, so that they work without knowing the script of the text.
+    pub fn lookups_for_features_in_all_scripts(&self, features: &[u32])
+                                               -> Result<Vec<u16>, FontError> {
+        let mut lookup_indices = vec![];
+
+        let script_list = try!(subtable_at(self.table.bytes, self.script_list as usize));
+        let mut reader = script_list;
+        let script_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        for _ in 0..script_count {
+            let _script_tag = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let script = try!(subtable_at(script_list, offset as usize));
+            if let Some(lang_sys) = try!(default_lang_sys_of_script(script)) {
+                try!(self.add_lookups_for_lang_sys(lang_sys, features, &mut lookup_indices))
+            }
+        }
+
+        lookup_indices.sort();
+        lookup_indices.dedup();
+        Ok(lookup_indices)
+    }
+
+    /// Returns the lookup with the given index in the lookup list.
+    pub fn lookup(&self, index: u16) -> Result<Lookup<'a>, FontError> {
+        let lookup_list = try!(subtable_at(self.table.bytes, self.lookup_list as usize));
+
+        let mut reader = lookup_list;
+        let lookup_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if index >= lookup_count {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        try!(reader.jump(index as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+        let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let table = try!(subtable_at(lookup_list, offset as usize));
+
+        let mut reader = table;
+        let lookup_type = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let lookup_flag = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let subtable_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // The mark filtering set index follows the subtable offsets.
+        let flags = LookupFlags::from_bits_truncate(lookup_flag);
+        let mark_filtering_set = if flags.contains(USE_MARK_FILTERING_SET) {
+            try!(reader.jump(subtable_count as usize * mem::size_of::<u16>())
+                       .map_err(FontError::eof));
+            Some(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+        } else {
+            None
+        };
+
+        Ok(Lookup {
+            lookup_type: lookup_type,
+            subtable_count: subtable_count,
+            flags: flags,
+            mark_attachment_type: lookup_flag >> 8,
+            mark_filtering_set: mark_filtering_set,
+            table: table,
+        })
+    }
+
+    // Finds the default language system table of the default script, if there is one.
+    fn default_lang_sys(&self) -> Result<Option<&'a [u8]>, FontError> {
+        let script_list = try!(subtable_at(self.table.bytes, self.script_list as usize));
+
+        let mut reader = script_list;
+        let script_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let mut script_offset = None;
+        for _ in 0..script_count {
+            let script_tag = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            match script_tag {
+                SCRIPT_LATN => {
+                    script_offset = Some(offset);
+                    break
+                }
+                SCRIPT_DFLT => script_offset = Some(offset),
+                _ => {}
+            }
+        }
+
+        match script_offset {
+            None => Ok(None),
+            Some(script_offset) => {
+                default_lang_sys_of_script(try!(subtable_at(script_list, script_offset as usize)))
+            }
+        }
+    }
+
+    // Appends the indices of the lookups that the given features enable for the given language
+    // system to `lookup_indices`.
+    fn add_lookups_for_lang_sys(&self,
+                                lang_sys: &[u8],
+                                features: &[u32],
+                                lookup_indices: &mut Vec<u16>)
+                                -> Result<(), FontError> {
+        let mut reader = lang_sys;
+        let _lookup_order = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let required_feature_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let feature_index_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let feature_list = try!(subtable_at(self.table.bytes, self.feature_list as usize));
+        if required_feature_index != NO_REQUIRED_FEATURE {
+            try!(add_lookups_for_feature(feature_list, required_feature_index, lookup_indices));
+        }
+
+        for _ in 0..feature_index_count {
+            let feature_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let mut feature_record = feature_list;
+            try!(feature_record.jump(mem::size_of::<u16>() + feature_index as usize * 6)
+                               .map_err(FontError::eof));
+            let feature_tag = try!(feature_record.read_u32::<BigEndian>().map_err(FontError::eof));
+            if features.contains(&feature_tag) {
+                try!(add_lookups_for_feature(feature_list, feature_index, lookup_indices));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A single lookup in the lookup list.
+#[derive(Clone, Copy, Debug)]
+pub struct Lookup<'a> {
+    pub lookup_type: u16,
+    pub subtable_count: u16,
+    flags: LookupFlags,
+    // If nonzero, marks of any other attachment class are skipped.
+    mark_attachment_type: u16,
+    // If present, marks outside this mark glyph set are skipped.
+    mark_filtering_set: Option<u16>,
+    table: &'a [u8],
+}
+
+impl<'a> Lookup<'a> {
+    /// Returns the bytes of the subtable with the given index.
+    pub fn subtable(&self, index: u16) -> Result<&'a [u8], FontError> {
+        let mut reader = self.table;
+        try!(reader.jump(mem::size_of::<u16>() * (3 + index as usize)).map_err(FontError::eof));
+        let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        subtable_at(self.table, offset as usize)
+    }
+
+    /// Returns an object that determines which glyphs this lookup skips over.
+    #[inline]
+    pub fn skipper<'b>(&self, gdef: Option<GdefTable<'b>>) -> GlyphSkipper<'b> {
+        GlyphSkipper {
+            flags: self.flags,
+            mark_attachment_type: self.mark_attachment_type,
+            mark_filtering_set: self.mark_filtering_set,
+            gdef: gdef,
+        }
+    }
+}
+
+/// Determines which glyphs a lookup skips over, according to its flags and the glyph classes in
+/// the `GDEF` table.
+///
+/// Without a `GDEF` table, no glyphs are skipped.
+#[derive(Clone, Copy)]
+pub struct GlyphSkipper<'a> {
+    flags: LookupFlags,
+    mark_attachment_type: u16,
+    mark_filtering_set: Option<u16>,
+    gdef: Option<GdefTable<'a>>,
+}
+
+impl<'a> GlyphSkipper<'a> {
+    /// Returns true if the lookup ignores the given glyph.
+    pub fn skips(&self, glyph_id: u16) -> Result<bool, FontError> {
+        let gdef = match self.gdef {
+            None => return Ok(false),
+            Some(gdef) => gdef,
+        };
+
+        match try!(gdef.glyph_class(glyph_id)) {
+            Some(GlyphClass::Base) => Ok(self.flags.contains(IGNORE_BASE_GLYPHS)),
+            Some(GlyphClass::Ligature) => Ok(self.flags.contains(IGNORE_LIGATURES)),
+            Some(GlyphClass::Mark) => {
+                if self.flags.contains(IGNORE_MARKS) {
+                    return Ok(true)
+                }
+                if let Some(mark_filtering_set) = self.mark_filtering_set {
+                    return Ok(!try!(gdef.mark_glyph_set_contains(mark_filtering_set, glyph_id)))
+                }
+                if self.mark_attachment_type != 0 {
+                    return Ok(try!(gdef.mark_attachment_class(glyph_id)) !=
+                              self.mark_attachment_type)
+                }
+                Ok(false)
+            }
+            Some(GlyphClass::Component) | None => Ok(false),
+        }
+    }
+
+    /// Returns the index of the first glyph after `index` that the lookup doesn't skip.
+    pub fn next(&self, glyph_ids: &[u16], index: usize) -> Result<Option<usize>, FontError> {
+        for next_index in (index + 1)..glyph_ids.len() {
+            if !try!(self.skips(glyph_ids[next_index])) {
+                return Ok(Some(next_index))
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the index of the last glyph before `index` that the lookup doesn't skip.
+    pub fn previous(&self, glyph_ids: &[u16], index: usize) -> Result<Option<usize>, FontError> {
+        for previous_index in (0..index).rev() {
+            if !try!(self.skips(glyph_ids[previous_index])) {
+                return Ok(Some(previous_index))
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads an extension subtable, returning the type of the lookup it wraps and the wrapped
+/// subtable.
+pub fn resolve_extension(subtable: &[u8]) -> Result<(u16, &[u8]), FontError> {
+    let mut reader = subtable;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if format != 1 {
+        return Err(FontError::UnknownFormat)
+    }
+
+    let extension_lookup_type = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+    Ok((extension_lookup_type, try!(subtable_at(subtable, offset as usize))))
+}
+
+/// Returns the index of the given glyph in the coverage table, or `None` if the glyph is not
+/// covered.
+pub fn coverage_index(coverage: &[u8], glyph_id: u16) -> Result<Option<u16>, FontError> {
+    let mut reader = coverage;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    match format {
+        1 => {
+            let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let (mut low, mut high) = (0, glyph_count);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut glyph = reader;
+                try!(glyph.jump(mid as usize * mem::size_of::<u16>()).map_err(FontError::eof));
+                let glyph = try!(glyph.read_u16::<BigEndian>().map_err(FontError::eof));
+                if glyph_id < glyph {
+                    high = mid
+                } else if glyph_id > glyph {
+                    low = mid + 1
+                } else {
+                    return Ok(Some(mid))
+                }
+            }
+
+            Ok(None)
+        }
+        2 => {
+            let range_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let (mut low, mut high) = (0, range_count);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut range = reader;
+                try!(range.jump(mid as usize * mem::size_of::<[u16; 3]>())
+                          .map_err(FontError::eof));
+                let start = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                let end = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                if glyph_id < start {
+                    high = mid
+                } else if glyph_id > end {
+                    low = mid + 1
+                } else {
+                    let start_coverage_index = try!(range.read_u16::<BigEndian>()
+                                                         .map_err(FontError::eof));
+                    return Ok(Some(start_coverage_index + (glyph_id - start)))
+                }
+            }
+
+            Ok(None)
+        }
+        _ => Err(FontError::UnknownFormat),
+    }
+}
+
+/// Returns the class that the given class definition table assigns to the given glyph.
+///
+/// Glyphs not mentioned in the table belong to class 0.
+pub fn class_of_glyph(class_def: &[u8], glyph_id: u16) -> Result<u16, FontError> {
+    let mut reader = class_def;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    match format {
+        1 => {
+            let start_glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if glyph_id < start_glyph_id || glyph_id - start_glyph_id >= glyph_count {
+                return Ok(0)
+            }
+
+            try!(reader.jump((glyph_id - start_glyph_id) as usize * mem::size_of::<u16>())
+                       .map_err(FontError::eof));
+            Ok(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+        }
+        2 => {
+            let range_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let (mut low, mut high) = (0, range_count);
+            while low < high {
+                let mid = (low + high) / 2;
+
+                let mut range = reader;
+                try!(range.jump(mid as usize * mem::size_of::<[u16; 3]>())
+                          .map_err(FontError::eof));
+                let start = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                let end = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                if glyph_id < start {
+                    high = mid
+                } else if glyph_id > end {
+                    low = mid + 1
+                } else {
+                    return Ok(try!(range.read_u16::<BigEndian>().map_err(FontError::eof)))
+                }
+            }
+
+            Ok(0)
+        }
+        _ => Err(FontError::UnknownFormat),
+    }
+}
+
+/// Returns the bytes starting at the given offset from the beginning of `table`.
+#[inline]
+pub fn subtable_at(table: &[u8], offset: usize) -> Result<&[u8], FontError> {
+    let mut reader = table;
+    try!(reader.jump(offset).map_err(FontError::eof));
+    Ok(reader)
+}
+
+// Finds the default language system table of the given script table, if there is one.
+fn default_lang_sys_of_script(script: &[u8]) -> Result<Option<&[u8]>, FontError> {
+    let mut reader = script;
+    let default_lang_sys = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if default_lang_sys == 0 {
+        return Ok(None)
+    }
+
+    Ok(Some(try!(subtable_at(script, default_lang_sys as usize))))
+}
+
+// Appends the lookup indices of the feature with the given index to `lookup_indices`.
+fn add_lookups_for_feature(feature_list: &[u8], feature_index: u16, lookup_indices: &mut Vec<u16>)
+                           -> Result<(), FontError> {
+    let mut reader = feature_list;
+    let feature_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if feature_index >= feature_count {
+        return Err(FontError::UnexpectedEof)
+    }
+
+    try!(reader.jump(feature_index as usize * 6 + mem::size_of::<u32>()).map_err(FontError::eof));
+    let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+    let mut reader = try!(subtable_at(feature_list, offset as usize));
+    let _feature_params = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let lookup_index_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    for _ in 0..lookup_index_count {
+        lookup_indices.push(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+    }
+
+    Ok(())
+}
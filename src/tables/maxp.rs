@@ -0,0 +1,45 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `maxp` table, which holds the number of glyphs in the font.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/maxp.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+
+pub const TAG: u32 = ((b'm' as u32) << 24) |
+                      ((b'a' as u32) << 16) |
+                      ((b'x' as u32) << 8)  |
+                       (b'p' as u32);
+
+#[derive(Clone, Debug)]
+pub struct MaxpTable {
+    pub num_glyphs: u16,
+}
+
+impl MaxpTable {
+    pub fn new(table: FontTable) -> Result<MaxpTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 0.5 is for CFF fonts; version 1.0 is for TrueType fonts. We
+        // only need the glyph count, which both have.
+        let version = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        if version != 0x5000 && version != 0x10000 {
+            return Err(FontError::UnsupportedMaxpVersion)
+        }
+
+        let num_glyphs = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Ok(MaxpTable {
+            num_glyphs: num_glyphs,
+        })
+    }
+}
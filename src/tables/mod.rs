@@ -10,13 +10,27 @@
 
 //! OpenType fonts.
 
+pub mod avar;
 pub mod cff;
+pub mod cff2;
 pub mod cmap;
+pub mod fvar;
+pub mod gdef;
 pub mod glyf;
+pub mod gpos;
+pub mod gsub;
+pub mod gvar;
 pub mod head;
 pub mod hhea;
 pub mod hmtx;
+pub mod hvar;
 pub mod kern;
+pub mod layout;
 pub mod loca;
+pub mod maxp;
+pub mod mvar;
+pub mod name;
 pub mod os_2;
+pub mod post;
+pub mod variations;
 
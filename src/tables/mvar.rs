@@ -0,0 +1,214 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The metrics variations table, which varies font-wide metrics such as the ascender.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/mvar.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use tables::variations::ItemVariationStore;
+use util::Jump;
+
+pub const TAG: u32 = ((b'M' as u32) << 24) |
+                      ((b'V' as u32) << 16) |
+                      ((b'A' as u32) << 8)  |
+                       (b'R' as u32);
+
+/// The tag of `OS/2.sTypoAscender`.
+pub const HORIZONTAL_ASCENDER: u32 = ((b'h' as u32) << 24) |
+                                      ((b'a' as u32) << 16) |
+                                      ((b's' as u32) << 8)  |
+                                       (b'c' as u32);
+/// The tag of `OS/2.sTypoDescender`.
+pub const HORIZONTAL_DESCENDER: u32 = ((b'h' as u32) << 24) |
+                                       ((b'd' as u32) << 16) |
+                                       ((b's' as u32) << 8)  |
+                                        (b'c' as u32);
+/// The tag of `OS/2.sTypoLineGap`.
+pub const HORIZONTAL_LINE_GAP: u32 = ((b'h' as u32) << 24) |
+                                      ((b'l' as u32) << 16) |
+                                      ((b'g' as u32) << 8)  |
+                                       (b'p' as u32);
+/// The tag of `OS/2.usWinAscent`.
+pub const HORIZONTAL_CLIPPING_ASCENT: u32 = ((b'h' as u32) << 24) |
+                                             ((b'c' as u32) << 16) |
+                                             ((b'l' as u32) << 8)  |
+                                              (b'a' as u32);
+/// The tag of `OS/2.usWinDescent`.
+pub const HORIZONTAL_CLIPPING_DESCENT: u32 = ((b'h' as u32) << 24) |
+                                              ((b'c' as u32) << 16) |
+                                              ((b'l' as u32) << 8)  |
+                                               (b'd' as u32);
+/// The tag of `OS/2.sxHeight`.
+pub const X_HEIGHT: u32 = ((b'x' as u32) << 24) |
+                           ((b'h' as u32) << 16) |
+                           ((b'g' as u32) << 8)  |
+                            (b't' as u32);
+/// The tag of `OS/2.sCapHeight`.
+pub const CAP_HEIGHT: u32 = ((b'c' as u32) << 24) |
+                             ((b'p' as u32) << 16) |
+                             ((b'h' as u32) << 8)  |
+                              (b't' as u32);
+/// The tag of `OS/2.ySubscriptXSize`.
+pub const SUBSCRIPT_X_SIZE: u32 = ((b's' as u32) << 24) |
+                                   ((b'b' as u32) << 16) |
+                                   ((b'x' as u32) << 8)  |
+                                    (b's' as u32);
+/// The tag of `OS/2.ySubscriptYSize`.
+pub const SUBSCRIPT_Y_SIZE: u32 = ((b's' as u32) << 24) |
+                                   ((b'b' as u32) << 16) |
+                                   ((b'y' as u32) << 8)  |
+                                    (b's' as u32);
+/// The tag of `OS/2.ySubscriptXOffset`.
+pub const SUBSCRIPT_X_OFFSET: u32 = ((b's' as u32) << 24) |
+                                     ((b'b' as u32) << 16) |
+                                     ((b'x' as u32) << 8)  |
+                                      (b'o' as u32);
+/// The tag of `OS/2.ySubscriptYOffset`.
+pub const SUBSCRIPT_Y_OFFSET: u32 = ((b's' as u32) << 24) |
+                                     ((b'b' as u32) << 16) |
+                                     ((b'y' as u32) << 8)  |
+                                      (b'o' as u32);
+/// The tag of `OS/2.ySuperscriptXSize`.
+pub const SUPERSCRIPT_X_SIZE: u32 = ((b's' as u32) << 24) |
+                                     ((b'p' as u32) << 16) |
+                                     ((b'x' as u32) << 8)  |
+                                      (b's' as u32);
+/// The tag of `OS/2.ySuperscriptYSize`.
+pub const SUPERSCRIPT_Y_SIZE: u32 = ((b's' as u32) << 24) |
+                                     ((b'p' as u32) << 16) |
+                                     ((b'y' as u32) << 8)  |
+                                      (b's' as u32);
+/// The tag of `OS/2.ySuperscriptXOffset`.
+pub const SUPERSCRIPT_X_OFFSET: u32 = ((b's' as u32) << 24) |
+                                       ((b'p' as u32) << 16) |
+                                       ((b'x' as u32) << 8)  |
+                                        (b'o' as u32);
+/// The tag of `OS/2.ySuperscriptYOffset`.
+pub const SUPERSCRIPT_Y_OFFSET: u32 = ((b's' as u32) << 24) |
+                                       ((b'p' as u32) << 16) |
+                                       ((b'y' as u32) << 8)  |
+                                        (b'o' as u32);
+/// The tag of `OS/2.yStrikeoutSize`.
+pub const STRIKEOUT_SIZE: u32 = ((b's' as u32) << 24) |
+                                 ((b't' as u32) << 16) |
+                                 ((b'r' as u32) << 8)  |
+                                  (b's' as u32);
+/// The tag of `OS/2.yStrikeoutPosition`.
+pub const STRIKEOUT_OFFSET: u32 = ((b's' as u32) << 24) |
+                                   ((b't' as u32) << 16) |
+                                   ((b'r' as u32) << 8)  |
+                                    (b'o' as u32);
+/// The tag of `hhea.caretSlopeRise`.
+pub const HORIZONTAL_CARET_RISE: u32 = ((b'h' as u32) << 24) |
+                                        ((b'c' as u32) << 16) |
+                                        ((b'r' as u32) << 8)  |
+                                         (b's' as u32);
+/// The tag of `hhea.caretSlopeRun`.
+pub const HORIZONTAL_CARET_RUN: u32 = ((b'h' as u32) << 24) |
+                                       ((b'c' as u32) << 16) |
+                                       ((b'r' as u32) << 8)  |
+                                        (b'n' as u32);
+/// The tag of `hhea.caretOffset`.
+pub const HORIZONTAL_CARET_OFFSET: u32 = ((b'h' as u32) << 24) |
+                                          ((b'c' as u32) << 16) |
+                                          ((b'o' as u32) << 8)  |
+                                           (b'f' as u32);
+/// The tag of `post.underlineThickness`.
+pub const UNDERLINE_SIZE: u32 = ((b'u' as u32) << 24) |
+                                 ((b'n' as u32) << 16) |
+                                 ((b'd' as u32) << 8)  |
+                                  (b's' as u32);
+/// The tag of `post.underlinePosition`.
+pub const UNDERLINE_OFFSET: u32 = ((b'u' as u32) << 24) |
+                                   ((b'n' as u32) << 16) |
+                                   ((b'd' as u32) << 8)  |
+                                    (b'o' as u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct MvarTable<'a> {
+    item_variation_store: Option<ItemVariationStore<'a>>,
+    value_record_size: u16,
+    value_record_count: u16,
+    table: FontTable<'a>,
+}
+
+impl<'a> MvarTable<'a> {
+    pub fn new(table: FontTable) -> Result<MvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedMvarVersion)
+        }
+
+        try!(reader.jump(mem::size_of::<u16>()).map_err(FontError::eof));
+        let value_record_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let value_record_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let item_variation_store = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // The variation store may be absent if there are no value records.
+        let item_variation_store = if item_variation_store == 0 {
+            None
+        } else {
+            match table.bytes.get(item_variation_store as usize..) {
+                Some(item_variation_store) => {
+                    Some(try!(ItemVariationStore::new(item_variation_store)))
+                }
+                None => return Err(FontError::UnexpectedEof),
+            }
+        };
+
+        Ok(MvarTable {
+            item_variation_store: item_variation_store,
+            value_record_size: value_record_size,
+            value_record_count: value_record_count,
+            table: table,
+        })
+    }
+
+    /// Returns how much the metric with the given value tag changes at the given normalized
+    /// coordinates.
+    ///
+    /// Metrics that the table doesn't mention don't change.
+    pub fn delta(&self, value_tag: u32, coords: &[f32]) -> Result<f32, FontError> {
+        let item_variation_store = match self.item_variation_store {
+            None => return Ok(0.0),
+            Some(item_variation_store) => item_variation_store,
+        };
+
+        // The value records are sorted by tag, so binary search.
+        let (mut low, mut high) = (0, self.value_record_count as usize);
+        while low < high {
+            let mid = (low + high) / 2;
+
+            let mut reader = self.table.bytes;
+            try!(reader.jump(mem::size_of::<u16>() * 6 + self.value_record_size as usize * mid)
+                       .map_err(FontError::eof));
+            let mid_value_tag = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            if value_tag < mid_value_tag {
+                high = mid
+            } else if value_tag > mid_value_tag {
+                low = mid + 1
+            } else {
+                let outer_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let inner_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                return item_variation_store.delta(outer_index, inner_index, coords)
+            }
+        }
+
+        Ok(0.0)
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The naming table, which holds human-readable strings such as the family name of the font.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/name.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use util::{self, Jump};
+
+pub const TAG: u32 = ((b'n' as u32) << 24) |
+                      ((b'a' as u32) << 16) |
+                      ((b'm' as u32) << 8)  |
+                       (b'e' as u32);
+
+/// The name ID of the copyright notice.
+pub const COPYRIGHT_NOTICE: u16 = 0;
+/// The name ID of the family name, as used to group at most four styles together.
+pub const FAMILY_NAME: u16 = 1;
+/// The name ID of the subfamily name, such as "Regular" or "Bold Italic".
+pub const SUBFAMILY_NAME: u16 = 2;
+/// The name ID of the unique font identifier.
+pub const UNIQUE_ID: u16 = 3;
+/// The name ID of the full font name, such as "Nimbus Sans L Regular".
+pub const FULL_NAME: u16 = 4;
+/// The name ID of the version string.
+pub const VERSION_STRING: u16 = 5;
+/// The name ID of the PostScript name.
+pub const POSTSCRIPT_NAME: u16 = 6;
+/// The name ID of the typographic family name, for families with more than four styles.
+pub const TYPOGRAPHIC_FAMILY_NAME: u16 = 16;
+/// The name ID of the typographic subfamily name, for families with more than four styles.
+pub const TYPOGRAPHIC_SUBFAMILY_NAME: u16 = 17;
+
+const PLATFORM_ID_UNICODE: u16 = 0;
+const PLATFORM_ID_MACINTOSH: u16 = 1;
+const PLATFORM_ID_MICROSOFT: u16 = 3;
+
+const MACINTOSH_ENCODING_ID_ROMAN: u16 = 0;
+
+const MICROSOFT_ENCODING_ID_SYMBOL: u16 = 0;
+const MICROSOFT_ENCODING_ID_UNICODE_BMP: u16 = 1;
+const MICROSOFT_ENCODING_ID_UNICODE_UCS4: u16 = 10;
+
+const MACINTOSH_LANGUAGE_ID_ENGLISH: u16 = 0;
+const MICROSOFT_LANGUAGE_ID_ENGLISH_US: u16 = 0x0409;
+
+const NAME_RECORD_SIZE: usize = 12;
+
+#[derive(Clone, Copy, Debug)]
+pub struct NameTable<'a> {
+    records: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> NameTable<'a> {
+    pub fn new(table: FontTable) -> Result<NameTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the format. Format 1 adds language-tag records, which we don't use, after the
+        // name records.
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if format > 1 {
+            return Err(FontError::UnsupportedNameVersion)
+        }
+
+        let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let string_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let records = match reader.get(0..(count as usize * NAME_RECORD_SIZE)) {
+            Some(records) => records,
+            None => return Err(FontError::UnexpectedEof),
+        };
+        let strings = match table.bytes.get(string_offset as usize..) {
+            Some(strings) => strings,
+            None => return Err(FontError::UnexpectedEof),
+        };
+
+        Ok(NameTable {
+            records: records,
+            strings: strings,
+        })
+    }
+
+    /// Returns the string with the given name ID, decoded from whichever record suits best.
+    ///
+    /// If a language is given, only records in that Windows language ID (such as 0x0409 for US
+    /// English) match. Otherwise, US English is preferred, followed by records in the Unicode
+    /// platform, English Macintosh records, and then records in any other language.
+    ///
+    /// Records in encodings other than Unicode and Mac OS Roman are ignored.
+    pub fn name(&self, name_id: u16, language_id: Option<u16>)
+                -> Result<Option<String>, FontError> {
+        let mut best_record = None;
+        let mut reader = self.records;
+        while !reader.is_empty() {
+            let platform_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let encoding_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let record_language_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let record_name_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if record_name_id != name_id {
+                continue
+            }
+
+            let encoding = match (platform_id, encoding_id) {
+                (PLATFORM_ID_UNICODE, _) |
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_SYMBOL) |
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP) |
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_UCS4) => {
+                    NameEncoding::Utf16Be
+                }
+                (PLATFORM_ID_MACINTOSH, MACINTOSH_ENCODING_ID_ROMAN) => NameEncoding::MacRoman,
+                _ => continue,
+            };
+
+            let rank = match (language_id, platform_id, record_language_id) {
+                (Some(language_id), PLATFORM_ID_MICROSOFT, record_language_id) if
+                        record_language_id == language_id => 0,
+                (Some(_), _, _) => continue,
+                (None, PLATFORM_ID_MICROSOFT, MICROSOFT_LANGUAGE_ID_ENGLISH_US) => 0,
+                (None, PLATFORM_ID_UNICODE, _) => 1,
+                (None, PLATFORM_ID_MACINTOSH, MACINTOSH_LANGUAGE_ID_ENGLISH) => 2,
+                (None, PLATFORM_ID_MICROSOFT, _) => 3,
+                (None, _, _) => 4,
+            };
+
+            match best_record {
+                Some((best_rank, _, _, _)) if best_rank <= rank => {}
+                _ => best_record = Some((rank, encoding, offset, length)),
+            }
+        }
+
+        let (encoding, offset, length) = match best_record {
+            None => return Ok(None),
+            Some((_, encoding, offset, length)) => (encoding, offset, length),
+        };
+
+        let mut reader = self.strings;
+        try!(reader.jump(offset as usize).map_err(FontError::eof));
+        let bytes = match reader.get(0..(length as usize)) {
+            Some(bytes) => bytes,
+            None => return Err(FontError::UnexpectedEof),
+        };
+
+        match encoding {
+            NameEncoding::Utf16Be => {
+                let mut reader = bytes;
+                let mut code_units = Vec::with_capacity(bytes.len() / mem::size_of::<u16>());
+                while reader.len() >= mem::size_of::<u16>() {
+                    code_units.push(try!(reader.read_u16::<BigEndian>()
+                                               .map_err(FontError::eof)))
+                }
+                Ok(Some(String::from_utf16_lossy(&code_units)))
+            }
+            NameEncoding::MacRoman => {
+                Ok(Some(bytes.iter().map(|&code| util::mac_roman_to_unicode(code)).collect()))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NameEncoding {
+    Utf16Be,
+    MacRoman,
+}
@@ -11,6 +11,7 @@
 use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
 use font::FontTable;
+use std::io::Read;
 use std::mem;
 use util::Jump;
 
@@ -19,11 +20,66 @@ pub const TAG: u32 = ((b'O' as u32) << 24) |
                       ((b'/' as u32) << 8)  |
                        (b'2' as u32);
 
+bitflags! {
+    /// Style information from the `fsSelection` field of the `OS/2` table.
+    pub flags FsSelection: u16 {
+        const ITALIC = 1 << 0,
+        const UNDERSCORE = 1 << 1,
+        const NEGATIVE = 1 << 2,
+        const OUTLINED = 1 << 3,
+        const STRIKEOUT = 1 << 4,
+        const BOLD = 1 << 5,
+        const REGULAR = 1 << 6,
+        const USE_TYPO_METRICS = 1 << 7,
+        const WWS = 1 << 8,
+        const OBLIQUE = 1 << 9,
+    }
+}
+
+bitflags! {
+    /// The embedding licensing rights for the font, from the `fsType` field of the `OS/2` table.
+    ///
+    /// If no bits are set, the font may be installed and embedded freely.
+    pub flags EmbeddingPermissions: u16 {
+        const RESTRICTED_LICENSE_EMBEDDING = 1 << 1,
+        const PREVIEW_AND_PRINT_EMBEDDING = 1 << 2,
+        const EDITABLE_EMBEDDING = 1 << 3,
+        const NO_SUBSETTING = 1 << 8,
+        const BITMAP_EMBEDDING_ONLY = 1 << 9,
+    }
+}
+
+impl EmbeddingPermissions {
+    /// Returns true if the font must not be embedded in documents at all.
+    ///
+    /// Fonts that set several usage permissions are subject to the least restrictive one, so this
+    /// is only the case if restricted license embedding is the only one set.
+    #[inline]
+    pub fn is_restricted(&self) -> bool {
+        self.contains(RESTRICTED_LICENSE_EMBEDDING) &&
+            !self.intersects(PREVIEW_AND_PRINT_EMBEDDING | EDITABLE_EMBEDDING)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Os2Table {
+    pub version: u16,
+    pub weight_class: u16,
+    pub width_class: u16,
+    pub embedding_permissions: EmbeddingPermissions,
+    pub strikeout_size: i16,
+    pub strikeout_position: i16,
+    pub panose: [u8; 10],
+    pub unicode_ranges: [u32; 4],
+    pub fs_selection: FsSelection,
     pub typo_ascender: i16,
     pub typo_descender: i16,
     pub typo_line_gap: i16,
+    pub win_ascent: u16,
+    pub win_descent: u16,
+    pub code_page_ranges: [u32; 2],
+    pub x_height: Option<i16>,
+    pub cap_height: Option<i16>,
 }
 
 impl Os2Table {
@@ -34,27 +90,81 @@ impl Os2Table {
         // Postel's law and hope for the best.
         let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
 
-        // Skip to the line gap.
-        try!(reader.jump(mem::size_of::<u16>() * 15).map_err(FontError::eof));
-        try!(reader.jump(10).map_err(FontError::eof));
-        if version == 0 {
-            try!(reader.jump(mem::size_of::<u32>() * 2).map_err(FontError::eof));
-        } else {
-            try!(reader.jump(mem::size_of::<u32>() * 5).map_err(FontError::eof));
+        // Read the style and licensing information.
+        try!(reader.jump(mem::size_of::<i16>()).map_err(FontError::eof));
+        let weight_class = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let width_class = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let fs_type = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Skip the subscript and superscript metrics.
+        try!(reader.jump(mem::size_of::<i16>() * 8).map_err(FontError::eof));
+
+        let strikeout_size = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let strikeout_position = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+
+        // Skip the family class.
+        try!(reader.jump(mem::size_of::<i16>()).map_err(FontError::eof));
+
+        let mut panose = [0; 10];
+        try!(reader.read_exact(&mut panose).map_err(FontError::eof));
+
+        let mut unicode_ranges = [0; 4];
+        for unicode_range in &mut unicode_ranges {
+            *unicode_range = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof))
         }
-        try!(reader.jump(mem::size_of::<u16>() * 3).map_err(FontError::eof));
+
+        // Skip the vendor ID.
+        try!(reader.jump(mem::size_of::<u32>()).map_err(FontError::eof));
+
+        let fs_selection = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Skip the first and last character indices.
+        try!(reader.jump(mem::size_of::<u16>() * 2).map_err(FontError::eof));
 
         // Read the line spacing information.
         let typo_ascender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
         let typo_descender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
         let typo_line_gap = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
 
+        // Read the Windows clipping metrics.
+        let win_ascent = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let win_descent = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Version 1 added the code page ranges.
+        let mut code_page_ranges = [0; 2];
+        if version >= 1 {
+            for code_page_range in &mut code_page_ranges {
+                *code_page_range = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof))
+            }
+        }
+
+        // Version 2 added the x-height and cap height.
+        let (x_height, cap_height) = if version >= 2 {
+            let x_height = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+            let cap_height = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+            (Some(x_height), Some(cap_height))
+        } else {
+            (None, None)
+        };
+
         Ok(Os2Table {
+            version: version,
+            weight_class: weight_class,
+            width_class: width_class,
+            embedding_permissions: EmbeddingPermissions::from_bits_truncate(fs_type),
+            strikeout_size: strikeout_size,
+            strikeout_position: strikeout_position,
+            panose: panose,
+            unicode_ranges: unicode_ranges,
+            fs_selection: FsSelection::from_bits_truncate(fs_selection),
             typo_ascender: typo_ascender,
             typo_descender: typo_descender,
             typo_line_gap: typo_line_gap,
+            win_ascent: win_ascent,
+            win_descent: win_descent,
+            code_page_ranges: code_page_ranges,
+            x_height: x_height,
+            cap_height: cap_height,
         })
     }
 }
-
-
@@ -0,0 +1,203 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The PostScript table, which holds glyph names among other information needed to print a font.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/post.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::str;
+use tables::variations;
+use util::Jump;
+
+pub const TAG: u32 = ((b'p' as u32) << 24) |
+                      ((b'o' as u32) << 16) |
+                      ((b's' as u32) << 8)  |
+                       (b't' as u32);
+
+const VERSION_1: u32 = 0x10000;
+const VERSION_2: u32 = 0x20000;
+
+// The size of the header common to all versions.
+const HEADER_SIZE: usize = 32;
+
+// The number of glyph names in the standard Macintosh glyph set.
+const MAC_GLYPH_NAME_COUNT: u16 = 258;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PostTable<'a> {
+    pub italic_angle: f32,
+    pub underline_position: i16,
+    pub underline_thickness: i16,
+
+    version: u32,
+    // For version 2.0, the glyph name index array.
+    glyph_name_indices: &'a [u8],
+    // For version 2.0, the Pascal strings holding the names not in the standard Macintosh set.
+    names: &'a [u8],
+}
+
+impl<'a> PostTable<'a> {
+    pub fn new(table: FontTable) -> Result<PostTable, FontError> {
+        // Every version shares the header, even the ones whose glyph names we can't read.
+        if table.bytes.len() < HEADER_SIZE {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        let mut reader = table.bytes;
+        let version = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let italic_angle = try!(variations::read_fixed(&mut reader));
+        let underline_position = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let underline_thickness = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+
+        // Only version 2.0 has any data after the header.
+        let mut post_table = PostTable {
+            italic_angle: italic_angle,
+            underline_position: underline_position,
+            underline_thickness: underline_thickness,
+            version: version,
+            glyph_name_indices: &[],
+            names: &[],
+        };
+        if version != VERSION_2 {
+            return Ok(post_table)
+        }
+
+        let mut reader = table.bytes;
+        try!(reader.jump(HEADER_SIZE).map_err(FontError::eof));
+        let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let glyph_name_indices_size = glyph_count as usize * 2;
+        if reader.len() < glyph_name_indices_size {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        post_table.glyph_name_indices = &reader[0..glyph_name_indices_size];
+        post_table.names = &reader[glyph_name_indices_size..];
+        Ok(post_table)
+    }
+
+    /// Returns the PostScript name of the given glyph, or `None` if the table doesn't name it.
+    ///
+    /// Only version 1.0 and 2.0 tables name glyphs.
+    pub fn glyph_name(&self, glyph_id: u16) -> Result<Option<&'a str>, FontError> {
+        match self.version {
+            VERSION_1 => Ok(MAC_GLYPH_NAMES.get(glyph_id as usize).map(|name| *name)),
+            VERSION_2 => {
+                let mut reader = self.glyph_name_indices;
+                if reader.jump(glyph_id as usize * 2).is_err() || reader.is_empty() {
+                    return Ok(None)
+                }
+                let name_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                if name_index < MAC_GLYPH_NAME_COUNT {
+                    return Ok(Some(MAC_GLYPH_NAMES[name_index as usize]))
+                }
+                self.custom_name(name_index - MAC_GLYPH_NAME_COUNT)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the ID of the first glyph with the given PostScript name, if there is one.
+    pub fn glyph_id_for_name(&self, name: &str) -> Result<Option<u16>, FontError> {
+        let mac_name_index = MAC_GLYPH_NAMES.iter().position(|mac_name| *mac_name == name);
+        match self.version {
+            VERSION_1 => return Ok(mac_name_index.map(|index| index as u16)),
+            VERSION_2 => {}
+            _ => return Ok(None),
+        }
+
+        // Find the name index that the name has, if any, and then look for a glyph with it. Fonts
+        // may redundantly store standard names as custom ones, so either index could be used.
+        let mac_name_index = mac_name_index.map(|index| index as u16);
+        let custom_name_index = try!(self.custom_name_index(name));
+        if mac_name_index.is_none() && custom_name_index.is_none() {
+            return Ok(None)
+        }
+
+        let mut reader = self.glyph_name_indices;
+        for glyph_id in 0..(self.glyph_name_indices.len() / 2) {
+            let name_index = Some(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)));
+            if name_index == mac_name_index || name_index == custom_name_index {
+                return Ok(Some(glyph_id as u16))
+            }
+        }
+        Ok(None)
+    }
+
+    // Returns the custom (non-Macintosh) name at the given index.
+    fn custom_name(&self, index: u16) -> Result<Option<&'a str>, FontError> {
+        let mut reader = self.names;
+        for _ in 0..index {
+            let length = try!(reader.read_u8().map_err(FontError::eof));
+            try!(reader.jump(length as usize).map_err(FontError::eof));
+        }
+
+        let length = try!(reader.read_u8().map_err(FontError::eof)) as usize;
+        match reader.get(0..length) {
+            Some(name) => Ok(str::from_utf8(name).ok()),
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+
+    // Returns the name index of the given custom (non-Macintosh) name, if the table has it.
+    fn custom_name_index(&self, name: &str) -> Result<Option<u16>, FontError> {
+        let mut reader = self.names;
+        let mut index = MAC_GLYPH_NAME_COUNT;
+        while !reader.is_empty() {
+            let length = try!(reader.read_u8().map_err(FontError::eof)) as usize;
+            match reader.get(0..length) {
+                Some(custom_name) if custom_name == name.as_bytes() => return Ok(Some(index)),
+                Some(_) => {}
+                None => return Err(FontError::UnexpectedEof),
+            }
+            reader = &reader[length..];
+            index = match index.checked_add(1) {
+                Some(index) => index,
+                None => break,
+            };
+        }
+        Ok(None)
+    }
+}
+
+// The standard Macintosh glyph set, in order.
+static MAC_GLYPH_NAMES: [&'static str; 258] = [
+    ".notdef", ".null", "nonmarkingreturn", "space", "exclam", "quotedbl", "numbersign", "dollar",
+    "percent", "ampersand", "quotesingle", "parenleft", "parenright", "asterisk", "plus", "comma",
+    "hyphen", "period", "slash", "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question", "at", "A", "B",
+    "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U",
+    "V", "W", "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum",
+    "underscore", "grave", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
+    "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z", "braceleft", "bar", "braceright",
+    "asciitilde", "Adieresis", "Aring", "Ccedilla", "Eacute", "Ntilde", "Odieresis", "Udieresis",
+    "aacute", "agrave", "acircumflex", "adieresis", "atilde", "aring", "ccedilla", "eacute",
+    "egrave", "ecircumflex", "edieresis", "iacute", "igrave", "icircumflex", "idieresis", "ntilde",
+    "oacute", "ograve", "ocircumflex", "odieresis", "otilde", "uacute", "ugrave", "ucircumflex",
+    "udieresis", "dagger", "degree", "cent", "sterling", "section", "bullet", "paragraph",
+    "germandbls", "registered", "copyright", "trademark", "acute", "dieresis", "notequal", "AE",
+    "Oslash", "infinity", "plusminus", "lessequal", "greaterequal", "yen", "mu", "partialdiff",
+    "summation", "product", "pi", "integral", "ordfeminine", "ordmasculine", "Omega", "ae",
+    "oslash", "questiondown", "exclamdown", "logicalnot", "radical", "florin", "approxequal",
+    "Delta", "guillemotleft", "guillemotright", "ellipsis", "nonbreakingspace", "Agrave", "Atilde",
+    "Otilde", "OE", "oe", "endash", "emdash", "quotedblleft", "quotedblright", "quoteleft",
+    "quoteright", "divide", "lozenge", "ydieresis", "Ydieresis", "fraction", "currency",
+    "guilsinglleft", "guilsinglright", "fi", "fl", "daggerdbl", "periodcentered", "quotesinglbase",
+    "quotedblbase", "perthousand", "Acircumflex", "Ecircumflex", "Aacute", "Edieresis", "Egrave",
+    "Iacute", "Icircumflex", "Idieresis", "Igrave", "Oacute", "Ocircumflex", "apple", "Ograve",
+    "Uacute", "Ucircumflex", "Ugrave", "dotlessi", "circumflex", "tilde", "macron", "breve",
+    "dotaccent", "ring", "cedilla", "hungarumlaut", "ogonek", "caron", "Lslash", "lslash", "Scaron",
+    "scaron", "Zcaron", "zcaron", "brokenbar", "Eth", "eth", "Yacute", "yacute", "Thorn", "thorn",
+    "minus", "multiply", "onesuperior", "twosuperior", "threesuperior", "onehalf", "onequarter",
+    "threequarters", "franc", "Gbreve", "gbreve", "Idotaccent", "Scedilla", "scedilla", "Cacute",
+    "cacute", "Ccaron", "ccaron", "dcroat",
+];
@@ -0,0 +1,218 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Data structures that the font variation tables share.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/otvarcommonformats.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use std::mem;
+use util::Jump;
+
+const WORD_DELTA_COUNT_MASK: u16 = 0x7fff;
+const LONG_WORDS: u16 = 0x8000;
+
+const INNER_INDEX_BIT_COUNT_MASK: u8 = 0x0f;
+const MAP_ENTRY_SIZE_MASK: u8 = 0x30;
+const MAP_ENTRY_SIZE_SHIFT: u8 = 4;
+
+/// Variation deltas for arbitrary values, grouped into sets of items that vary over the same
+/// regions of the design space.
+#[derive(Clone, Copy, Debug)]
+pub struct ItemVariationStore<'a> {
+    // The store, starting at its header.
+    bytes: &'a [u8],
+    // The offset of the variation region list from the start of the store.
+    region_list: u32,
+    item_variation_data_count: u16,
+}
+
+impl<'a> ItemVariationStore<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<ItemVariationStore<'a>, FontError> {
+        let mut reader = bytes;
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if format != 1 {
+            return Err(FontError::UnknownFormat)
+        }
+
+        let region_list = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let item_variation_data_count = try!(reader.read_u16::<BigEndian>()
+                                                   .map_err(FontError::eof));
+        Ok(ItemVariationStore {
+            bytes: bytes,
+            region_list: region_list,
+            item_variation_data_count: item_variation_data_count,
+        })
+    }
+
+    /// Returns how much each region that the given item variation data refers to applies at the
+    /// given normalized coordinates, in the order of the data's deltas.
+    pub fn region_scalars(&self, outer_index: u16, coords: &[f32])
+                          -> Result<Vec<f32>, FontError> {
+        let mut reader = try!(self.item_variation_data(outer_index));
+
+        // Skip the item count and the short delta count.
+        try!(reader.jump(mem::size_of::<u16>() * 2).map_err(FontError::eof));
+        let region_index_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let mut scalars = Vec::with_capacity(region_index_count as usize);
+        for _ in 0..region_index_count {
+            let region_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            scalars.push(try!(self.region_scalar(region_index, coords)))
+        }
+        Ok(scalars)
+    }
+
+    /// Returns the net delta of the given item at the given normalized coordinates.
+    pub fn delta(&self, outer_index: u16, inner_index: u16, coords: &[f32])
+                 -> Result<f32, FontError> {
+        let scalars = try!(self.region_scalars(outer_index, coords));
+
+        let mut reader = try!(self.item_variation_data(outer_index));
+        let item_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let word_delta_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let region_index_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if inner_index >= item_count {
+            return Err(FontError::VariationDataNotFound)
+        }
+
+        // Each row has the wide deltas first, followed by the narrow ones. If the `LONG_WORDS`
+        // flag is set, these are 32 and 16 bits wide respectively instead of 16 and 8.
+        let long_words = (word_delta_count & LONG_WORDS) != 0;
+        let word_delta_count = (word_delta_count & WORD_DELTA_COUNT_MASK) as usize;
+        let (word_size, narrow_size) = if long_words {
+            (mem::size_of::<i32>(), mem::size_of::<i16>())
+        } else {
+            (mem::size_of::<i16>(), mem::size_of::<i8>())
+        };
+        let row_size = word_size * word_delta_count +
+            narrow_size * (region_index_count as usize).saturating_sub(word_delta_count);
+        try!(reader.jump(mem::size_of::<u16>() * region_index_count as usize +
+                         row_size * inner_index as usize).map_err(FontError::eof));
+
+        let mut delta = 0.0;
+        for (region, &scalar) in scalars.iter().enumerate() {
+            let region_delta = match (region < word_delta_count, long_words) {
+                (true, true) => try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)),
+                (true, false) | (false, true) => {
+                    try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as i32
+                }
+                (false, false) => try!(reader.read_i8().map_err(FontError::eof)) as i32,
+            };
+            delta += region_delta as f32 * scalar
+        }
+        Ok(delta)
+    }
+
+    // Returns the item variation data subtable with the given index.
+    fn item_variation_data(&self, outer_index: u16) -> Result<&'a [u8], FontError> {
+        if outer_index >= self.item_variation_data_count {
+            return Err(FontError::VariationDataNotFound)
+        }
+
+        let mut reader = self.bytes;
+        try!(reader.jump(mem::size_of::<u16>() * 2 + mem::size_of::<u32>() +
+                         mem::size_of::<u32>() * outer_index as usize).map_err(FontError::eof));
+        let offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        self.bytes.get(offset as usize..).ok_or(FontError::UnexpectedEof)
+    }
+
+    // Returns how much the region with the given index applies at the given coordinates.
+    fn region_scalar(&self, region_index: u16, coords: &[f32]) -> Result<f32, FontError> {
+        let mut reader = self.bytes;
+        try!(reader.jump(self.region_list as usize).map_err(FontError::eof));
+
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let region_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if region_index >= region_count {
+            return Err(FontError::VariationDataNotFound)
+        }
+
+        // Each region has a start, peak, and end coordinate per axis.
+        try!(reader.jump(mem::size_of::<i16>() * 3 * axis_count as usize * region_index as usize)
+                   .map_err(FontError::eof));
+
+        let mut scalar = 1.0;
+        for axis in 0..(axis_count as usize) {
+            let start = try!(read_f2dot14(&mut reader));
+            let peak = try!(read_f2dot14(&mut reader));
+            let end = try!(read_f2dot14(&mut reader));
+            let coord = coords.get(axis).cloned().unwrap_or(0.0);
+            scalar *= axis_scalar(coord, start, peak, end)
+        }
+        Ok(scalar)
+    }
+}
+
+/// Looks up the outer and inner indices of the delta set for the given item in a delta set index
+/// map, which starts at the beginning of `map`.
+///
+/// Items past the end of the map use the last entry.
+pub fn delta_set_index(map: &[u8], item: u32) -> Result<(u16, u16), FontError> {
+    let mut reader = map;
+    let format = try!(reader.read_u8().map_err(FontError::eof));
+    let entry_format = try!(reader.read_u8().map_err(FontError::eof));
+    let map_count = match format {
+        0 => try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32,
+        1 => try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)),
+        _ => return Err(FontError::UnknownFormat),
+    };
+    if map_count == 0 {
+        return Err(FontError::VariationDataNotFound)
+    }
+
+    let inner_index_bit_count = (entry_format & INNER_INDEX_BIT_COUNT_MASK) as u32 + 1;
+    let entry_size = ((entry_format & MAP_ENTRY_SIZE_MASK) >> MAP_ENTRY_SIZE_SHIFT) as usize + 1;
+    let item = if item < map_count { item } else { map_count - 1 };
+    try!(reader.jump(entry_size * item as usize).map_err(FontError::eof));
+
+    let mut entry = 0u32;
+    for _ in 0..entry_size {
+        entry = (entry << 8) | try!(reader.read_u8().map_err(FontError::eof)) as u32
+    }
+
+    let outer_index = entry >> inner_index_bit_count;
+    let inner_index = entry & ((1 << inner_index_bit_count) - 1);
+    Ok((outer_index as u16, inner_index as u16))
+}
+
+/// Returns how much a region that spans from `start` to `end` along an axis and peaks at `peak`
+/// applies at the given normalized coordinate on that axis.
+///
+/// The result is 1 at the peak and falls off linearly to 0 at the start and the end. Regions with
+/// no peak, or with inconsistent coordinates, apply everywhere.
+pub fn axis_scalar(coord: f32, start: f32, peak: f32, end: f32) -> f32 {
+    if peak == 0.0 || start > peak || peak > end || (start < 0.0 && end > 0.0) {
+        return 1.0
+    }
+
+    if coord == peak {
+        1.0
+    } else if coord <= start || coord >= end {
+        0.0
+    } else if coord < peak {
+        (coord - start) / (peak - start)
+    } else {
+        (end - coord) / (end - peak)
+    }
+}
+
+/// Reads a signed 2.14 fixed-point number, the format of normalized coordinates.
+pub fn read_f2dot14(reader: &mut &[u8]) -> Result<f32, FontError> {
+    let value = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+    Ok(value as f32 / 16384.0)
+}
+
+/// Reads a signed 16.16 fixed-point number, the format of user-space axis values.
+pub fn read_fixed(reader: &mut &[u8]) -> Result<f32, FontError> {
+    let value = try!(reader.read_i32::<BigEndian>().map_err(FontError::eof));
+    Ok(value as f32 / 65536.0)
+}
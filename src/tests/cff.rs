@@ -0,0 +1,279 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, WriteBytesExt};
+use containers::otf;
+use error::FontError;
+use font::Font;
+use tables::{cff, cmap, head, hhea, hmtx, maxp};
+
+const OTTO: u32 = ((b'O' as u32) << 24) |
+                  ((b'T' as u32) << 16) |
+                  ((b'T' as u32) << 8)  |
+                   (b'O' as u32);
+
+// DICT operators.
+const OPERATOR_ENCODING: u8 = 16;
+const OPERATOR_CHAR_STRINGS: u8 = 17;
+const OPERATOR_PRIVATE: u8 = 18;
+
+// CharString operators.
+const OPERATOR_RLINETO: u8 = 5;
+const OPERATOR_ENDCHAR: u8 = 14;
+const OPERATOR_RMOVETO: u8 = 21;
+
+struct TestFont {
+    name: &'static str,
+    // The advance widths of the glyphs after `.notdef`, each of which is a box.
+    widths: Vec<i16>,
+    // The raw encoding, if the font doesn't use the Standard Encoding.
+    encoding: Option<Vec<u8>>,
+}
+
+// Builds an INDEX with 32-bit offsets.
+fn index(elements: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.write_u16::<BigEndian>(elements.len() as u16).unwrap();
+    if elements.is_empty() {
+        return bytes
+    }
+
+    bytes.push(4);
+    let mut offset = 1;
+    bytes.write_u32::<BigEndian>(offset).unwrap();
+    for element in elements {
+        offset += element.len() as u32;
+        bytes.write_u32::<BigEndian>(offset).unwrap();
+    }
+    for element in elements {
+        bytes.extend_from_slice(element)
+    }
+    bytes
+}
+
+// Always uses the five-byte encoding, so that the size of a DICT doesn't depend on its operands.
+fn push_dict_operand(dict: &mut Vec<u8>, value: usize) {
+    dict.push(29);
+    dict.write_i32::<BigEndian>(value as i32).unwrap();
+}
+
+fn push_char_string_operands(char_string: &mut Vec<u8>, operands: &[i16], operator: u8) {
+    for &operand in operands {
+        char_string.push(28);
+        char_string.write_i16::<BigEndian>(operand).unwrap();
+    }
+    char_string.push(operator)
+}
+
+// A box that fills the given advance width, less a margin on either side.
+fn box_char_string(width: i16) -> Vec<u8> {
+    let mut char_string = vec![];
+    push_char_string_operands(&mut char_string, &[width, 10, 0], OPERATOR_RMOVETO);
+    push_char_string_operands(&mut char_string, &[width - 20, 0], OPERATOR_RLINETO);
+    push_char_string_operands(&mut char_string, &[0, 500], OPERATOR_RLINETO);
+    push_char_string_operands(&mut char_string, &[], OPERATOR_ENDCHAR);
+    char_string
+}
+
+// Builds a bare CFF FontSet containing the given fonts, each with an empty Private DICT and no
+// charset, so that they use the ISOAdobe charset.
+fn font_set(fonts: &[TestFont]) -> Vec<u8> {
+    let names: Vec<_> = fonts.iter().map(|font| font.name.as_bytes().to_vec()).collect();
+
+    // The Top DICTs refer to data that follows them, so work out how big they'll be first.
+    let top_dict_sizes: Vec<_> = fonts.iter().map(|font| {
+        vec![0; if font.encoding.is_some() { 23 } else { 17 }]
+    }).collect();
+    let data_offset = 4 + index(&names).len() + index(&top_dict_sizes).len() + index(&[]).len() +
+        index(&[]).len();
+
+    let (mut top_dicts, mut data) = (vec![], vec![]);
+    for font in fonts {
+        let mut top_dict = vec![];
+
+        let mut char_strings = vec![vec![OPERATOR_ENDCHAR]];
+        char_strings.extend(font.widths.iter().map(|&width| box_char_string(width)));
+        push_dict_operand(&mut top_dict, data_offset + data.len());
+        top_dict.push(OPERATOR_CHAR_STRINGS);
+        data.extend_from_slice(&index(&char_strings));
+
+        push_dict_operand(&mut top_dict, 0);
+        push_dict_operand(&mut top_dict, data_offset + data.len());
+        top_dict.push(OPERATOR_PRIVATE);
+
+        if let Some(ref encoding) = font.encoding {
+            push_dict_operand(&mut top_dict, data_offset + data.len());
+            top_dict.push(OPERATOR_ENCODING);
+            data.extend_from_slice(encoding);
+        }
+
+        top_dicts.push(top_dict)
+    }
+
+    let mut bytes = vec![1, 0, 4, 4];
+    bytes.extend_from_slice(&index(&names));
+    bytes.extend_from_slice(&index(&top_dicts));
+    bytes.extend_from_slice(&index(&[]));
+    bytes.extend_from_slice(&index(&[]));
+    bytes.extend_from_slice(&data);
+    bytes
+}
+
+// Wraps a FontSet in an OpenType font with the other tables that fonts must have and an empty
+// character map.
+fn otf(cff: Vec<u8>, glyph_count: u16) -> Vec<u8> {
+    let mut head = vec![];
+    head.write_u16::<BigEndian>(1).unwrap();
+    head.write_u16::<BigEndian>(0).unwrap();
+    head.write_u32::<BigEndian>(0x10000).unwrap();
+    head.write_u32::<BigEndian>(0).unwrap();
+    head.write_u32::<BigEndian>(0x5f0f3cf5).unwrap();
+    head.write_u16::<BigEndian>(0).unwrap();
+    head.write_u16::<BigEndian>(1000).unwrap();
+    head.write_i64::<BigEndian>(0).unwrap();
+    head.write_i64::<BigEndian>(0).unwrap();
+    for &bound in &[0, 0, 1000, 1000] {
+        head.write_i16::<BigEndian>(bound).unwrap();
+    }
+    for &field in &[0, 0, 2, 0, 0] {
+        head.write_i16::<BigEndian>(field).unwrap();
+    }
+
+    let mut hhea = vec![];
+    hhea.write_u16::<BigEndian>(1).unwrap();
+    hhea.write_u16::<BigEndian>(0).unwrap();
+    for &field in &[800, -200, 0, 1000, 0, 0, 1000, 1, 0, 0, 0, 0, 0, 0, 0] {
+        hhea.write_i16::<BigEndian>(field).unwrap();
+    }
+    hhea.write_u16::<BigEndian>(glyph_count).unwrap();
+
+    let mut hmtx = vec![];
+    for _ in 0..glyph_count {
+        hmtx.write_u16::<BigEndian>(500).unwrap();
+        hmtx.write_i16::<BigEndian>(0).unwrap();
+    }
+
+    let mut maxp = vec![];
+    maxp.write_u32::<BigEndian>(0x5000).unwrap();
+    maxp.write_u16::<BigEndian>(glyph_count).unwrap();
+
+    otf::write_otf(OTTO, &[
+        (cff::TAG, cff),
+        (cmap::TAG, vec![0, 0, 0, 0]),
+        (head::TAG, head),
+        (hhea::TAG, hhea),
+        (hmtx::TAG, hmtx),
+        (maxp::TAG, maxp),
+    ])
+}
+
+fn otf_with_encoding(encoding: Option<Vec<u8>>) -> Vec<u8> {
+    let fonts = [TestFont { name: "Test", widths: vec![250, 333], encoding: encoding }];
+    otf(font_set(&fonts), 3)
+}
+
+#[test]
+fn standard_encoding() {
+    let bytes = otf_with_encoding(None);
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    // The ISOAdobe charset names glyphs 1 and 2 `space` and `exclam`, which the Standard Encoding
+    // puts at their ASCII codes. `quotedbl` would be glyph 3, which the font doesn't have.
+    assert_eq!(font.glyph_id_for_cff_code(b' '), Some(1));
+    assert_eq!(font.glyph_id_for_cff_code(b'!'), Some(2));
+    assert_eq!(font.glyph_id_for_cff_code(b'"'), None);
+    assert_eq!(font.glyph_id_for_cff_code(0), None);
+}
+
+#[test]
+fn custom_encoding_with_codes() {
+    let bytes = otf_with_encoding(Some(vec![0, 2, b'b', b'a']));
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    assert_eq!(font.glyph_id_for_cff_code(b'b'), Some(1));
+    assert_eq!(font.glyph_id_for_cff_code(b'a'), Some(2));
+    assert_eq!(font.glyph_id_for_cff_code(b'c'), None);
+    assert_eq!(font.glyph_id_for_cff_code(b' '), None);
+}
+
+#[test]
+fn custom_encoding_with_ranges_and_supplements() {
+    // One range of two codes, starting at `x`, and a supplement mapping `!` to `space`.
+    let bytes = otf_with_encoding(Some(vec![0x81, 1, b'x', 1, 1, b'!', 0, 1]));
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    assert_eq!(font.glyph_id_for_cff_code(b'x'), Some(1));
+    assert_eq!(font.glyph_id_for_cff_code(b'y'), Some(2));
+    assert_eq!(font.glyph_id_for_cff_code(b'z'), None);
+    assert_eq!(font.glyph_id_for_cff_code(b'!'), Some(1));
+}
+
+fn alpha_and_beta() -> Vec<u8> {
+    font_set(&[
+        TestFont { name: "Alpha", widths: vec![250, 333], encoding: None },
+        TestFont { name: "Beta", widths: vec![400, 500, 600], encoding: None },
+    ])
+}
+
+#[test]
+fn bare_font_set_loads_first_font() {
+    let bytes = alpha_and_beta();
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    assert_eq!(font.cff_font_count(), 2);
+    assert_eq!(font.postscript_name(), Some("Alpha".to_owned()));
+    assert_eq!(font.metrics_for_glyph(2).unwrap().advance_width, 333);
+    assert!(font.metrics_for_glyph(3).is_err());
+}
+
+#[test]
+fn bare_font_set_loads_font_by_index() {
+    let bytes = alpha_and_beta();
+    let mut buffer = vec![];
+    let font = Font::from_cff_font_set_index(&bytes, 1, &mut buffer).unwrap();
+
+    assert_eq!(font.postscript_name(), Some("Beta".to_owned()));
+    let metrics = font.metrics_for_glyph(3).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (600, 10));
+    assert_eq!(font.cff_advance_width(3).unwrap(), Some(600.0));
+
+    let bounds = font.glyph_bounds(3).unwrap();
+    assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top), (10, 0, 590, 500));
+
+    let mut buffer = vec![];
+    let font = Font::from_collection_index(&bytes, 1, &mut buffer).unwrap();
+    assert_eq!(font.postscript_name(), Some("Beta".to_owned()));
+
+    let mut buffer = vec![];
+    assert_eq!(Font::from_cff_font_set_index(&bytes, 2, &mut buffer).err(),
+               Some(FontError::FontIndexOutOfBounds));
+}
+
+#[test]
+fn bare_font_set_loads_font_by_name() {
+    let bytes = alpha_and_beta();
+    let mut buffer = vec![];
+    let font = Font::from_cff_font_set_name(&bytes, "Beta", &mut buffer).unwrap();
+    assert_eq!(font.metrics_for_glyph(1).unwrap().advance_width, 400);
+
+    let mut buffer = vec![];
+    assert_eq!(Font::from_cff_font_set_name(&bytes, "Gamma", &mut buffer).err(),
+               Some(FontError::CffFontNotFound));
+}
+
+#[test]
+fn otf_font_set_loads_font_by_index() {
+    let bytes = otf(alpha_and_beta(), 4);
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    assert_eq!(font.cff_font_count(), 2);
+    assert_eq!(font.cff_advance_width(2).unwrap(), Some(333.0));
+
+    let mut buffer = vec![];
+    let font = Font::from_cff_font_set_index(&bytes, 1, &mut buffer).unwrap();
+    assert_eq!(font.cff_advance_width(3).unwrap(), Some(600.0));
+}
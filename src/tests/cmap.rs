@@ -0,0 +1,240 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, WriteBytesExt};
+use charmap::CodepointRange;
+use font::FontTable;
+use tables::cmap::CmapTable;
+
+// Platform and encoding IDs.
+const UNICODE_BMP: (u16, u16) = (0, 3);
+const UNICODE_VARIATION_SEQUENCES: (u16, u16) = (0, 5);
+const MACINTOSH_ROMAN: (u16, u16) = (1, 0);
+const MICROSOFT_SYMBOL: (u16, u16) = (3, 0);
+const MICROSOFT_UNICODE_BMP: (u16, u16) = (3, 1);
+const MICROSOFT_UNICODE_UCS4: (u16, u16) = (3, 10);
+
+// Builds a `cmap` table from subtables and the platform and encoding IDs they're listed under.
+fn cmap(subtables: &[((u16, u16), Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.write_u16::<BigEndian>(0).unwrap();
+    bytes.write_u16::<BigEndian>(subtables.len() as u16).unwrap();
+
+    let mut offset = 4 + 8 * subtables.len();
+    for &((platform_id, encoding_id), ref subtable) in subtables {
+        bytes.write_u16::<BigEndian>(platform_id).unwrap();
+        bytes.write_u16::<BigEndian>(encoding_id).unwrap();
+        bytes.write_u32::<BigEndian>(offset as u32).unwrap();
+        offset += subtable.len()
+    }
+    for &(_, ref subtable) in subtables {
+        bytes.extend_from_slice(subtable)
+    }
+    bytes
+}
+
+// Maps each codepoint in the given inclusive range, with 0 for missing glyphs.
+fn glyph_ids(table: &[u8], start: u32, end: u32) -> Vec<u16> {
+    let cmap = CmapTable::new(FontTable {
+        bytes: table,
+    });
+    let glyph_mapping = cmap.glyph_mapping_for_codepoint_ranges(&[CodepointRange::new(start, end)])
+                            .unwrap();
+    (start..(end + 1)).map(|codepoint| glyph_mapping.glyph_for(codepoint).unwrap()).collect()
+}
+
+fn format_0(glyph_ids: &[(u8, u8)]) -> Vec<u8> {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    subtable.write_u16::<BigEndian>(262).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    let mut glyph_id_array = [0; 256];
+    for &(code, glyph_id) in glyph_ids {
+        glyph_id_array[code as usize] = glyph_id
+    }
+    subtable.extend_from_slice(&glyph_id_array);
+    subtable
+}
+
+fn format_6(first_code: u16, glyph_ids: &[u16]) -> Vec<u8> {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(6).unwrap();
+    subtable.write_u16::<BigEndian>(10 + 2 * glyph_ids.len() as u16).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    subtable.write_u16::<BigEndian>(first_code).unwrap();
+    subtable.write_u16::<BigEndian>(glyph_ids.len() as u16).unwrap();
+    for &glyph_id in glyph_ids {
+        subtable.write_u16::<BigEndian>(glyph_id).unwrap();
+    }
+    subtable
+}
+
+// Formats 12 and 13, which differ only in how they map the codepoints in each group.
+fn segmented_coverage(format: u16, groups: &[(u32, u32, u32)]) -> Vec<u8> {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(format).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    subtable.write_u32::<BigEndian>(16 + 12 * groups.len() as u32).unwrap();
+    subtable.write_u32::<BigEndian>(0).unwrap();
+    subtable.write_u32::<BigEndian>(groups.len() as u32).unwrap();
+    for &(start_char_code, end_char_code, glyph_id) in groups {
+        subtable.write_u32::<BigEndian>(start_char_code).unwrap();
+        subtable.write_u32::<BigEndian>(end_char_code).unwrap();
+        subtable.write_u32::<BigEndian>(glyph_id).unwrap();
+    }
+    subtable
+}
+
+#[test]
+fn format_0_maps_bytes() {
+    let table = cmap(&[(UNICODE_BMP, format_0(&[(0x41, 3), (0xff, 4)]))]);
+    assert_eq!(glyph_ids(&table, 0x40, 0x42), vec![0, 3, 0]);
+    assert_eq!(glyph_ids(&table, 0xff, 0x100), vec![4, 0]);
+}
+
+#[test]
+fn format_2_maps_single_and_double_bytes() {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(2).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+
+    // High byte 0x81 uses sub-header 1. Everything else uses sub-header 0, which covers single
+    // bytes.
+    for high_byte in 0..256 {
+        subtable.write_u16::<BigEndian>(if high_byte == 0x81 { 8 } else { 0 }).unwrap();
+    }
+
+    // Each `idRangeOffset` counts from its own position to the glyph ID array, which follows the
+    // two sub-headers. Sub-header 1 starts partway into the array, and adds 5 to its glyph IDs.
+    for &(first_code, entry_count, id_delta, id_range_offset) in &[(0, 256, 0, 10),
+                                                                   (0x40, 2, 5, 2 + 512)] {
+        subtable.write_u16::<BigEndian>(first_code).unwrap();
+        subtable.write_u16::<BigEndian>(entry_count).unwrap();
+        subtable.write_i16::<BigEndian>(id_delta).unwrap();
+        subtable.write_u16::<BigEndian>(id_range_offset).unwrap();
+    }
+
+    for code in 0..256 {
+        subtable.write_u16::<BigEndian>(if code == 0x41 { 3 } else { 0 }).unwrap();
+    }
+    subtable.write_u16::<BigEndian>(10).unwrap();
+    subtable.write_u16::<BigEndian>(11).unwrap();
+
+    let table = cmap(&[(UNICODE_BMP, subtable)]);
+    assert_eq!(glyph_ids(&table, 0x40, 0x41), vec![0, 3]);
+    assert_eq!(glyph_ids(&table, 0x813f, 0x8142), vec![0, 15, 16, 0]);
+
+    // 0x81 only starts two-byte codes, and 0x82 doesn't start any.
+    assert_eq!(glyph_ids(&table, 0x81, 0x81), vec![0]);
+    assert_eq!(glyph_ids(&table, 0x8240, 0x8240), vec![0]);
+}
+
+#[test]
+fn format_6_maps_a_range() {
+    let table = cmap(&[(UNICODE_BMP, format_6(0x20, &[1, 2, 3]))]);
+    assert_eq!(glyph_ids(&table, 0x1f, 0x23), vec![0, 1, 2, 3, 0]);
+}
+
+#[test]
+fn format_10_maps_a_range_outside_the_bmp() {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(10).unwrap();
+    subtable.write_u16::<BigEndian>(0).unwrap();
+    subtable.write_u32::<BigEndian>(24).unwrap();
+    subtable.write_u32::<BigEndian>(0).unwrap();
+    subtable.write_u32::<BigEndian>(0x1f600).unwrap();
+    subtable.write_u32::<BigEndian>(2).unwrap();
+    subtable.write_u16::<BigEndian>(4).unwrap();
+    subtable.write_u16::<BigEndian>(5).unwrap();
+
+    let table = cmap(&[(MICROSOFT_UNICODE_UCS4, subtable)]);
+    assert_eq!(glyph_ids(&table, 0x1f5ff, 0x1f602), vec![0, 4, 5, 0]);
+}
+
+#[test]
+fn format_12_maps_ranges_to_consecutive_glyphs() {
+    let table = cmap(&[(MICROSOFT_UNICODE_UCS4,
+                        segmented_coverage(12, &[(0x41, 0x43, 7), (0x10000, 0x10001, 20)]))]);
+    assert_eq!(glyph_ids(&table, 0x40, 0x44), vec![0, 7, 8, 9, 0]);
+    assert_eq!(glyph_ids(&table, 0x10000, 0x10002), vec![20, 21, 0]);
+}
+
+#[test]
+fn format_13_maps_ranges_to_one_glyph() {
+    let table = cmap(&[(MICROSOFT_UNICODE_UCS4,
+                        segmented_coverage(13, &[(0x41, 0x43, 7), (0x10000, 0x1ffff, 8)]))]);
+    assert_eq!(glyph_ids(&table, 0x40, 0x44), vec![0, 7, 7, 7, 0]);
+    assert_eq!(glyph_ids(&table, 0x1fffe, 0x20000), vec![8, 8, 0]);
+}
+
+#[test]
+fn format_14_maps_variation_sequences() {
+    let mut subtable = vec![];
+    subtable.write_u16::<BigEndian>(14).unwrap();
+    subtable.write_u32::<BigEndian>(49).unwrap();
+    subtable.write_u32::<BigEndian>(2).unwrap();
+
+    // U+FE0E uses the default glyphs of U+0041 and U+0042; U+FE0F has a glyph of its own for
+    // U+0041. Selectors are 24-bit.
+    for &(selector, default_uvs_offset, non_default_uvs_offset) in &[(0xfe0e, 32, 0),
+                                                                     (0xfe0f, 0, 40)] {
+        subtable.push(0);
+        subtable.write_u16::<BigEndian>(selector).unwrap();
+        subtable.write_u32::<BigEndian>(default_uvs_offset).unwrap();
+        subtable.write_u32::<BigEndian>(non_default_uvs_offset).unwrap();
+    }
+
+    subtable.write_u32::<BigEndian>(1).unwrap();
+    subtable.extend_from_slice(&[0, 0, 0x41, 1]);
+
+    subtable.write_u32::<BigEndian>(1).unwrap();
+    subtable.extend_from_slice(&[0, 0, 0x41]);
+    subtable.write_u16::<BigEndian>(9).unwrap();
+
+    let table = cmap(&[
+        (UNICODE_BMP, format_6(0x41, &[1, 2, 3])),
+        (UNICODE_VARIATION_SEQUENCES, subtable),
+    ]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &table,
+    });
+    assert_eq!(cmap.glyph_for_variation_sequence(0x41, 0xfe0f).unwrap(), Some(9));
+    assert_eq!(cmap.glyph_for_variation_sequence(0x42, 0xfe0f).unwrap(), None);
+    assert_eq!(cmap.glyph_for_variation_sequence(0x41, 0xfe0e).unwrap(), Some(1));
+    assert_eq!(cmap.glyph_for_variation_sequence(0x42, 0xfe0e).unwrap(), Some(2));
+    assert_eq!(cmap.glyph_for_variation_sequence(0x43, 0xfe0e).unwrap(), None);
+    assert_eq!(cmap.glyph_for_variation_sequence(0x41, 0xfe00).unwrap(), None);
+}
+
+#[test]
+fn symbol_subtables_map_latin_1_to_the_private_use_area() {
+    let table = cmap(&[(MICROSOFT_SYMBOL, format_6(0xf041, &[4, 5]))]);
+    assert_eq!(glyph_ids(&table, 0x40, 0x43), vec![0, 4, 5, 0]);
+    assert_eq!(glyph_ids(&table, 0xf041, 0xf042), vec![4, 5]);
+}
+
+#[test]
+fn mac_roman_subtables_map_through_mac_roman() {
+    // Mac OS Roman puts `ä` at 0x8a.
+    let table = cmap(&[(MACINTOSH_ROMAN, format_0(&[(0x41, 3), (0x8a, 6)]))]);
+    assert_eq!(glyph_ids(&table, 0x41, 0x41), vec![3]);
+    assert_eq!(glyph_ids(&table, 0xe4, 0xe4), vec![6]);
+    assert_eq!(glyph_ids(&table, 0x8a, 0x8a), vec![0]);
+}
+
+#[test]
+fn preferred_subtables_win_regardless_of_order() {
+    let table = cmap(&[
+        (MACINTOSH_ROMAN, format_0(&[(0x41, 1)])),
+        (MICROSOFT_UNICODE_BMP, format_6(0x41, &[2])),
+        (MICROSOFT_UNICODE_UCS4, segmented_coverage(12, &[(0x41, 0x41, 3)])),
+    ]);
+    assert_eq!(glyph_ids(&table, 0x41, 0x41), vec![3]);
+
+    let table = cmap(&[
+        (MACINTOSH_ROMAN, format_0(&[(0x41, 1)])),
+        (MICROSOFT_UNICODE_BMP, format_6(0x41, &[2])),
+    ]);
+    assert_eq!(glyph_ids(&table, 0x41, 0x41), vec![2]);
+}
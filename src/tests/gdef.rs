@@ -0,0 +1,154 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{FontTable, GlyphClass};
+use tables::gdef::GdefTable;
+use tests::gpos::{self, attachments};
+use tests::gsub::{self, substitute};
+use tests::layout::{Field, class_def, coverage, lookup, table, tag};
+
+const SINGLE: u16 = 1;
+const LIGATURE: u16 = 4;
+const MARK_TO_BASE: u16 = 4;
+const MARK_TO_MARK: u16 = 6;
+
+// Lookup flags.
+const IGNORE_MARKS: u16 = 1 << 3;
+const USE_MARK_FILTERING_SET: u16 = 1 << 4;
+
+// Glyph IDs, matching those of the `GPOS` tests.
+const A: u16 = 1;
+const B: u16 = 2;
+const F: u16 = 4;
+const I: u16 = 5;
+const FI: u16 = 7;
+const ACUTE: u16 = 10;
+const CEDILLA: u16 = 11;
+const DOT: u16 = 12;
+
+// Builds a `GDEF` table that classifies the glyphs above. Acute accents and dots attach above
+// (mark attachment class 1) and cedillas below (class 2). If `mark_glyph_sets` is true, the table
+// is version 1.2, with one mark glyph set for acute accents and another for the other marks.
+fn gdef(mark_glyph_sets: bool) -> Vec<u8> {
+    let mut fields = vec![
+        Field::U16(1),
+        Field::U16(if mark_glyph_sets { 2 } else { 0 }),
+        Field::Offset(class_def(A, &[1, 1, 0, 1, 1, 0, 2, 0, 0, 3, 3, 3])),
+        Field::Null,
+        Field::Null,
+        Field::Offset(class_def(ACUTE, &[1, 2, 1])),
+    ];
+    if mark_glyph_sets {
+        fields.push(Field::Offset(table(vec![
+            Field::U16(1),
+            Field::U16(2),
+            Field::Offset32(coverage(&[ACUTE])),
+            Field::Offset32(coverage(&[CEDILLA, DOT])),
+        ])))
+    }
+    table(fields)
+}
+
+fn gdef_table<'a>(gdef: &'a [u8]) -> GdefTable<'a> {
+    GdefTable::new(FontTable {
+        bytes: gdef,
+    }).unwrap()
+}
+
+#[test]
+fn glyph_classes() {
+    let gdef = gdef(false);
+    let gdef = gdef_table(&gdef);
+    assert_eq!(gdef.glyph_class(A).unwrap(), Some(GlyphClass::Base));
+    assert_eq!(gdef.glyph_class(FI).unwrap(), Some(GlyphClass::Ligature));
+    assert_eq!(gdef.glyph_class(ACUTE).unwrap(), Some(GlyphClass::Mark));
+    assert_eq!(gdef.glyph_class(3).unwrap(), None);
+    assert_eq!(gdef.glyph_class(100).unwrap(), None);
+}
+
+#[test]
+fn mark_attachment_classes_and_mark_glyph_sets() {
+    let gdef = gdef(true);
+    let gdef = gdef_table(&gdef);
+    assert_eq!(gdef.mark_attachment_class(ACUTE).unwrap(), 1);
+    assert_eq!(gdef.mark_attachment_class(CEDILLA).unwrap(), 2);
+    assert_eq!(gdef.mark_attachment_class(A).unwrap(), 0);
+
+    assert!(gdef.mark_glyph_set_contains(0, ACUTE).unwrap());
+    assert!(!gdef.mark_glyph_set_contains(0, DOT).unwrap());
+    assert!(gdef.mark_glyph_set_contains(1, DOT).unwrap());
+    assert!(!gdef.mark_glyph_set_contains(2, DOT).unwrap());
+
+    // Version 1.0 tables have no mark glyph sets.
+    let gdef = self::gdef(false);
+    assert!(!gdef_table(&gdef).mark_glyph_set_contains(0, ACUTE).unwrap());
+}
+
+#[test]
+fn ligatures_form_across_ignored_marks() {
+    let ligature = |lookup_flag| {
+        let subtable = gsub::ligatures(&[(F, &[(FI, &[I])])]);
+        gsub::gsub(&[(tag(b"liga"), &[0])],
+                   vec![lookup(LIGATURE, lookup_flag, None, vec![subtable])])
+    };
+    let gdef = gdef(false);
+
+    // The mark ends up after the ligature.
+    assert_eq!(substitute(&ligature(IGNORE_MARKS), &[F, ACUTE, I], Some(&gdef)), vec![FI, ACUTE]);
+    assert_eq!(substitute(&ligature(0), &[F, ACUTE, I], Some(&gdef)), vec![F, ACUTE, I]);
+
+    // Without a `GDEF` table, there's no telling which glyphs are marks.
+    assert_eq!(substitute(&ligature(IGNORE_MARKS), &[F, ACUTE, I], None), vec![F, ACUTE, I]);
+}
+
+#[test]
+fn lookups_leave_ignored_glyphs_alone() {
+    let subtable = table(vec![Field::U16(1), Field::Offset(coverage(&[A, ACUTE])), Field::U16(1)]);
+    let gsub = gsub::gsub(&[(tag(b"ccmp"), &[0])],
+                          vec![lookup(SINGLE, IGNORE_MARKS, None, vec![subtable])]);
+    assert_eq!(substitute(&gsub, &[A, ACUTE], Some(&gdef(false))), vec![B, ACUTE]);
+}
+
+#[test]
+fn marks_skip_over_gdef_marks_to_reach_the_base() {
+    // The dot is a mark according to the `GDEF` table, but the subtable doesn't cover it.
+    let mark_to_base = gpos::mark_to_base_and_ligature().remove(0);
+    let gpos = gpos::gpos(&[(tag(b"mark"), &[0])],
+                          vec![lookup(MARK_TO_BASE, 0, None, vec![mark_to_base])]);
+
+    assert_eq!(attachments(&gpos, &[A, DOT, ACUTE], Some(&gdef(false))),
+               vec![None, None, Some((0, 150, 200))]);
+    assert_eq!(attachments(&gpos, &[A, DOT, ACUTE], None), vec![None, None, None]);
+}
+
+#[test]
+fn mark_to_mark_lookups_skip_marks_of_other_classes_and_sets() {
+    // Acute accents attach to the base, unless a mark lookup that sees a preceding acute accent
+    // stacks them.
+    let mark_gpos = |lookup_flag, mark_filtering_set| {
+        let mark_to_base = gpos::mark_to_base_and_ligature().remove(0);
+        gpos::gpos(&[(tag(b"mark"), &[0]), (tag(b"mkmk"), &[1])],
+                   vec![
+                       lookup(MARK_TO_BASE, 0, None, vec![mark_to_base]),
+                       lookup(MARK_TO_MARK, lookup_flag, mark_filtering_set, vec![
+                           gpos::mark_to_mark(),
+                       ]),
+                   ])
+    };
+    let (gdef, glyph_ids) = (gdef(true), [A, ACUTE, CEDILLA, ACUTE]);
+    let stacked = vec![None, Some((0, 150, 200)), Some((0, 170, 0)), Some((1, 0, 300))];
+    let unstacked = vec![None, Some((0, 150, 200)), Some((0, 170, 0)), Some((0, 150, 200))];
+
+    // Without any flags, the cedilla is in the way.
+    assert_eq!(attachments(&mark_gpos(0, None), &glyph_ids, Some(&gdef)), unstacked);
+
+    // Marks of other attachment classes, such as the cedilla, can be skipped.
+    assert_eq!(attachments(&mark_gpos(1 << 8, None), &glyph_ids, Some(&gdef)), stacked);
+
+    // So can marks outside a mark glyph set. If the set leaves out the acute accent, the lookup
+    // ignores it altogether.
+    assert_eq!(attachments(&mark_gpos(USE_MARK_FILTERING_SET, Some(0)), &glyph_ids, Some(&gdef)),
+               stacked);
+    assert_eq!(attachments(&mark_gpos(USE_MARK_FILTERING_SET, Some(1)), &glyph_ids, Some(&gdef)),
+               unstacked);
+}
@@ -0,0 +1,321 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::FontTable;
+use tables::gdef::GdefTable;
+use tables::gpos::GposTable;
+use tests::layout::{self, Field, coverage, lookup, table, tag, u16s};
+
+const PAIR_ADJUSTMENT: u16 = 2;
+const MARK_TO_BASE: u16 = 4;
+const MARK_TO_LIGATURE: u16 = 5;
+const MARK_TO_MARK: u16 = 6;
+const EXTENSION: u16 = 9;
+
+// Value formats.
+const X_PLACEMENT: u16 = 1 << 0;
+const X_ADVANCE: u16 = 1 << 2;
+const Y_ADVANCE_DEVICE: u16 = 1 << 7;
+
+// Glyph IDs.
+const A: u16 = 1;
+const B: u16 = 2;
+const T: u16 = 3;
+const V: u16 = 4;
+const W: u16 = 5;
+const O: u16 = 6;
+const FI: u16 = 7;
+const ACUTE: u16 = 10;
+const CEDILLA: u16 = 11;
+const BEH: u16 = 20;
+const FATHA: u16 = 21;
+const ALEF: u16 = 30;
+const QAMATS: u16 = 31;
+
+// Mark classes.
+const ABOVE: u16 = 0;
+const BELOW: u16 = 1;
+
+// Builds a `GPOS` table whose `latn` script enables the given features.
+pub fn gpos(features: &[(u32, &[u16])], lookups: Vec<Vec<u8>>) -> Vec<u8> {
+    let feature_indices: Vec<_> = (0..features.len() as u16).collect();
+    layout::layout_table(&[(tag(b"latn"), &feature_indices)], features, lookups)
+}
+
+fn gpos_table<'a>(gpos: &'a [u8]) -> GposTable<'a> {
+    GposTable::new(FontTable {
+        bytes: gpos,
+    }).unwrap()
+}
+
+// Returns the index of the glyph that each glyph attaches to and its offset from that glyph.
+pub fn attachments(gpos: &[u8], glyph_ids: &[u16], gdef: Option<&[u8]>)
+                   -> Vec<Option<(usize, i16, i16)>> {
+    let gdef = gdef.map(|gdef| {
+        GdefTable::new(FontTable {
+            bytes: gdef,
+        }).unwrap()
+    });
+    let attachments = gpos_table(gpos).mark_attachments(glyph_ids, gdef).unwrap();
+    attachments.iter().map(|attachment| {
+        attachment.map(|attachment| {
+            (attachment.base_index, attachment.x_offset, attachment.y_offset)
+        })
+    }).collect()
+}
+
+// A format 1 pair adjustment subtable with the given advance adjustments for the first glyph of
+// each pair. The first glyph also gets a placement and the second one a placement and a device
+// table, which should be skipped over.
+fn pairs(pair_sets: &[(u16, &[(u16, i16)])]) -> Vec<u8> {
+    let first_glyph_ids: Vec<_> = pair_sets.iter().map(|&(glyph_id, _)| glyph_id).collect();
+    let mut fields = vec![
+        Field::U16(1),
+        Field::Offset(coverage(&first_glyph_ids)),
+        Field::U16(X_PLACEMENT | X_ADVANCE),
+        Field::U16(X_PLACEMENT | Y_ADVANCE_DEVICE),
+        Field::U16(pair_sets.len() as u16),
+    ];
+    for &(_, pair_set) in pair_sets {
+        let mut pair_set_fields = u16s(&[pair_set.len() as u16]);
+        for &(second_glyph_id, x_advance) in pair_set {
+            pair_set_fields.extend(u16s(&[second_glyph_id, 7, x_advance as u16, 9, 0]))
+        }
+        fields.push(Field::Offset(table(pair_set_fields)))
+    }
+    table(fields)
+}
+
+// A format 2 pair adjustment subtable with a matrix of advance adjustments for the first glyph,
+// indexed by the classes of both glyphs.
+fn class_pairs(covered_glyph_ids: &[u16],
+               class_def_1: Vec<u8>,
+               class_def_2: Vec<u8>,
+               x_advances: &[&[i16]])
+               -> Vec<u8> {
+    let mut fields = vec![
+        Field::U16(2),
+        Field::Offset(coverage(covered_glyph_ids)),
+        Field::U16(X_ADVANCE),
+        Field::U16(X_PLACEMENT),
+        Field::Offset(class_def_1),
+        Field::Offset(class_def_2),
+        Field::U16(x_advances.len() as u16),
+        Field::U16(x_advances[0].len() as u16),
+    ];
+    for row in x_advances {
+        for &x_advance in *row {
+            fields.extend(u16s(&[x_advance as u16, 0]))
+        }
+    }
+    table(fields)
+}
+
+fn kern(subtables: Vec<Vec<u8>>) -> Vec<u8> {
+    gpos(&[(tag(b"kern"), &[0])], vec![lookup(PAIR_ADJUSTMENT, 0, None, subtables)])
+}
+
+#[test]
+fn pair_adjustment_by_glyph_pair() {
+    let gpos = kern(vec![pairs(&[(A, &[(T, -30), (V, -80), (W, -40)]), (T, &[(O, -60)])])]);
+    let gpos = gpos_table(&gpos);
+
+    assert!(gpos.has_kerning());
+    assert_eq!(gpos.kerning_for_glyph_pair(A, V).unwrap(), -80);
+    assert_eq!(gpos.kerning_for_glyph_pair(A, T).unwrap(), -30);
+    assert_eq!(gpos.kerning_for_glyph_pair(A, W).unwrap(), -40);
+    assert_eq!(gpos.kerning_for_glyph_pair(T, O).unwrap(), -60);
+    assert_eq!(gpos.kerning_for_glyph_pair(A, O).unwrap(), 0);
+    assert_eq!(gpos.kerning_for_glyph_pair(V, A).unwrap(), 0);
+}
+
+#[test]
+fn pair_adjustment_by_class() {
+    // For the first glyph, `a` and `b` are in class 1. For the second, `v` and `w` are in class 1
+    // and `o` is in class 2. Other glyphs are in class 0.
+    let subtable = class_pairs(&[A, B, T],
+                               layout::class_def(A, &[1, 1]),
+                               layout::class_def_ranges(&[(V, W, 1), (O, O, 2)]),
+                               &[&[0, -10, -20], &[-5, -70, 0]]);
+    let gpos = kern(vec![subtable]);
+    let gpos = gpos_table(&gpos);
+
+    assert_eq!(gpos.kerning_for_glyph_pair(A, V).unwrap(), -70);
+    assert_eq!(gpos.kerning_for_glyph_pair(B, W).unwrap(), -70);
+    assert_eq!(gpos.kerning_for_glyph_pair(A, O).unwrap(), 0);
+    assert_eq!(gpos.kerning_for_glyph_pair(A, A).unwrap(), -5);
+    assert_eq!(gpos.kerning_for_glyph_pair(T, V).unwrap(), -10);
+    assert_eq!(gpos.kerning_for_glyph_pair(T, O).unwrap(), -20);
+    assert_eq!(gpos.kerning_for_glyph_pair(V, A).unwrap(), 0);
+}
+
+#[test]
+fn first_covering_subtable_wins_and_lookups_add_up() {
+    // The class subtable covers `a`, but the glyph pair subtable before it takes precedence.
+    let class_subtable = class_pairs(&[A],
+                                     layout::class_def(A, &[1]),
+                                     layout::class_def(V, &[1]),
+                                     &[&[0, 0], &[0, -100]]);
+    let gpos = gpos(&[(tag(b"kern"), &[0, 1])],
+                    vec![
+                        lookup(PAIR_ADJUSTMENT, 0, None, vec![pairs(&[(A, &[(V, -80)])]),
+                                                               class_subtable]),
+                        lookup(EXTENSION, 0, None, vec![
+                            layout::extension(PAIR_ADJUSTMENT, pairs(&[(A, &[(V, -5)])])),
+                        ]),
+                    ]);
+    assert_eq!(gpos_table(&gpos).kerning_for_glyph_pair(A, V).unwrap(), -85);
+}
+
+#[test]
+fn no_kerning_without_a_kern_feature() {
+    let gpos = gpos(&[(tag(b"dist"), &[0])],
+                    vec![lookup(PAIR_ADJUSTMENT, 0, None, vec![pairs(&[(A, &[(V, -80)])])])]);
+    let gpos = gpos_table(&gpos);
+    assert!(!gpos.has_kerning());
+    assert_eq!(gpos.kerning_for_glyph_pair(A, V).unwrap(), 0);
+}
+
+fn anchor(x: i16, y: i16) -> Vec<u8> {
+    table(u16s(&[1, x as u16, y as u16]))
+}
+
+// Builds rows of anchors for each glyph, one per mark class. `None` means there's no anchor.
+fn anchor_rows(rows: &[&[Option<(i16, i16)>]]) -> Vec<u8> {
+    let mut fields = u16s(&[rows.len() as u16]);
+    for row in rows {
+        fields.extend(row.iter().map(|anchor_position| {
+            match *anchor_position {
+                None => Field::Null,
+                Some((x, y)) => Field::Offset(anchor(x, y)),
+            }
+        }))
+    }
+    table(fields)
+}
+
+// A mark attachment subtable. Each mark has a class and an anchor, and the array of the glyphs
+// that marks attach to must be built to match.
+pub fn mark_attachment(marks: &[(u16, u16, (i16, i16))],
+                       mark_class_count: u16,
+                       base_glyph_ids: &[u16],
+                       base_array: Vec<u8>)
+                       -> Vec<u8> {
+    let mark_glyph_ids: Vec<_> = marks.iter().map(|&(glyph_id, _, _)| glyph_id).collect();
+    let mut mark_array = u16s(&[marks.len() as u16]);
+    for &(_, class, (x, y)) in marks {
+        mark_array.extend(vec![Field::U16(class), Field::Offset(anchor(x, y))])
+    }
+
+    table(vec![
+        Field::U16(1),
+        Field::Offset(coverage(&mark_glyph_ids)),
+        Field::Offset(coverage(base_glyph_ids)),
+        Field::U16(mark_class_count),
+        Field::Offset(table(mark_array)),
+        Field::Offset(base_array),
+    ])
+}
+
+// The acute accent attaches above and the cedilla below.
+static MARKS: [(u16, u16, (i16, i16)); 2] = [
+    (ACUTE, ABOVE, (100, 500)),
+    (CEDILLA, BELOW, (80, 0)),
+];
+
+// Attaches the marks to `a` and to the second component of `fi`.
+pub fn mark_to_base_and_ligature() -> Vec<Vec<u8>> {
+    let ligature_attach = anchor_rows(&[&[Some((150, 700)), None], &[Some((400, 650)), None]]);
+    let ligature_array = table(vec![Field::U16(1), Field::Offset(ligature_attach)]);
+    vec![
+        mark_attachment(&MARKS, 2, &[A], anchor_rows(&[&[Some((250, 700)), Some((250, 0))]])),
+        mark_attachment(&MARKS, 2, &[FI], ligature_array),
+    ]
+}
+
+// Lets the acute accent stack on top of another acute accent.
+pub fn mark_to_mark() -> Vec<u8> {
+    mark_attachment(&[(ACUTE, ABOVE, (100, 500))],
+                    1,
+                    &[ACUTE],
+                    anchor_rows(&[&[Some((100, 800))]]))
+}
+
+fn mark_gpos() -> Vec<u8> {
+    let subtables = mark_to_base_and_ligature();
+    gpos(&[(tag(b"mark"), &[0, 1]), (tag(b"mkmk"), &[2])],
+         vec![
+             lookup(MARK_TO_BASE, 0, None, vec![subtables[0].clone()]),
+             lookup(MARK_TO_LIGATURE, 0, None, vec![subtables[1].clone()]),
+             lookup(MARK_TO_MARK, 0, None, vec![mark_to_mark()]),
+         ])
+}
+
+#[test]
+fn marks_attach_to_base_glyphs() {
+    let gpos = mark_gpos();
+    assert_eq!(attachments(&gpos, &[A, ACUTE], None), vec![None, Some((0, 150, 200))]);
+    assert_eq!(attachments(&gpos, &[A, CEDILLA, B, ACUTE], None),
+               vec![None, Some((0, 170, 0)), None, None]);
+    assert_eq!(attachments(&gpos, &[ACUTE], None), vec![None]);
+}
+
+#[test]
+fn marks_skip_over_other_marks_to_reach_the_base() {
+    // Without a `GDEF` table, the glyphs that the subtable covers as marks count as marks.
+    let gpos = mark_gpos();
+    assert_eq!(attachments(&gpos, &[A, CEDILLA, CEDILLA], None),
+               vec![None, Some((0, 170, 0)), Some((0, 170, 0))]);
+}
+
+#[test]
+fn marks_attach_to_the_last_component_of_ligatures() {
+    let gpos = mark_gpos();
+    assert_eq!(attachments(&gpos, &[FI, ACUTE], None), vec![None, Some((0, 300, 150))]);
+
+    // The ligature has no anchor for marks below.
+    assert_eq!(attachments(&gpos, &[FI, CEDILLA], None), vec![None, None]);
+}
+
+#[test]
+fn marks_attach_to_preceding_marks() {
+    // The second acute accent stacks on the first rather than attaching to the base.
+    let gpos = mark_gpos();
+    assert_eq!(attachments(&gpos, &[A, ACUTE, ACUTE], None),
+               vec![None, Some((0, 150, 200)), Some((1, 0, 300))]);
+}
+
+#[test]
+fn marks_attach_in_every_script() {
+    // Latin kerning and Arabic and Hebrew mark attachment, each under its own script. The marks
+    // must attach even though `latn`, which the other features come from, has none.
+    let fatha = mark_attachment(&[(FATHA, ABOVE, (50, 400))],
+                                1,
+                                &[BEH],
+                                anchor_rows(&[&[Some((300, 600))]]));
+    let qamats = mark_attachment(&[(QAMATS, BELOW, (60, 0))],
+                                 2,
+                                 &[ALEF],
+                                 anchor_rows(&[&[None, Some((250, -50))]]));
+    let gpos = layout::layout_table(&[
+                                        (tag(b"arab"), &[1]),
+                                        (tag(b"hebr"), &[2]),
+                                        (tag(b"latn"), &[0]),
+                                    ],
+                                    &[
+                                        (tag(b"kern"), &[0]),
+                                        (tag(b"mark"), &[1]),
+                                        (tag(b"mark"), &[2]),
+                                    ],
+                                    vec![
+                                        lookup(PAIR_ADJUSTMENT, 0, None, vec![
+                                            pairs(&[(A, &[(V, -80)])]),
+                                        ]),
+                                        lookup(MARK_TO_BASE, 0, None, vec![fatha]),
+                                        lookup(MARK_TO_BASE, 0, None, vec![qamats]),
+                                    ]);
+
+    assert_eq!(attachments(&gpos, &[BEH, FATHA], None), vec![None, Some((0, 250, 200))]);
+    assert_eq!(attachments(&gpos, &[ALEF, QAMATS], None), vec![None, Some((0, 190, -50))]);
+    assert_eq!(gpos_table(&gpos).kerning_for_glyph_pair(A, V).unwrap(), -80);
+}
@@ -0,0 +1,249 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::FontTable;
+use tables::gdef::GdefTable;
+use tables::gsub::GsubTable;
+use tests::layout::{self, Field, coverage, lookup, table, tag, u16s};
+
+const SINGLE: u16 = 1;
+const MULTIPLE: u16 = 2;
+const ALTERNATE: u16 = 3;
+const LIGATURE: u16 = 4;
+const CHAINED_CONTEXT: u16 = 6;
+const EXTENSION: u16 = 7;
+
+// Glyph IDs.
+const A: u16 = 1;
+const B: u16 = 2;
+const C: u16 = 3;
+const F: u16 = 4;
+const I: u16 = 5;
+const L: u16 = 6;
+const X: u16 = 7;
+const Y: u16 = 8;
+const Z: u16 = 9;
+const FI: u16 = 10;
+const FL: u16 = 11;
+const FFI: u16 = 12;
+
+// Builds a `GSUB` table whose `latn` script enables the given features.
+pub fn gsub(features: &[(u32, &[u16])], lookups: Vec<Vec<u8>>) -> Vec<u8> {
+    let feature_indices: Vec<_> = (0..features.len() as u16).collect();
+    layout::layout_table(&[(tag(b"latn"), &feature_indices)], features, lookups)
+}
+
+// Applies the default features of the given table to the given glyphs.
+pub fn substitute(gsub: &[u8], glyph_ids: &[u16], gdef: Option<&[u8]>) -> Vec<u16> {
+    let gsub = GsubTable::new(FontTable {
+        bytes: gsub,
+    }).unwrap();
+    let gdef = gdef.map(|gdef| {
+        GdefTable::new(FontTable {
+            bytes: gdef,
+        }).unwrap()
+    });
+
+    let mut glyph_ids = glyph_ids.to_vec();
+    gsub.substitute(&mut glyph_ids, gdef).unwrap();
+    glyph_ids
+}
+
+// A format 2 single substitution subtable.
+fn single(substitutions: &[(u16, u16)]) -> Vec<u8> {
+    let glyph_ids: Vec<_> = substitutions.iter().map(|&(glyph_id, _)| glyph_id).collect();
+    let mut fields = vec![Field::U16(2), Field::Offset(coverage(&glyph_ids))];
+    fields.push(Field::U16(substitutions.len() as u16));
+    fields.extend(substitutions.iter().map(|&(_, substitute)| Field::U16(substitute)));
+    table(fields)
+}
+
+// A ligature substitution subtable. Each ligature set lists the ligatures that start with the
+// given glyph, in order of preference, along with their remaining components.
+pub fn ligatures(ligature_sets: &[(u16, &[(u16, &[u16])])]) -> Vec<u8> {
+    let first_glyph_ids: Vec<_> = ligature_sets.iter().map(|&(glyph_id, _)| glyph_id).collect();
+    let mut fields = vec![Field::U16(1), Field::Offset(coverage(&first_glyph_ids))];
+    fields.push(Field::U16(ligature_sets.len() as u16));
+    for &(_, ligature_set) in ligature_sets {
+        let mut ligature_set_fields = u16s(&[ligature_set.len() as u16]);
+        for &(ligature_glyph_id, components) in ligature_set {
+            let mut ligature = u16s(&[ligature_glyph_id, components.len() as u16 + 1]);
+            ligature.extend(u16s(components));
+            ligature_set_fields.push(Field::Offset(table(ligature)))
+        }
+        fields.push(Field::Offset(table(ligature_set_fields)))
+    }
+    table(fields)
+}
+
+// A single lookup, applied by the `liga` feature.
+fn liga(lookup_type: u16, subtable: Vec<u8>) -> Vec<u8> {
+    gsub(&[(tag(b"liga"), &[0])], vec![lookup(lookup_type, 0, None, vec![subtable])])
+}
+
+#[test]
+fn single_substitution_by_delta_and_by_glyph() {
+    let by_delta = table(vec![Field::U16(1), Field::Offset(coverage(&[A, B])), Field::U16(5)]);
+    let gsub = liga(SINGLE, by_delta);
+    assert_eq!(substitute(&gsub, &[A, B, C], None), vec![A + 5, B + 5, C]);
+
+    let gsub = liga(SINGLE, single(&[(A, Z), (C, X)]));
+    assert_eq!(substitute(&gsub, &[A, B, C], None), vec![Z, B, X]);
+}
+
+#[test]
+fn multiple_substitution() {
+    let sequence = table(u16s(&[3, F, F, I]));
+    let subtable = table(vec![
+        Field::U16(1),
+        Field::Offset(coverage(&[FFI])),
+        Field::U16(1),
+        Field::Offset(sequence),
+    ]);
+    let gsub = liga(MULTIPLE, subtable);
+    assert_eq!(substitute(&gsub, &[A, FFI, B], None), vec![A, F, F, I, B]);
+}
+
+#[test]
+fn alternate_substitution_picks_the_first_alternate() {
+    let alternate_set = table(u16s(&[2, Y, Z]));
+    let subtable = table(vec![
+        Field::U16(1),
+        Field::Offset(coverage(&[X])),
+        Field::U16(1),
+        Field::Offset(alternate_set),
+    ]);
+    let gsub = liga(ALTERNATE, subtable);
+    assert_eq!(substitute(&gsub, &[X, A], None), vec![Y, A]);
+}
+
+#[test]
+fn ligature_substitution_prefers_earlier_ligatures() {
+    let subtable = ligatures(&[(F, &[(FFI, &[F, I]), (FI, &[I]), (FL, &[L])])]);
+    let gsub = liga(LIGATURE, subtable);
+    assert_eq!(substitute(&gsub, &[F, F, I], None), vec![FFI]);
+    assert_eq!(substitute(&gsub, &[A, F, I, F, L], None), vec![A, FI, FL]);
+    assert_eq!(substitute(&gsub, &[F, F, L], None), vec![F, FL]);
+    assert_eq!(substitute(&gsub, &[F, A, I], None), vec![F, A, I]);
+}
+
+#[test]
+fn extension_lookups_wrap_other_lookups() {
+    let gsub = liga(EXTENSION, layout::extension(SINGLE, single(&[(A, B)])));
+    assert_eq!(substitute(&gsub, &[A, C], None), vec![B, C]);
+}
+
+// A `calt` lookup with a single chained context subtable, followed by lookups that only it
+// invokes.
+fn calt(subtable: Vec<u8>, nested_lookups: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut lookups = vec![lookup(CHAINED_CONTEXT, 0, None, vec![subtable])];
+    lookups.extend(nested_lookups);
+    gsub(&[(tag(b"calt"), &[0])], lookups)
+}
+
+#[test]
+fn chained_context_substitution_by_glyph() {
+    // Replaces `y` with `z` after `a x` and before `b`.
+    let rule = table(u16s(&[1, A, 2, Y, 1, B, 1, 1, 1]));
+    let rule_set = table(vec![Field::U16(1), Field::Offset(rule)]);
+    let subtable = table(vec![
+        Field::U16(1),
+        Field::Offset(coverage(&[X])),
+        Field::U16(1),
+        Field::Offset(rule_set),
+    ]);
+    let gsub = calt(subtable, vec![lookup(SINGLE, 0, None, vec![single(&[(Y, Z)])])]);
+
+    assert_eq!(substitute(&gsub, &[A, X, Y, B], None), vec![A, X, Z, B]);
+    assert_eq!(substitute(&gsub, &[C, X, Y, B], None), vec![C, X, Y, B]);
+    assert_eq!(substitute(&gsub, &[A, X, Y, C], None), vec![A, X, Y, C]);
+    assert_eq!(substitute(&gsub, &[A, X, Y], None), vec![A, X, Y]);
+}
+
+#[test]
+fn chained_context_substitution_by_class() {
+    // Replaces `a`, `b`, or `c` (input class 1) with `z` before `x` or `y` (lookahead class 2).
+    // Glyphs of input class 0 have no rules.
+    let rule = table(u16s(&[0, 1, 1, 2, 1, 0, 1]));
+    let rule_set = table(vec![Field::U16(1), Field::Offset(rule)]);
+    let subtable = table(vec![
+        Field::U16(2),
+        Field::Offset(coverage(&[A, B, C, X])),
+        Field::Offset(layout::class_def(A, &[])),
+        Field::Offset(layout::class_def(A, &[1, 1, 1])),
+        Field::Offset(layout::class_def_ranges(&[(X, Y, 2)])),
+        Field::U16(2),
+        Field::Null,
+        Field::Offset(rule_set),
+    ]);
+    let gsub = calt(subtable, vec![lookup(SINGLE, 0, None, vec![single(&[(A, Z), (B, Z)])])]);
+
+    assert_eq!(substitute(&gsub, &[A, Y, B, X, C, X], None), vec![Z, Y, Z, X, C, X]);
+    assert_eq!(substitute(&gsub, &[A, A, X, X], None), vec![A, Z, X, X]);
+}
+
+#[test]
+fn chained_context_substitution_by_coverage_tracks_changes_in_length() {
+    // After `a`, splits `x` into `f i` and then replaces the `y` after it with `z`.
+    let subtable = table(vec![
+        Field::U16(3),
+        Field::U16(1),
+        Field::Offset(coverage(&[A])),
+        Field::U16(2),
+        Field::Offset(coverage(&[X])),
+        Field::Offset(coverage(&[Y])),
+        Field::U16(0),
+        Field::U16(2),
+        Field::U16(0),
+        Field::U16(1),
+        Field::U16(1),
+        Field::U16(2),
+    ]);
+    let split = table(vec![
+        Field::U16(1),
+        Field::Offset(coverage(&[X])),
+        Field::U16(1),
+        Field::Offset(table(u16s(&[2, F, I]))),
+    ]);
+    let gsub = calt(subtable, vec![
+        lookup(MULTIPLE, 0, None, vec![split]),
+        lookup(SINGLE, 0, None, vec![single(&[(Y, Z)])]),
+    ]);
+
+    assert_eq!(substitute(&gsub, &[A, X, Y, B], None), vec![A, F, I, Z, B]);
+    assert_eq!(substitute(&gsub, &[B, X, Y], None), vec![B, X, Y]);
+}
+
+#[test]
+fn only_default_features_of_the_latn_script_apply() {
+    // `DFLT` and `latn` enable different `liga` features, and `latn` also enables `smcp`, which
+    // isn't on by default.
+    let gsub = layout::layout_table(&[(tag(b"DFLT"), &[0]), (tag(b"latn"), &[1, 2])],
+                                    &[
+                                        (tag(b"liga"), &[0]),
+                                        (tag(b"liga"), &[1]),
+                                        (tag(b"smcp"), &[2]),
+                                    ],
+                                    vec![
+                                        lookup(SINGLE, 0, None, vec![single(&[(A, X)])]),
+                                        lookup(SINGLE, 0, None, vec![single(&[(A, Y)])]),
+                                        lookup(SINGLE, 0, None, vec![single(&[(B, Z)])]),
+                                    ]);
+    assert_eq!(substitute(&gsub, &[A, B], None), vec![Y, B]);
+
+    let gsub = layout::layout_table(&[(tag(b"DFLT"), &[0])],
+                                    &[(tag(b"liga"), &[0])],
+                                    vec![lookup(SINGLE, 0, None, vec![single(&[(A, X)])])]);
+    assert_eq!(substitute(&gsub, &[A, B], None), vec![X, B]);
+}
+
+#[test]
+fn lookups_apply_in_lookup_list_order() {
+    // `ccmp` comes first among the default features, but its lookup comes last in the list.
+    let gsub = gsub(&[(tag(b"ccmp"), &[1]), (tag(b"liga"), &[0])],
+                    vec![
+                        lookup(SINGLE, 0, None, vec![single(&[(A, B)])]),
+                        lookup(SINGLE, 0, None, vec![single(&[(B, C)])]),
+                    ]);
+    assert_eq!(substitute(&gsub, &[A], None), vec![C]);
+}
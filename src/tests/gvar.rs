@@ -0,0 +1,192 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, WriteBytesExt};
+use euclid::Point2D;
+use tables::gvar;
+
+const POINTS_ARE_WORDS: u8 = 0x80;
+const DELTAS_ARE_ZERO: u8 = 0x80;
+const DELTAS_ARE_WORDS: u8 = 0x40;
+
+// Packs point numbers the way a font compiler would, using bytes for small differences.
+fn pack_point_numbers(point_numbers: &[u16]) -> Vec<u8> {
+    let mut bytes = vec![];
+    if point_numbers.len() < 0x80 {
+        bytes.push(point_numbers.len() as u8)
+    } else {
+        bytes.write_u16::<BigEndian>(0x8000 | point_numbers.len() as u16).unwrap();
+    }
+
+    let differences: Vec<_> = point_numbers.iter().enumerate().map(|(index, &point_number)| {
+        if index == 0 { point_number } else { point_number - point_numbers[index - 1] }
+    }).collect();
+
+    let mut rest = &differences[..];
+    while !rest.is_empty() {
+        let are_words = rest[0] > 0xff;
+        let run_count = rest.iter()
+                            .take(0x80)
+                            .take_while(|&&difference| (difference > 0xff) == are_words)
+                            .count();
+        if are_words {
+            bytes.push(POINTS_ARE_WORDS | (run_count - 1) as u8);
+            for &difference in &rest[..run_count] {
+                bytes.write_u16::<BigEndian>(difference).unwrap();
+            }
+        } else {
+            bytes.push((run_count - 1) as u8);
+            bytes.extend(rest[..run_count].iter().map(|&difference| difference as u8));
+        }
+        rest = &rest[run_count..]
+    }
+    bytes
+}
+
+fn delta_kind(delta: i16) -> u8 {
+    if delta == 0 {
+        DELTAS_ARE_ZERO
+    } else if delta < -0x80 || delta > 0x7f {
+        DELTAS_ARE_WORDS
+    } else {
+        0
+    }
+}
+
+// Packs deltas the way a font compiler would, using the smallest kind of run for each.
+pub fn pack_deltas(deltas: &[i16]) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut rest = deltas;
+    while !rest.is_empty() {
+        let kind = delta_kind(rest[0]);
+        let run_count = rest.iter()
+                            .take(0x40)
+                            .take_while(|&&delta| delta_kind(delta) == kind)
+                            .count();
+        bytes.push(kind | (run_count - 1) as u8);
+        for &delta in &rest[..run_count] {
+            match kind {
+                DELTAS_ARE_ZERO => {}
+                DELTAS_ARE_WORDS => bytes.write_i16::<BigEndian>(delta).unwrap(),
+                _ => bytes.push(delta as i8 as u8),
+            }
+        }
+        rest = &rest[run_count..]
+    }
+    bytes
+}
+
+#[test]
+fn packed_point_numbers_of_all_points() {
+    let bytes = [0, 0xaa];
+    let mut reader = &bytes[..];
+    assert_eq!(gvar::read_packed_point_numbers(&mut reader).unwrap(), None);
+    assert_eq!(reader, &[0xaa]);
+}
+
+#[test]
+fn packed_point_numbers_in_byte_and_word_runs() {
+    // A run of two byte differences and a run of two word differences.
+    let bytes = [4, 0x01, 2, 3, 0x81, 0x01, 0x00, 0x00, 0x01];
+    let mut reader = &bytes[..];
+    assert_eq!(gvar::read_packed_point_numbers(&mut reader).unwrap(),
+               Some(vec![2, 5, 261, 262]));
+    assert!(reader.is_empty());
+
+    // The count itself can take two bytes.
+    let bytes = [0x80, 0x02, 0x81, 0x01, 0x00, 0x00, 0x05];
+    let mut reader = &bytes[..];
+    assert_eq!(gvar::read_packed_point_numbers(&mut reader).unwrap(), Some(vec![256, 261]));
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn packed_deltas_in_byte_word_and_zero_runs() {
+    // Three byte deltas, two word deltas, and four zeroes, followed by unrelated data.
+    let bytes = [0x02, 1, 0xff, 0x7f, 0x41, 0x01, 0x00, 0xff, 0x00, 0x83, 0xaa];
+    let mut reader = &bytes[..];
+    assert_eq!(gvar::read_packed_deltas(&mut reader, 9).unwrap(),
+               vec![1, -1, 127, 256, -256, 0, 0, 0, 0]);
+    assert_eq!(reader, &[0xaa]);
+
+    // Runs can hold more deltas than asked for.
+    let bytes = [0x83];
+    let mut reader = &bytes[..];
+    assert_eq!(gvar::read_packed_deltas(&mut reader, 2).unwrap(), vec![0, 0]);
+}
+
+#[test]
+fn interpolate_delta_between_neighbors() {
+    assert_eq!(gvar::interpolate_delta(5, 0, 10, 0.0, 10.0), 5.0);
+    assert_eq!(gvar::interpolate_delta(5, 10, 0, 10.0, 0.0), 5.0);
+
+    // Points beyond the neighbors move with the nearer one.
+    assert_eq!(gvar::interpolate_delta(-5, 0, 10, 2.0, 10.0), 2.0);
+    assert_eq!(gvar::interpolate_delta(15, 10, 0, 10.0, 2.0), 10.0);
+}
+
+#[test]
+fn interpolate_delta_with_neighbors_at_equal_coordinates() {
+    assert_eq!(gvar::interpolate_delta(5, 3, 3, 4.0, 4.0), 4.0);
+    assert_eq!(gvar::interpolate_delta(5, 3, 3, 4.0, 6.0), 0.0);
+}
+
+#[test]
+fn interpolate_untouched_points_of_contour_with_one_touched_point() {
+    // A square, whose points all move with the one touched corner, and a triangle with no
+    // touched points, which stays put.
+    let points = [
+        Point2D::new(0, 0), Point2D::new(10, 0), Point2D::new(10, 10), Point2D::new(0, 10),
+        Point2D::new(20, 0), Point2D::new(30, 0), Point2D::new(25, 10),
+    ];
+    let mut deltas = [Point2D::new(0.0, 0.0); 7];
+    deltas[2] = Point2D::new(3.0, 4.0);
+    let touched = [false, false, true, false, false, false, false];
+
+    gvar::interpolate_untouched_points(&points, &[3, 6], &mut deltas, &touched);
+    for delta in &deltas[0..4] {
+        assert_eq!(*delta, Point2D::new(3.0, 4.0));
+    }
+    for delta in &deltas[4..7] {
+        assert_eq!(*delta, Point2D::new(0.0, 0.0));
+    }
+}
+
+#[test]
+fn interpolate_untouched_points_between_touched_points() {
+    let points = [Point2D::new(0, 0), Point2D::new(5, 0), Point2D::new(10, 0), Point2D::new(5, 5)];
+    let mut deltas = [Point2D::new(0.0, 0.0); 4];
+    deltas[2] = Point2D::new(10.0, 2.0);
+    let touched = [true, false, true, false];
+
+    gvar::interpolate_untouched_points(&points, &[3], &mut deltas, &touched);
+
+    // Both untouched points lie halfway between the touched ones horizontally. Vertically, the
+    // touched points are level but move differently, so the untouched points don't move.
+    assert_eq!(deltas[1], Point2D::new(5.0, 0.0));
+    assert_eq!(deltas[3], Point2D::new(5.0, 0.0));
+    assert_eq!(deltas[0], Point2D::new(0.0, 0.0));
+    assert_eq!(deltas[2], Point2D::new(10.0, 2.0));
+}
+
+quickcheck! {
+    fn packed_point_numbers_round_trip(point_numbers: Vec<u16>) -> bool {
+        let mut point_numbers = point_numbers;
+        point_numbers.sort();
+        point_numbers.dedup();
+        if point_numbers.is_empty() {
+            return true
+        }
+
+        let bytes = pack_point_numbers(&point_numbers);
+        let mut reader = &bytes[..];
+        gvar::read_packed_point_numbers(&mut reader).unwrap() == Some(point_numbers) &&
+            reader.is_empty()
+    }
+
+    fn packed_deltas_round_trip(deltas: Vec<i16>) -> bool {
+        let bytes = pack_deltas(&deltas);
+        let mut reader = &bytes[..];
+        gvar::read_packed_deltas(&mut reader, deltas.len()).unwrap() == deltas && reader.is_empty()
+    }
+}
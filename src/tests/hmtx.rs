@@ -0,0 +1,62 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, WriteBytesExt};
+use font::FontTable;
+use tables::hhea::HheaTable;
+use tables::hmtx::HmtxTable;
+
+fn hhea(number_of_h_metrics: u16) -> HheaTable {
+    HheaTable {
+        ascender: 800,
+        descender: -200,
+        line_gap: 0,
+        caret_slope_rise: 1,
+        caret_slope_run: 0,
+        caret_offset: 0,
+        number_of_h_metrics: number_of_h_metrics,
+    }
+}
+
+// Two long metrics followed by the side bearings of two more glyphs, which share the last
+// advance width.
+fn hmtx() -> Vec<u8> {
+    let mut bytes = vec![];
+    for &(advance_width, lsb) in &[(500, 10), (600, 20)] {
+        bytes.write_u16::<BigEndian>(advance_width).unwrap();
+        bytes.write_i16::<BigEndian>(lsb).unwrap();
+    }
+    for &lsb in &[30, -40] {
+        bytes.write_i16::<BigEndian>(lsb).unwrap();
+    }
+    bytes
+}
+
+#[test]
+fn long_metrics() {
+    let bytes = hmtx();
+    let hmtx = HmtxTable::new(FontTable {
+        bytes: &bytes,
+    });
+    let hhea = hhea(2);
+
+    let metrics = hmtx.metrics_for_glyph(&hhea, 0).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (500, 10));
+    let metrics = hmtx.metrics_for_glyph(&hhea, 1).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (600, 20));
+}
+
+#[test]
+fn trailing_side_bearings() {
+    let bytes = hmtx();
+    let hmtx = HmtxTable::new(FontTable {
+        bytes: &bytes,
+    });
+    let hhea = hhea(2);
+
+    let metrics = hmtx.metrics_for_glyph(&hhea, 2).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (600, 30));
+    let metrics = hmtx.metrics_for_glyph(&hhea, 3).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (600, -40));
+    assert!(hmtx.metrics_for_glyph(&hhea, 4).is_err());
+}
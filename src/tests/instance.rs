@@ -0,0 +1,231 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use containers::otf;
+use euclid::Point2D;
+use font::{Font, FontTable};
+use tables::head::HeadTable;
+use tables::{cmap, fvar, glyf, gvar, head, hhea, hmtx, hvar, loca, maxp, os_2};
+use tests::gvar::pack_deltas;
+use tests::layout::{Field, table};
+
+const TRUETYPE: u32 = 0x10000;
+
+// Builds a variable TrueType font with a `wght` axis from 100 to 900 that defaults to 400. Glyph
+// 0 is empty, and glyph 1 is a box from (100, 0) to (500, 700) with an advance width of 600.
+//
+// At the heaviest instance, the box's left side moves right by 20, its right side by 100, and its
+// top up by 50. The advance width grows by 100, according to both `gvar` and `HVAR`.
+fn variable_font() -> Vec<u8> {
+    let mut head = vec![];
+    head.write_u16::<BigEndian>(1).unwrap();
+    head.write_u16::<BigEndian>(0).unwrap();
+    head.write_u32::<BigEndian>(0x10000).unwrap();
+    head.write_u32::<BigEndian>(0).unwrap();
+    head.write_u32::<BigEndian>(0x5f0f3cf5).unwrap();
+    head.write_u16::<BigEndian>(0).unwrap();
+    head.write_u16::<BigEndian>(1000).unwrap();
+    head.write_i64::<BigEndian>(0).unwrap();
+    head.write_i64::<BigEndian>(0).unwrap();
+    for &bound in &[100, 0, 500, 700] {
+        head.write_i16::<BigEndian>(bound).unwrap();
+    }
+    // Use the short version of `loca`, so that we can see the instance switch to the long one.
+    for &field in &[0, 0, 2, 0, 0] {
+        head.write_i16::<BigEndian>(field).unwrap();
+    }
+
+    let mut hhea = vec![];
+    hhea.write_u16::<BigEndian>(1).unwrap();
+    hhea.write_u16::<BigEndian>(0).unwrap();
+    for &field in &[800, -200, 0, 600, 100, 100, 500, 1, 0, 0, 0, 0, 0, 0, 0] {
+        hhea.write_i16::<BigEndian>(field).unwrap();
+    }
+    hhea.write_u16::<BigEndian>(2).unwrap();
+
+    let mut hmtx = vec![];
+    for &(advance_width, lsb) in &[(500, 0), (600, 100)] {
+        hmtx.write_u16::<BigEndian>(advance_width).unwrap();
+        hmtx.write_i16::<BigEndian>(lsb).unwrap();
+    }
+
+    let mut maxp = vec![];
+    maxp.write_u32::<BigEndian>(0x10000).unwrap();
+    maxp.write_u16::<BigEndian>(2).unwrap();
+    maxp.resize(32, 0);
+
+    // A version 0 `OS/2` table of regular weight and normal width.
+    let mut os_2 = vec![];
+    for &field in &[0, 0, 400, 5] {
+        os_2.write_u16::<BigEndian>(field).unwrap();
+    }
+    os_2.resize(78, 0);
+
+    // One contour with a point in each corner, all on the curve and with word coordinates.
+    let mut glyf = vec![];
+    for &field in &[1, 100, 0, 500, 700, 3, 0] {
+        glyf.write_i16::<BigEndian>(field).unwrap();
+    }
+    glyf.extend_from_slice(&[1; 4]);
+    for &delta in &[100, 400, 0, -400] {
+        glyf.write_i16::<BigEndian>(delta).unwrap();
+    }
+    for &delta in &[0, 0, 700, 0] {
+        glyf.write_i16::<BigEndian>(delta).unwrap();
+    }
+    glyf.resize(36, 0);
+    let mut loca = vec![];
+    for &offset in &[0, 0, glyf.len() as u16 / 2] {
+        loca.write_u16::<BigEndian>(offset).unwrap();
+    }
+
+    let mut fvar = vec![];
+    for &field in &[1, 0, 16, 2, 1, 20, 0, 8] {
+        fvar.write_u16::<BigEndian>(field).unwrap();
+    }
+    fvar.write_u32::<BigEndian>(fvar::WEIGHT).unwrap();
+    for &value in &[100, 400, 900] {
+        fvar.write_i32::<BigEndian>(value << 16).unwrap();
+    }
+    fvar.write_u16::<BigEndian>(0).unwrap();
+    fvar.write_u16::<BigEndian>(256).unwrap();
+
+    // A single tuple variation peaking at the heaviest instance, with deltas for all four points
+    // of glyph 1 and its phantom points.
+    let deltas = pack_deltas(&[20, 100, 100, 20, 0, 100, 0, 0, 0, 0, 50, 50, 0, 0, 0, 0]);
+    let mut glyph_variation_data = vec![];
+    for &field in &[1, 10, deltas.len() as u16, 0x8000, 0x4000] {
+        glyph_variation_data.write_u16::<BigEndian>(field).unwrap();
+    }
+    glyph_variation_data.extend_from_slice(&deltas);
+    let mut gvar = vec![];
+    for &field in &[1, 0, 1, 0] {
+        gvar.write_u16::<BigEndian>(field).unwrap();
+    }
+    gvar.write_u32::<BigEndian>(32).unwrap();
+    gvar.write_u16::<BigEndian>(2).unwrap();
+    gvar.write_u16::<BigEndian>(1).unwrap();
+    for &offset in &[32, 0, 0, glyph_variation_data.len() as u32] {
+        gvar.write_u32::<BigEndian>(offset).unwrap();
+    }
+    gvar.extend_from_slice(&glyph_variation_data);
+
+    // One region peaking at the heaviest instance, and 8-bit advance width deltas for it, indexed
+    // by glyph ID.
+    let region_list = table(vec![
+        Field::U16(1),
+        Field::U16(1),
+        Field::U16(0),
+        Field::U16(0x4000),
+        Field::U16(0x4000),
+    ]);
+    let mut item_variation_data = vec![];
+    for &field in &[2, 0, 1, 0] {
+        item_variation_data.write_u16::<BigEndian>(field).unwrap();
+    }
+    item_variation_data.extend_from_slice(&[0, 100]);
+    let item_variation_store = table(vec![
+        Field::U16(1),
+        Field::Offset32(region_list),
+        Field::U16(1),
+        Field::Offset32(item_variation_data),
+    ]);
+    let hvar = table(vec![
+        Field::U16(1),
+        Field::U16(0),
+        Field::Offset32(item_variation_store),
+        Field::U32(0),
+        Field::U32(0),
+        Field::U32(0),
+    ]);
+
+    otf::write_otf(TRUETYPE, &[
+        (cmap::TAG, vec![0, 0, 0, 0]),
+        (fvar::TAG, fvar),
+        (glyf::TAG, glyf),
+        (gvar::TAG, gvar),
+        (head::TAG, head),
+        (hhea::TAG, hhea),
+        (hmtx::TAG, hmtx),
+        (hvar::TAG, hvar),
+        (loca::TAG, loca),
+        (maxp::TAG, maxp),
+        (os_2::TAG, os_2),
+    ])
+}
+
+// Finds the table with the given tag in the table directory of an OpenType font.
+fn table_data(bytes: &[u8], table_tag: u32) -> &[u8] {
+    let mut reader = &bytes[4..];
+    let num_tables = reader.read_u16::<BigEndian>().unwrap();
+    for table_index in 0..(num_tables as usize) {
+        let mut record = &bytes[12 + table_index * 16..];
+        if record.read_u32::<BigEndian>().unwrap() != table_tag {
+            continue
+        }
+        let _checksum = record.read_u32::<BigEndian>().unwrap();
+        let offset = record.read_u32::<BigEndian>().unwrap() as usize;
+        let length = record.read_u32::<BigEndian>().unwrap() as usize;
+        return &bytes[offset..offset + length]
+    }
+    panic!("no table with the tag {:08x}", table_tag)
+}
+
+fn points(font: &Font, glyph_id: u16) -> Vec<Point2D<f32>> {
+    let mut points = vec![];
+    font.for_each_point(glyph_id, |point| points.push(point.position)).unwrap();
+    points
+}
+
+#[test]
+fn instance_loads_again() {
+    let variable_font = variable_font();
+    let variable_font = Font::from_otf(&variable_font, 0).unwrap();
+
+    // Halfway to the heaviest instance, at a weight of 650.
+    let bytes = variable_font.instantiate(&[0.5]).unwrap();
+    let font = Font::from_otf(&bytes, 0).unwrap();
+
+    assert!(font.variation_axes().unwrap().is_empty());
+    assert!(points(&font, 0).is_empty());
+    // The contour ends by returning to its first point.
+    assert_eq!(points(&font, 1), vec![
+        Point2D::new(110.0, 0.0),
+        Point2D::new(550.0, 0.0),
+        Point2D::new(550.0, 725.0),
+        Point2D::new(110.0, 725.0),
+        Point2D::new(110.0, 0.0),
+    ]);
+
+    let metrics = font.metrics_for_glyph(0).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (500, 0));
+    let metrics = font.metrics_for_glyph(1).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (650, 110));
+
+    let head = HeadTable::new(FontTable {
+        bytes: table_data(&bytes, head::TAG),
+    }).unwrap();
+    let bounds = head.max_glyph_bounds;
+    assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top), (110, 0, 550, 725));
+    assert_eq!(head.index_to_loc_format, 1);
+
+    assert_eq!(font.weight_class(), 650);
+    assert_eq!(font.width_class(), 5);
+}
+
+#[test]
+fn default_instance_matches_variable_font() {
+    let variable_font = variable_font();
+    let variable_font = Font::from_otf(&variable_font, 0).unwrap();
+
+    let bytes = variable_font.instantiate(&[0.0]).unwrap();
+    let font = Font::from_otf(&bytes, 0).unwrap();
+
+    assert_eq!(points(&font, 1), points(&variable_font, 1));
+    let metrics = font.metrics_for_glyph(1).unwrap();
+    assert_eq!((metrics.advance_width, metrics.lsb), (600, 100));
+    let bounds = font.glyph_bounds(1).unwrap();
+    assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top), (100, 0, 500, 700));
+    assert_eq!(font.weight_class(), 400);
+}
@@ -0,0 +1,182 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+// Builders for the tables shared by `GSUB`, `GPOS`, and `GDEF`, and tests for them.
+
+use byteorder::{BigEndian, WriteBytesExt};
+use tables::layout;
+
+// A field of a table under construction.
+pub enum Field {
+    U16(u16),
+    U32(u32),
+    // A null offset.
+    Null,
+    // A 16-bit offset to the given table, which is placed after the fields.
+    Offset(Vec<u8>),
+    // A 32-bit offset to the given table, which is placed after the fields.
+    Offset32(Vec<u8>),
+}
+
+// Lays out the given fields, followed by the tables that they point to, in order. Offsets are
+// from the start of the table, as most offsets in the layout tables are.
+pub fn table(fields: Vec<Field>) -> Vec<u8> {
+    let header_size = fields.iter().map(|field| {
+        match *field {
+            Field::U16(_) | Field::Null | Field::Offset(_) => 2,
+            Field::U32(_) | Field::Offset32(_) => 4,
+        }
+    }).fold(0, |size, field_size| size + field_size);
+
+    let (mut bytes, mut children) = (vec![], vec![]);
+    for field in fields {
+        match field {
+            Field::U16(value) => bytes.write_u16::<BigEndian>(value).unwrap(),
+            Field::U32(value) => bytes.write_u32::<BigEndian>(value).unwrap(),
+            Field::Null => bytes.write_u16::<BigEndian>(0).unwrap(),
+            Field::Offset(child) => {
+                bytes.write_u16::<BigEndian>((header_size + children.len()) as u16).unwrap();
+                children.extend_from_slice(&child)
+            }
+            Field::Offset32(child) => {
+                bytes.write_u32::<BigEndian>((header_size + children.len()) as u32).unwrap();
+                children.extend_from_slice(&child)
+            }
+        }
+    }
+    bytes.extend_from_slice(&children);
+    bytes
+}
+
+// Returns the given values as fields.
+pub fn u16s(values: &[u16]) -> Vec<Field> {
+    values.iter().map(|&value| Field::U16(value)).collect()
+}
+
+pub fn tag(name: &[u8; 4]) -> u32 {
+    ((name[0] as u32) << 24) | ((name[1] as u32) << 16) | ((name[2] as u32) << 8) | name[3] as u32
+}
+
+// A format 1 coverage table listing the given glyphs, which must be sorted.
+pub fn coverage(glyph_ids: &[u16]) -> Vec<u8> {
+    let mut fields = u16s(&[1, glyph_ids.len() as u16]);
+    fields.extend(u16s(glyph_ids));
+    table(fields)
+}
+
+// A format 2 coverage table listing the given inclusive ranges of glyphs, which must be sorted.
+pub fn coverage_ranges(ranges: &[(u16, u16)]) -> Vec<u8> {
+    let mut fields = u16s(&[2, ranges.len() as u16]);
+    let mut start_coverage_index = 0;
+    for &(start, end) in ranges {
+        fields.extend(u16s(&[start, end, start_coverage_index]));
+        start_coverage_index += end - start + 1
+    }
+    table(fields)
+}
+
+// A format 1 class definition table assigning the given classes to consecutive glyphs.
+pub fn class_def(start_glyph_id: u16, classes: &[u16]) -> Vec<u8> {
+    let mut fields = u16s(&[1, start_glyph_id, classes.len() as u16]);
+    fields.extend(u16s(classes));
+    table(fields)
+}
+
+// A format 2 class definition table assigning a class to each inclusive range of glyphs, which
+// must be sorted.
+pub fn class_def_ranges(ranges: &[(u16, u16, u16)]) -> Vec<u8> {
+    let mut fields = u16s(&[2, ranges.len() as u16]);
+    for &(start, end, class) in ranges {
+        fields.extend(u16s(&[start, end, class]))
+    }
+    table(fields)
+}
+
+// A lookup of the given type and flags with the given subtables.
+pub fn lookup(lookup_type: u16,
+              lookup_flag: u16,
+              mark_filtering_set: Option<u16>,
+              subtables: Vec<Vec<u8>>)
+              -> Vec<u8> {
+    let mut fields = u16s(&[lookup_type, lookup_flag, subtables.len() as u16]);
+    fields.extend(subtables.into_iter().map(Field::Offset));
+    if let Some(mark_filtering_set) = mark_filtering_set {
+        fields.push(Field::U16(mark_filtering_set))
+    }
+    table(fields)
+}
+
+// An extension subtable wrapping a subtable of the given type.
+pub fn extension(lookup_type: u16, subtable: Vec<u8>) -> Vec<u8> {
+    table(vec![Field::U16(1), Field::U16(lookup_type), Field::Offset32(subtable)])
+}
+
+// Builds a version 1.0 `GSUB` or `GPOS` table.
+//
+// Each script lists the indices of the features that its default language system enables, and
+// each feature lists the indices of its lookups.
+pub fn layout_table(scripts: &[(u32, &[u16])],
+                    features: &[(u32, &[u16])],
+                    lookups: Vec<Vec<u8>>)
+                    -> Vec<u8> {
+    let mut script_list = u16s(&[scripts.len() as u16]);
+    for &(script_tag, feature_indices) in scripts {
+        let mut lang_sys = u16s(&[0, 0xffff, feature_indices.len() as u16]);
+        lang_sys.extend(u16s(feature_indices));
+        let script = table(vec![Field::Offset(table(lang_sys)), Field::U16(0)]);
+        script_list.extend(vec![Field::U32(script_tag), Field::Offset(script)])
+    }
+
+    let mut feature_list = u16s(&[features.len() as u16]);
+    for &(feature_tag, lookup_indices) in features {
+        let mut feature = u16s(&[0, lookup_indices.len() as u16]);
+        feature.extend(u16s(lookup_indices));
+        feature_list.extend(vec![Field::U32(feature_tag), Field::Offset(table(feature))])
+    }
+
+    let mut lookup_list = u16s(&[lookups.len() as u16]);
+    lookup_list.extend(lookups.into_iter().map(Field::Offset));
+
+    table(vec![
+        Field::U16(1),
+        Field::U16(0),
+        Field::Offset(table(script_list)),
+        Field::Offset(table(feature_list)),
+        Field::Offset(table(lookup_list)),
+    ])
+}
+
+#[test]
+fn coverage_index_of_glyph_list() {
+    let coverage = coverage(&[3, 7, 8, 20]);
+    assert_eq!(layout::coverage_index(&coverage, 3).unwrap(), Some(0));
+    assert_eq!(layout::coverage_index(&coverage, 8).unwrap(), Some(2));
+    assert_eq!(layout::coverage_index(&coverage, 20).unwrap(), Some(3));
+    assert_eq!(layout::coverage_index(&coverage, 4).unwrap(), None);
+    assert_eq!(layout::coverage_index(&coverage, 21).unwrap(), None);
+}
+
+#[test]
+fn coverage_index_of_glyph_ranges() {
+    let coverage = coverage_ranges(&[(10, 12), (20, 29)]);
+    assert_eq!(layout::coverage_index(&coverage, 10).unwrap(), Some(0));
+    assert_eq!(layout::coverage_index(&coverage, 12).unwrap(), Some(2));
+    assert_eq!(layout::coverage_index(&coverage, 25).unwrap(), Some(8));
+    assert_eq!(layout::coverage_index(&coverage, 13).unwrap(), None);
+    assert_eq!(layout::coverage_index(&coverage, 9).unwrap(), None);
+}
+
+#[test]
+fn class_of_glyph_in_class_arrays_and_ranges() {
+    let class_def = class_def(5, &[1, 0, 2]);
+    assert_eq!(layout::class_of_glyph(&class_def, 5).unwrap(), 1);
+    assert_eq!(layout::class_of_glyph(&class_def, 6).unwrap(), 0);
+    assert_eq!(layout::class_of_glyph(&class_def, 7).unwrap(), 2);
+    assert_eq!(layout::class_of_glyph(&class_def, 4).unwrap(), 0);
+    assert_eq!(layout::class_of_glyph(&class_def, 8).unwrap(), 0);
+
+    let class_def = class_def_ranges(&[(10, 19, 3), (30, 30, 1)]);
+    assert_eq!(layout::class_of_glyph(&class_def, 15).unwrap(), 3);
+    assert_eq!(layout::class_of_glyph(&class_def, 30).unwrap(), 1);
+    assert_eq!(layout::class_of_glyph(&class_def, 20).unwrap(), 0);
+}
@@ -9,5 +9,15 @@
 // except according to those terms.
 
 mod buffers;
+mod cff;
+mod cmap;
+mod gdef;
+mod gpos;
+mod gsub;
+mod gvar;
+mod hmtx;
+mod instance;
+mod layout;
+mod post;
 mod rect_packer;
 
@@ -0,0 +1,77 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, WriteBytesExt};
+use error::FontError;
+use font::FontTable;
+use tables::post::PostTable;
+
+// Builds a table with the given version and an italic angle of -12.5 degrees, followed by the
+// given version-specific data.
+fn post(version: u32, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.write_u32::<BigEndian>(version).unwrap();
+    bytes.write_i32::<BigEndian>(-12 * 0x10000 - 0x8000).unwrap();
+    bytes.write_i16::<BigEndian>(-100).unwrap();                // underlinePosition
+    bytes.write_i16::<BigEndian>(50).unwrap();                  // underlineThickness
+    bytes.extend_from_slice(&[0; 20]);
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn version_1_names_the_standard_macintosh_glyphs() {
+    let bytes = post(0x10000, &[]);
+    let post = PostTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    assert_eq!(post.glyph_name(3).unwrap(), Some("space"));
+    assert_eq!(post.glyph_id_for_name("A").unwrap(), Some(36));
+    assert_eq!(post.glyph_name(258).unwrap(), None);
+}
+
+#[test]
+fn version_2_names_glyphs_by_index() {
+    // Three glyphs: `.notdef`, one with the first custom name, and `A`.
+    let mut data = vec![];
+    for &name_index in &[3, 0, 258, 36] {
+        data.write_u16::<BigEndian>(name_index).unwrap();
+    }
+    data.extend_from_slice(b"\x07uni0301");
+    let bytes = post(0x20000, &data);
+    let post = PostTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    assert_eq!(post.glyph_name(0).unwrap(), Some(".notdef"));
+    assert_eq!(post.glyph_name(1).unwrap(), Some("uni0301"));
+    assert_eq!(post.glyph_name(2).unwrap(), Some("A"));
+    assert_eq!(post.glyph_name(3).unwrap(), None);
+    assert_eq!(post.glyph_name(4).unwrap(), None);
+    assert_eq!(post.glyph_id_for_name("uni0301").unwrap(), Some(1));
+    assert_eq!(post.glyph_id_for_name("B").unwrap(), None);
+}
+
+#[test]
+fn other_versions_have_a_header_but_no_names() {
+    for &version in &[0x25000, 0x30000, 0x40000] {
+        let bytes = post(version, &[0, 1, 0, 3]);
+        let post = PostTable::new(FontTable {
+            bytes: &bytes,
+        }).unwrap();
+
+        assert_eq!(post.italic_angle, -12.5);
+        assert_eq!((post.underline_position, post.underline_thickness), (-100, 50));
+        assert_eq!(post.glyph_name(0).unwrap(), None);
+        assert_eq!(post.glyph_id_for_name("space").unwrap(), None);
+    }
+}
+
+#[test]
+fn truncated_header() {
+    let bytes = post(0x30000, &[]);
+    assert_eq!(PostTable::new(FontTable {
+        bytes: &bytes[..31],
+    }).err(), Some(FontError::UnexpectedEof));
+}
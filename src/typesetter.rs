@@ -42,6 +42,18 @@ impl Typesetter {
     }
 
     pub fn add_text(&mut self, font: &Font, point_size: f32, string: &str) {
+        self.add_text_with_variations(font, point_size, string, &[])
+    }
+
+    /// Lays out the given text in the instance of the given variable font with the given
+    /// normalized coordinates.
+    ///
+    /// See `Font::for_each_point_with_variations()` for the meaning of the coordinates.
+    pub fn add_text_with_variations(&mut self,
+                                    font: &Font,
+                                    point_size: f32,
+                                    string: &str,
+                                    coords: &[f32]) {
         // TODO(pcwalton): Cache this mapping.
         let mut chars: Vec<char> = string.chars().collect();
         chars.push(' ');
@@ -52,14 +64,17 @@ impl Typesetter {
 
         // All of these values are in pixels.
         let pixels_per_unit = point_size / font.units_per_em() as f32;
-        let space_advance = font.metrics_for_glyph(glyph_mapping.glyph_for(' ' as u32).unwrap())
+        let space_glyph_id = glyph_mapping.glyph_for(' ' as u32).unwrap();
+        let space_advance = font.metrics_for_glyph_with_variations(space_glyph_id, coords)
                                 .unwrap()
                                 .advance_width as f32 * pixels_per_unit;
-        let line_spacing = (font.ascender() as f32 - font.descender() as f32 +
-                            font.line_gap() as f32) * pixels_per_unit;
+        let line_spacing = (font.ascender_with_variations(coords) as f32 -
+                            font.descender_with_variations(coords) as f32 +
+                            font.line_gap_with_variations(coords) as f32) * pixels_per_unit;
 
         for word in string.split_whitespace() {
-            let shaped_glyph_positions = shaper::shape_text(&font, &glyph_mapping, word);
+            let shaped_glyph_positions =
+                shaper::shape_text_with_variations(&font, &glyph_mapping, word, coords);
             let total_advance = pixels_per_unit *
                 shaped_glyph_positions.iter().map(|p| p.advance as f32).sum::<f32>();
             if self.cursor.x + total_advance > self.page_width {
@@ -68,9 +83,10 @@ impl Typesetter {
             }
 
             for glyph_position in &shaped_glyph_positions {
+                // Font units point up, but our Y axis points down.
                 self.glyph_positions.push(GlyphPosition {
-                    x: self.cursor.x,
-                    y: self.cursor.y,
+                    x: self.cursor.x + glyph_position.x_offset as f32 * pixels_per_unit,
+                    y: self.cursor.y - glyph_position.y_offset as f32 * pixels_per_unit,
                     glyph_id: glyph_position.glyph_id,
                 });
                 self.cursor.x += glyph_position.advance as f32 * pixels_per_unit;
@@ -85,11 +101,18 @@ impl Typesetter {
     }
 
     pub fn create_glyph_store(&self, font: &Font) -> Result<GlyphStore, GlyphStoreCreationError> {
+        self.create_glyph_store_with_variations(font, &[])
+    }
+
+    /// Creates a glyph store with the outlines of the laid-out glyphs at the instance of the given
+    /// variable font with the given normalized coordinates.
+    pub fn create_glyph_store_with_variations(&self, font: &Font, coords: &[f32])
+                                              -> Result<GlyphStore, GlyphStoreCreationError> {
         let glyph_ids = self.glyph_positions
                             .iter()
                             .map(|glyph_position| glyph_position.glyph_id)
                             .collect();
-        GlyphStore::from_glyph_ids(glyph_ids, font)
+        GlyphStore::from_glyph_ids(glyph_ids, font, coords)
     }
 
     /// Returns the positions of the glyphs that intersect the given pixel rectangle.
@@ -171,7 +194,7 @@ pub struct GlyphStore {
 }
 
 impl GlyphStore {
-    fn from_glyph_ids(mut glyph_ids: Vec<u16>, font: &Font)
+    fn from_glyph_ids(mut glyph_ids: Vec<u16>, font: &Font, coords: &[f32])
                       -> Result<GlyphStore, GlyphStoreCreationError> {
         glyph_ids.sort();
         glyph_ids.dedup();
@@ -185,7 +208,7 @@ impl GlyphStore {
         let mut glyph_id_to_glyph_index = vec![u16::MAX; last_glyph_id as usize];
         let mut all_glyph_indices = vec![];
         for glyph_id in glyph_ids {
-            let glyph_index = try!(outline_builder.add_glyph(font, glyph_id)
+            let glyph_index = try!(outline_builder.add_glyph_with_variations(font, glyph_id, coords)
                                                   .map_err(GlyphStoreCreationError::FontError));
             glyph_id_to_glyph_index[glyph_id as usize] = glyph_index;
             all_glyph_indices.push(glyph_index);
@@ -209,7 +232,7 @@ impl GlyphStore {
         let mapping = try!(font.glyph_mapping_for_codepoint_ranges(&codepoints.ranges)
                                .map_err(GlyphStoreCreationError::FontError));
         let glyph_ids = mapping.iter().map(|(_, glyph_id)| glyph_id).collect();
-        GlyphStore::from_glyph_ids(glyph_ids, font)
+        GlyphStore::from_glyph_ids(glyph_ids, font, &[])
     }
 
     #[inline]